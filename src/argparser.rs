@@ -1,14 +1,87 @@
+// explicit returns are the style of this module
+#![allow(clippy::needless_return)]
+
+use crate::config;
+use crate::devname;
+use crate::help;
+use crate::output;
+use crate::personality;
+use crate::personality::Personality;
+use crate::readproc;
+use crate::sortformat;
+
+// format flags
+#[allow(non_upper_case_globals)]
+pub const FF_Uc: u32 = 0x01; // -c
+#[allow(non_upper_case_globals)]
+pub const FF_Bs: u32 = 0x02; // s
+#[allow(non_upper_case_globals)]
+pub const FF_Uf: u32 = 0x04; // -f
+#[allow(non_upper_case_globals)]
+pub const FF_Uj: u32 = 0x08; // -j
+#[allow(non_upper_case_globals)]
+pub const FF_Ul: u32 = 0x10; // -l
+
+// format modifiers, applied to any format
+pub const FM_M: u32 = 0x01; // -M, -Z, Z: the security label first
+
+// sort/format option flags, to find conflicts
+#[allow(non_upper_case_globals)]
+pub const SF_U_o: u32 = 0x01; // -o, o, --format
+pub const SF_B_O: u32 = 0x02; // O
+#[allow(non_upper_case_globals)]
+pub const SF_G_sort: u32 = 0x04; // --sort, k
+#[allow(non_upper_case_globals)]
+pub const SF_B_m: u32 = 0x08; // m sorting by memory, in some personalities
+
+// size of the output buffer, used as the "unlimited" screen width
+pub const OUTBUF_SIZE: u32 = 2 * 64 * 1024;
+
+// simple selection flags
+#[allow(non_upper_case_globals)]
+pub const SS_B_x: u32 = 0x01;
+#[allow(non_upper_case_globals)]
+pub const SS_B_g: u32 = 0x02;
+#[allow(non_upper_case_globals)]
+pub const SS_U_d: u32 = 0x04;
+#[allow(non_upper_case_globals)]
+pub const SS_U_a: u32 = 0x08;
+#[allow(non_upper_case_globals)]
+pub const SS_B_a: u32 = 0x10;
+
 #[derive(Debug)]
 pub struct PsParser {
   pub args: Vec<String>,
   pub curargix: usize,
   pub thread_flags: Vec<ThreadFlag>,
   pub select_bits: u32,
-  pub simple_select: bool,
+  pub simple_select: u32,
   pub screen_cols: u32,
+  pub screen_rows: u32,
   pub w_count: u32,
   pub all_process: bool,
   pub selection_list: Vec<SelectionNode>,
+  pub format_list: Vec<sortformat::FormatNode>,
+  pub format_flags: u32,
+  pub sf_flags: u32,
+  pub format_modifiers: u32,
+  pub decode_signals: bool,
+  pub decode_caps: bool,
+  pub sort_list: Vec<sortformat::SortNode>,
+  pub forest_type: ForestType,
+  pub output_mode: OutputMode,
+  pub header_type: HeaderType,
+  pub personality: &'static Personality,
+  pub prefer_bsd_defaults: bool,
+  pub config: config::Config,
+  pub interval: Option<std::time::Duration>, // --interval
+  pub count: Option<u64>,                    // --count
+  pub mem_summary: Option<MemGroup>,         // --mem-summary
+  pub files: Option<i32>,                    // --files
+  pub limits: Option<i32>,                   // --limits
+  pub pidns: Option<u64>,                    // --pidns, a pid or a namespace inode
+  pub sockets: bool,                         // --sockets
+  pub match_every_list: bool, // a process must be in every list of selection_list, like pgrep
 }
 
 impl Default for PsParser {
@@ -18,11 +91,33 @@ impl Default for PsParser {
       curargix: 0,
       thread_flags: vec![],
       select_bits: 0,
-      simple_select: false,
-      screen_cols: 0,
-      w_count: 211,
+      simple_select: 0,
+      screen_cols: 80,
+      screen_rows: 24,
+      w_count: 0,
       all_process: false,
       selection_list: vec![],
+      format_list: vec![],
+      format_flags: 0,
+      sf_flags: 0,
+      format_modifiers: 0,
+      decode_signals: false,
+      decode_caps: false,
+      sort_list: vec![],
+      forest_type: ForestType::NONE,
+      output_mode: OutputMode::TEXT,
+      header_type: HeaderType::SINGLE,
+      personality: personality::search_personality("linux").unwrap(),
+      prefer_bsd_defaults: false,
+      config: config::Config::default(),
+      interval: None,
+      count: None,
+      mem_summary: None,
+      files: None,
+      limits: None,
+      pidns: None,
+      sockets: false,
+      match_every_list: false,
     }
   }
 }
@@ -30,6 +125,15 @@ impl Default for PsParser {
 #[derive(Debug, PartialEq)]
 pub enum SelectionNode {
   PID(PidSelection),
+  TTY(TtySelection),
+  PORT(PortSelection),
+  EUID(UidSelection),
+  RUID(UidSelection),
+  RGID(GidSelection),
+  // these have ids of groups, sessions and parents in PidSelection, like procps
+  PGRP(PidSelection),
+  SESS(PidSelection),
+  PPID(PidSelection),
 }
 
 #[derive(Debug, PartialEq)]
@@ -37,6 +141,28 @@ pub struct PidSelection {
   pub pid: Vec<i32>,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct TtySelection {
+  pub tty: Vec<i32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UidSelection {
+  pub uid: Vec<u32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GidSelection {
+  pub gid: Vec<u32>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PortSelection {
+  pub port: Vec<u16>,
+  // the processes on the ports, found when they are first needed
+  pub pids: std::sync::OnceLock<Vec<i32>>,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum ArgType {
   GNU,
@@ -49,6 +175,36 @@ pub enum ArgType {
 }
 
 #[derive(Debug, PartialEq)]
+pub enum ForestType {
+  NONE,
+  BSD,  // f, --forest: ASCII art
+  UNIX, // -H: indentation
+}
+
+#[derive(Debug, PartialEq)]
+pub enum OutputMode {
+  TEXT,
+  JSON,   // --json: an array of objects
+  NDJSON, // --ndjson: an object per line
+  CSV,    // --csv: RFC 4180
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HeaderType {
+  SINGLE, // default: once at the top
+  NONE,   // h, --no-headers
+  MULTI,  // --headers: once per screen of --rows lines
+}
+
+// how --mem-summary groups the processes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemGroup {
+  USER,
+  COMMAND,
+}
+
+#[derive(Debug, PartialEq)]
+#[allow(non_camel_case_types)]
 pub enum ThreadFlag {
   B_H,
   B_m,
@@ -66,11 +222,43 @@ pub enum ThreadFlag {
 
 impl PsParser {
   pub fn from(args: std::env::Args) -> PsParser {
-    PsParser {
+    let mut parser = PsParser {
       curargix: 0,
       args: args.collect(),
       thread_flags: vec![],
       ..Default::default()
+    };
+    parser.set_screen_size();
+    parser.personality = personality::personality_from_env();
+    parser.config = config::load_config();
+    parser
+  }
+
+  // get the screen size from the terminal or COLUMNS/LINES.
+  // the width is unlimited when the output is not a tty, so piped output keeps everything.
+  pub fn set_screen_size(&mut self) {
+    let mut ws = libc::winsize {
+      ws_row: 0,
+      ws_col: 0,
+      ws_xpixel: 0,
+      ws_ypixel: 0,
+    };
+    let is_tty = unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1;
+    if is_tty
+      && unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) } != -1
+      && ws.ws_col > 0
+      && ws.ws_row > 0
+    {
+      self.screen_cols = ws.ws_col as u32;
+      self.screen_rows = ws.ws_row as u32;
+    } else if !is_tty {
+      self.screen_cols = OUTBUF_SIZE;
+    }
+    if let Some(cols) = env_size("COLUMNS") {
+      self.screen_cols = cols;
+    }
+    if let Some(rows) = env_size("LINES") {
+      self.screen_rows = rows;
     }
   }
 
@@ -79,6 +267,10 @@ impl PsParser {
   pub fn parse(&mut self) -> Result<(), String> {
     match self.arg_parse() {
       Ok(list) => {
+        // --pidns alone selects the whole namespace, other selections narrow it
+        if self.pidns.is_some() && list.is_empty() && self.simple_select == 0 {
+          self.all_process = true;
+        }
         self.selection_list = list;
         Ok(())
       }
@@ -87,10 +279,7 @@ impl PsParser {
   }
 
   pub fn arg_parse(&mut self) -> Result<Vec<SelectionNode>, String> {
-    let option_nodes = match self.parse_all_options() {
-      Ok(list) => list,
-      Err(msg) => return Err(msg),
-    };
+    let option_nodes = self.parse_all_options()?;
     match self.thread_option_check() {
       Ok(_) => log::trace!("thread option updated"),
       Err(msg) => return Err(msg),
@@ -113,24 +302,218 @@ impl PsParser {
     while self.curargix < self.args.len() {
       log::trace!("arg: {}", self.args[self.curargix]);
 
-      match arg_type(&self.args[self.curargix]) {
+      let mut list = match arg_type(&self.args[self.curargix]) {
         ArgType::GNU => {
           log::trace!("GNU type arg: {}", &self.args[self.curargix]);
-          match self.parse_gnu_option() {
-            Ok(mut list) => selection_list.append(&mut list),
-            Err(msg) => return Err(msg),
+          self.parse_gnu_option()?
+        }
+        ArgType::SYSV if self.personality.flags & personality::PER_FORCE_BSD != 0 => {
+          log::trace!("SysV type arg as BSD: {}", &self.args[self.curargix]);
+          self.parse_bsd_option()?
+        }
+        ArgType::BSD if self.personality.flags & personality::PER_NEED_DASH != 0 => {
+          return Err(format!(
+            "the {} personality requires options to start with '-': {}",
+            self.personality.name, self.args[self.curargix]
+          ));
+        }
+        ArgType::SYSV => {
+          log::trace!("SysV type arg: {}", &self.args[self.curargix]);
+          self.parse_sysv_option()?
+        }
+        ArgType::BSD => {
+          log::trace!("BSD type arg: {}", &self.args[self.curargix]);
+          self.parse_bsd_option()?
+        }
+        ArgType::PID => {
+          // BSD style pid list, like `ps 1,2`
+          let arg = self.args[self.curargix].clone();
+          match self.parse_list(&arg, parse_pid) {
+            Some(list) => list,
+            None => return Err(String::from("process ID list syntax error")),
           }
         }
         _ => {
-          unimplemented!();
+          return Err(format!("unsupported option: {}", self.args[self.curargix]));
         }
       };
+      selection_list.append(&mut list);
 
       self.curargix += 1;
     }
     return Ok(selection_list);
   }
 
+  // parse SysV options.
+  // @self.args[@self.curargix] should be start with "-"
+  pub fn parse_sysv_option(&mut self) -> Result<Vec<SelectionNode>, String> {
+    let mut selection_list: Vec<SelectionNode> = vec![];
+    let arg: Vec<char> = self.args[self.curargix].chars().collect();
+    let mut ix = 1;
+    while ix < arg.len() {
+      log::trace!("SysV flag: {}", arg[ix]);
+      match arg[ix] {
+        'A' | 'e' => self.all_process = true,
+        'H' => self.forest_type = ForestType::UNIX,
+        'L' => self.thread_flags.push(ThreadFlag::U_L),
+        'M' | 'Z' => self.format_modifiers |= FM_M,
+        'T' => self.thread_flags.push(ThreadFlag::U_T),
+        'V' => help::do_version(),
+        'a' => self.simple_select |= SS_U_a,
+        'c' => self.format_flags |= FF_Uc,
+        'd' => self.simple_select |= SS_U_d,
+        'f' => self.format_flags |= FF_Uf,
+        'j' => self.format_flags |= FF_Uj,
+        'l' => self.format_flags |= FF_Ul,
+        'm' => self.thread_flags.push(ThreadFlag::U_m),
+        'o' => {
+          let fmt = match self.grab_opt_arg(&arg, ix) {
+            Some(s) => s,
+            None => return Err(String::from("format specification must follow -o")),
+          };
+          self.sf_flags |= SF_U_o;
+          self.format_list.append(&mut self.parse_format(&fmt)?);
+          return Ok(selection_list);
+        }
+        'p' => {
+          let pids = match self.grab_opt_arg(&arg, ix) {
+            Some(s) => s,
+            None => return Err(String::from("list of process IDs must follow -p")),
+          };
+          match self.parse_list(&pids, parse_pid) {
+            Some(mut list) => selection_list.append(&mut list),
+            None => return Err(String::from("process ID list syntax error")),
+          }
+          return Ok(selection_list);
+        }
+        't' => {
+          let ttys = match self.grab_opt_arg(&arg, ix) {
+            Some(s) => s,
+            None => {
+              return Err(String::from(
+                "list of terminals (pty, tty...) must follow -t",
+              ))
+            }
+          };
+          match self.parse_list(&ttys, parse_tty) {
+            Some(mut list) => selection_list.append(&mut list),
+            None => return Err(String::from("terminal list syntax error")),
+          }
+          return Ok(selection_list);
+        }
+        c => return Err(format!("unsupported SysV option: -{}", c)),
+      }
+      ix += 1;
+    }
+    return Ok(selection_list);
+  }
+
+  // parse BSD options.
+  // @self.args[@self.curargix] should be start with an alphabet
+  pub fn parse_bsd_option(&mut self) -> Result<Vec<SelectionNode>, String> {
+    let mut selection_list: Vec<SelectionNode> = vec![];
+    let arg: Vec<char> = self.args[self.curargix].chars().collect();
+    // a dash is allowed in the personalities forcing BSD options
+    let mut ix = if arg[0] == '-' { 1 } else { 0 };
+    self.prefer_bsd_defaults = true;
+    while ix < arg.len() {
+      log::trace!("BSD flag: {}", arg[ix]);
+      match arg[ix] {
+        'H' => self.thread_flags.push(ThreadFlag::B_H),
+        'O' => {
+          // BSD O gives sort keys, like "O-pid"
+          let keys = match self.grab_opt_arg(&arg, ix) {
+            Some(s) => s,
+            None => return Err(String::from("sort specification must follow O")),
+          };
+          self.sf_flags |= SF_B_O;
+          self
+            .sort_list
+            .append(&mut sortformat::parse_sort_opt(&keys)?);
+          return Ok(selection_list);
+        }
+        'L' => {
+          print!("{}", help::format_specifiers());
+          std::process::exit(0);
+        }
+        'V' => help::do_version(),
+        'a' => self.simple_select |= SS_B_a,
+        'f' => self.forest_type = ForestType::BSD,
+        'g' => self.simple_select |= SS_B_g,
+        'h' => {
+          self.header_type = if self.personality.flags & personality::PER_BSD_h != 0 {
+            HeaderType::MULTI
+          } else {
+            HeaderType::NONE
+          }
+        }
+        'k' => {
+          let keys = match self.grab_opt_arg(&arg, ix) {
+            Some(s) => s,
+            None => return Err(String::from("long sort specification must follow 'k'")),
+          };
+          self.sf_flags |= SF_G_sort;
+          self
+            .sort_list
+            .append(&mut sortformat::parse_sort_opt(&keys)?);
+          return Ok(selection_list);
+        }
+        'm' => {
+          if self.personality.flags & (personality::PER_BSD_m | personality::PER_OLD_m)
+            == personality::PER_BSD_m
+          {
+            self.sf_flags |= SF_B_m;
+            self
+              .sort_list
+              .append(&mut sortformat::parse_sort_opt("-rss")?);
+          } else {
+            self.thread_flags.push(ThreadFlag::B_m);
+          }
+        }
+        's' => self.format_flags |= FF_Bs,
+        'Z' => self.format_modifiers |= FM_M,
+        'w' => self.w_count += 1,
+        'x' => self.simple_select |= SS_B_x,
+        'o' => {
+          let fmt = match self.grab_opt_arg(&arg, ix) {
+            Some(s) => s,
+            None => return Err(String::from("format specification must follow o")),
+          };
+          self.sf_flags |= SF_U_o;
+          self.format_list.append(&mut self.parse_format(&fmt)?);
+          return Ok(selection_list);
+        }
+        'p' => {
+          let pids = match self.grab_opt_arg(&arg, ix) {
+            Some(s) => s,
+            None => return Err(String::from("list of process IDs must follow p")),
+          };
+          match self.parse_list(&pids, parse_pid) {
+            Some(mut list) => selection_list.append(&mut list),
+            None => return Err(String::from("process ID list syntax error")),
+          }
+          return Ok(selection_list);
+        }
+        't' => {
+          // with no argument, the terminal of ps itself is used
+          match self.grab_opt_arg(&arg, ix) {
+            Some(ttys) => match self.parse_list(&ttys, parse_tty) {
+              Some(mut list) => selection_list.append(&mut list),
+              None => return Err(String::from("terminal list syntax error")),
+            },
+            None => selection_list.push(SelectionNode::TTY(TtySelection {
+              tty: vec![readproc::our_self().tty],
+            })),
+          }
+          return Ok(selection_list);
+        }
+        c => return Err(format!("unsupported BSD option: {}", c)),
+      }
+      ix += 1;
+    }
+    return Ok(selection_list);
+  }
+
   // parse GNU options.
   // @self.args[@self.curargix] should be start with "--"
   pub fn parse_gnu_option(&mut self) -> Result<Vec<SelectionNode>, String> {
@@ -145,7 +528,7 @@ impl PsParser {
       Some(i) => i,
       None => arg.len(),
     };
-    let argname = &arg[2..std::cmp::min(p0, p1)];
+    let argname = String::from(&arg[2..std::cmp::min(p0, p1)]);
     log::trace!("GNU arg name: {}", argname);
 
     if argname == "pid" {
@@ -160,13 +543,187 @@ impl PsParser {
         None => return Err(String::from("error parse_pid")),
       }
       log::trace!("GNU pid parsed: {:?}", selection_list);
+    } else if argname == "tty" {
+      log::trace!("processing GNU --tty");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => {
+          return Err(String::from(
+            "list of terminals (pty, tty...) must follow --tty",
+          ))
+        }
+      };
+      match self.parse_list(&arg, parse_tty) {
+        Some(mut list) => selection_list.append(&mut list),
+        None => return Err(String::from("terminal list syntax error")),
+      }
+    } else if argname == "port" {
+      log::trace!("processing GNU --port");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("list of ports must follow --port")),
+      };
+      match self.parse_list(&arg, parse_port) {
+        Some(mut list) => selection_list.append(&mut list),
+        None => return Err(String::from("port list syntax error")),
+      }
+    } else if argname == "sockets" {
+      log::trace!("processing GNU --sockets");
+      self.sockets = true;
+    } else if argname == "decode-signals" {
+      log::trace!("processing GNU --decode-signals");
+      self.decode_signals = true;
+    } else if argname == "decode-caps" {
+      log::trace!("processing GNU --decode-caps");
+      self.decode_caps = true;
+    } else if argname == "forest" {
+      log::trace!("processing GNU --forest");
+      self.forest_type = ForestType::BSD;
+    } else if argname == "sort" {
+      log::trace!("processing GNU --sort");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("long sort specification must follow --sort")),
+      };
+      self.sf_flags |= SF_G_sort;
+      self
+        .sort_list
+        .append(&mut sortformat::parse_sort_opt(&arg)?);
+    } else if argname == "cols" || argname == "columns" || argname == "width" {
+      log::trace!("processing GNU --cols");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => {
+          return Err(String::from(
+            "number of columns must follow --cols, --width, or --columns",
+          ))
+        }
+      };
+      self.screen_cols = match arg.parse::<u32>() {
+        Ok(n) if (1..OUTBUF_SIZE).contains(&n) => n,
+        _ => return Err(String::from("invalid screen width")),
+      };
+    } else if argname == "rows" || argname == "lines" {
+      log::trace!("processing GNU --rows");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("number of rows must follow --rows or --lines")),
+      };
+      self.screen_rows = match arg.parse::<u32>() {
+        Ok(n) if (1..OUTBUF_SIZE).contains(&n) => n,
+        _ => return Err(String::from("invalid number of rows")),
+      };
+    } else if argname == "headers" || argname == "heading" {
+      log::trace!("processing GNU --headers");
+      self.header_type = HeaderType::MULTI;
+    } else if argname == "no-headers" || argname == "no-heading" {
+      log::trace!("processing GNU --no-headers");
+      self.header_type = HeaderType::NONE;
+    } else if argname == "help" {
+      log::trace!("processing GNU --help");
+      let arg = self.grab_gnu_arg();
+      help::do_help(arg.as_deref(), 0);
+    } else if argname == "version" {
+      help::do_version();
+    } else if argname == "json" {
+      self.output_mode = OutputMode::JSON;
+    } else if argname == "ndjson" {
+      self.output_mode = OutputMode::NDJSON;
+    } else if argname == "csv" {
+      self.output_mode = OutputMode::CSV;
+    } else if argname == "format" {
+      log::trace!("processing GNU --format");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("format specification must follow --format")),
+      };
+      self.sf_flags |= SF_U_o;
+      self.format_list.append(&mut self.parse_format(&arg)?);
+    } else if argname == "interval" {
+      log::trace!("processing GNU --interval");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("number of seconds must follow --interval")),
+      };
+      self.interval = match arg.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => {
+          Some(std::time::Duration::from_secs_f64(secs))
+        }
+        _ => return Err(String::from("invalid interval")),
+      };
+    } else if argname == "files" {
+      log::trace!("processing GNU --files");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("process ID must follow --files")),
+      };
+      self.files = match arg.parse::<i32>() {
+        Ok(pid) if pid > 0 => Some(pid),
+        _ => return Err(String::from("process ID out of range")),
+      };
+    } else if argname == "limits" {
+      log::trace!("processing GNU --limits");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("process ID must follow --limits")),
+      };
+      self.limits = match arg.parse::<i32>() {
+        Ok(pid) if pid > 0 => Some(pid),
+        _ => return Err(String::from("process ID out of range")),
+      };
+    } else if argname == "pidns" {
+      log::trace!("processing GNU --pidns");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("process ID or namespace must follow --pidns")),
+      };
+      self.pidns = match arg.parse::<u64>() {
+        Ok(n) if n > 0 => Some(n),
+        _ => return Err(String::from("invalid pid namespace")),
+      };
+    } else if argname == "mem-summary" {
+      log::trace!("processing GNU --mem-summary");
+      // the grouping is optional, so it is only taken from "=group"
+      let group = if arg.len() > "--mem-summary".len() {
+        self.grab_gnu_arg()
+      } else {
+        None
+      };
+      self.mem_summary = match group.as_deref() {
+        None | Some("user") => Some(MemGroup::USER),
+        Some("comm") | Some("command") => Some(MemGroup::COMMAND),
+        Some(group) => return Err(format!("unknown grouping for --mem-summary: {}", group)),
+      };
+    } else if argname == "count" {
+      log::trace!("processing GNU --count");
+      let arg = match self.grab_gnu_arg() {
+        Some(s) => s,
+        None => return Err(String::from("number of reports must follow --count")),
+      };
+      self.count = match arg.parse::<u64>() {
+        Ok(n) if n > 0 => Some(n),
+        _ => return Err(String::from("invalid count")),
+      };
     } else {
-      return Err(String::from("unknown gnu long option"));
+      return Err(format!("unknown gnu long option: --{}", argname));
     }
 
     return Ok(selection_list);
   }
 
+  // get the value of a SysV or BSD option at @arg[@ix].
+  // it is either the rest of the current arg or the next arg.
+  pub fn grab_opt_arg(&mut self, arg: &[char], ix: usize) -> Option<String> {
+    if ix + 1 < arg.len() {
+      return Some(arg[ix + 1..].iter().collect());
+    }
+    if self.curargix + 1 >= self.args.len() || self.args[self.curargix + 1].is_empty() {
+      return None;
+    }
+    self.curargix += 1;
+    return Some(self.args[self.curargix].clone());
+  }
+
   // get GNU type arg value
   pub fn grab_gnu_arg(&mut self) -> Option<String> {
     let arg = &self.args[self.curargix];
@@ -195,8 +752,8 @@ impl PsParser {
 
   pub fn parse_list(
     &mut self,
-    argval: &String,
-    f: fn(&Vec<String>) -> Option<Vec<SelectionNode>>,
+    argval: &str,
+    f: fn(&[String]) -> Option<Vec<SelectionNode>>,
   ) -> Option<Vec<SelectionNode>> {
     let mut need_item = true;
     let mut items = 0;
@@ -228,38 +785,255 @@ impl PsParser {
     return f(&vals);
   }
 
-  // XXX
+  // translate thread options into how to show threads
   pub fn thread_option_check(&mut self) -> Result<(), String> {
-    if self.thread_flags.len() == 0 {
+    if self.thread_flags.is_empty() {
       self.thread_flags.push(ThreadFlag::SHOW_PROC);
       return Ok(());
     }
-    return Err(String::from("thread option: not imp"));
+    if self.thread_flags.contains(&ThreadFlag::B_H) {
+      // H: threads as if they were processes
+      self.thread_flags.push(ThreadFlag::SHOW_PROC);
+      self.thread_flags.push(ThreadFlag::LOOSE_TASKS);
+    } else if self.thread_flags.contains(&ThreadFlag::B_m)
+      || self.thread_flags.contains(&ThreadFlag::U_m)
+    {
+      // m, -m: threads after processes
+      self.thread_flags.push(ThreadFlag::SHOW_PROC);
+      self.thread_flags.push(ThreadFlag::SHOW_TASK);
+    } else {
+      // -L, -T: threads only
+      self.thread_flags.push(ThreadFlag::SHOW_TASK);
+    }
+    return Ok(());
+  }
+
+  // the format of -f, -j, -l or a combination of them
+  #[allow(non_upper_case_globals)]
+  fn sysv_format(&self) -> Option<&'static str> {
+    let fmt = match self.format_flags & (FF_Uf | FF_Uj | FF_Ul) {
+      FF_Uf => sortformat::FFMT,
+      FF_Uj => sortformat::JFMT,
+      FF_Ul => sortformat::LFMT,
+      v if v == FF_Uf | FF_Uj => sortformat::FJFMT,
+      v if v == FF_Uf | FF_Ul => sortformat::FLFMT,
+      v if v == FF_Uj | FF_Ul => sortformat::JLFMT,
+      v if v != 0 => sortformat::FJLFMT,
+      _ => return None,
+    };
+    return Some(fmt);
+  }
+
+  // -L and -T add a column of thread ID after PPID, or PID without it,
+  // to a default format
+  fn add_thread_column(&mut self) -> Result<(), String> {
+    let tid = if self.thread_flags.contains(&ThreadFlag::U_L) {
+      "lwp"
+    } else if self.thread_flags.contains(&ThreadFlag::U_T) {
+      "spid"
+    } else {
+      return Ok(());
+    };
+    if self.format_list.iter().any(|n| n.spec == tid) {
+      return Ok(());
+    }
+    let ix = ["ppid", "pid"]
+      .iter()
+      .find_map(|spec| self.format_list.iter().position(|n| n.spec == *spec))
+      .map_or(0, |ix| ix + 1);
+    let mut node = sortformat::parse_formatted(tid)?;
+    self.format_list.insert(ix, node.remove(0));
+    return Ok(());
   }
 
-  // XXX
   pub fn process_sf_options(&mut self) -> Result<(), String> {
-    Ok(())
-    //if self.simple_select {
-    //  self.select_bits = 0xaa00;
-    //  return Ok(());
-    //}
-    //return Err(String::from("thread option: not imp"));
+    self.select_bits_setup()?;
+    if self.format_list.is_empty() {
+      let fmt = if self.format_flags & FF_Bs != 0 {
+        sortformat::SFMT
+      } else if self.format_flags & FF_Uc != 0 {
+        sortformat::CFMT
+      } else if let Some(fmt) = self.sysv_format() {
+        fmt
+      } else if self.prefer_bsd_defaults || self.personality.prefer_bsd_defaults {
+        sortformat::BSD_DFMT
+      } else {
+        self.personality.dfmt.unwrap_or(sortformat::DFMT)
+      };
+      self.format_list = sortformat::parse_formatted(fmt)?;
+      self.add_thread_column()?;
+    }
+    // some personalities sort by default
+    if let Some(keys) = self.personality.sort {
+      if self.sort_list.is_empty() && self.forest_type == ForestType::NONE {
+        self.sort_list = sortformat::parse_sort_opt(keys)?;
+      }
+    }
+    return Ok(());
+  }
+
+  // prepare select_bits used by table_accept()
+  #[allow(non_upper_case_globals)]
+  pub fn select_bits_setup(&mut self) -> Result<(), String> {
+    if self.simple_select == 0 {
+      // the STANDARD selection: same euid and same tty
+      self.select_bits = 0xaa00;
+      return Ok(());
+    }
+    // 'g' is a NOP except when SysV -a or -d is specified
+    let switch_val = if self.simple_select & (SS_U_a | SS_U_d) == 0 {
+      self.simple_select | SS_B_g
+    } else {
+      self.simple_select
+    };
+    self.select_bits = match switch_val {
+      // UNIX options
+      v if v == SS_U_a | SS_U_d => 0x3f3f,
+      SS_U_a => 0x0303,
+      SS_U_d => 0x3333,
+      // BSD options
+      SS_B_g => 0x0a0a,
+      v if v == SS_B_g | SS_B_a => 0x0f0f,
+      v if v == SS_B_g | SS_B_x => 0xaaaa,
+      v if v == SS_B_g | SS_B_x | SS_B_a => {
+        // convert to -e
+        self.all_process = true;
+        self.simple_select = 0;
+        0
+      }
+      _ => return Err(String::from("process selection options conflict")),
+    };
+    return Ok(());
+  }
+
+  // parse a format list given by the user, which can contain aliases of the config
+  pub fn parse_format(&self, s: &str) -> Result<Vec<sortformat::FormatNode>, String> {
+    sortformat::parse_formatted(&self.config.expand_aliases(s))
+  }
+
+  // replace the built-in defaults by @ps_format (PS_FORMAT) and the config file.
+  // explicit format and sort options always win.
+  pub fn apply_defaults(&mut self, ps_format: Option<&str>) -> Result<(), String> {
+    if self.sf_flags & SF_U_o == 0 && self.format_flags == 0 {
+      let fmt = match ps_format {
+        Some(s) if !s.trim().is_empty() => Some(String::from(s.trim())),
+        _ => self.config.format.clone(),
+      };
+      if let Some(fmt) = fmt {
+        self.format_list = self.parse_format(&fmt)?;
+        self.add_thread_column()?;
+      }
+    }
+    if self.sf_flags & (SF_B_O | SF_G_sort | SF_B_m) == 0 && self.forest_type == ForestType::NONE {
+      if let Some(keys) = &self.config.sort {
+        self.sort_list = sortformat::parse_sort_opt(keys)?;
+      }
+    }
+    if self.format_modifiers & FM_M != 0 && !self.format_list.iter().any(|n| n.spec == "label") {
+      let mut label = sortformat::parse_formatted("label")?;
+      self.format_list.insert(0, label.remove(0));
+    }
+    if self.decode_caps {
+      for node in self.format_list.iter_mut() {
+        if let Some(pr) = output::decoded_caps_pr(node.spec) {
+          node.pr = pr;
+        }
+      }
+    }
+    if self.decode_signals {
+      for node in self.format_list.iter_mut() {
+        if let Some(pr) = output::decoded_signals_pr(node.spec) {
+          node.pr = pr;
+        }
+      }
+    }
+    return Ok(());
   }
 
+  // find options which can not be used together
+  pub fn arg_check_conflicts(&self) -> Result<(), String> {
+    let has = |flag: ThreadFlag| self.thread_flags.contains(&flag);
+    if has(ThreadFlag::B_H) && (has(ThreadFlag::B_m) || has(ThreadFlag::U_m)) {
+      return Err(String::from(
+        "thread flags conflict; can't use H with m or -m",
+      ));
+    }
+    if has(ThreadFlag::B_m) && has(ThreadFlag::U_m) {
+      return Err(String::from(
+        "thread flags conflict; can't use both m and -m",
+      ));
+    }
+    if has(ThreadFlag::U_L) && has(ThreadFlag::U_T) {
+      return Err(String::from(
+        "thread flags conflict; can't use both -L and -T",
+      ));
+    }
+    if !self.sort_list.is_empty() && self.forest_type != ForestType::NONE {
+      return Err(String::from(
+        "conflicting sort and forest options; can't use --sort or k with --forest, f or -H",
+      ));
+    }
+    if self.sf_flags & SF_U_o != 0 && self.format_flags != 0 {
+      return Err(String::from(
+        "conflicting format options; can't use -o with a preset format like -f, -l, -j, -c or s",
+      ));
+    }
+    if self.sf_flags & SF_B_O != 0 && self.sf_flags & SF_G_sort != 0 {
+      return Err(String::from(
+        "conflicting sort options; can't use O with --sort or k",
+      ));
+    }
+    let reports = [
+      self.files.is_some(),
+      self.limits.is_some(),
+      self.sockets,
+      self.mem_summary.is_some(),
+      self.interval.is_some(),
+    ];
+    if reports.iter().filter(|r| **r).count() > 1 {
+      return Err(String::from(
+        "conflicting report options; can't combine --files, --limits, --sockets, --mem-summary or --interval",
+      ));
+    }
+    if self.pidns.is_some() && (self.files.is_some() || self.limits.is_some()) {
+      return Err(String::from(
+        "conflicting report options; can't use --pidns with --files or --limits",
+      ));
+    }
+    // every sample is a report, and reports in a row are not one JSON document
+    if self.interval.is_some() && self.output_mode == OutputMode::JSON {
+      return Err(String::from(
+        "conflicting output options; can't use --json with --interval, use --ndjson",
+      ));
+    }
+    if self.count.is_some() && self.interval.is_none() {
+      return Err(String::from("--count needs --interval"));
+    }
+    return Ok(());
+  }
+
+  // w makes the screen at least 132 columns, ww makes it unlimited
   pub fn choose_dimensions(&mut self) -> Result<(), String> {
-    if self.w_count != 1 && (self.screen_cols < 132) {
+    if self.w_count != 0 && (self.screen_cols < 132) {
       self.screen_cols = 132;
     }
     if self.w_count > 1 {
-      self.screen_cols = 2 * 64 * 1024 * 1;
+      self.screen_cols = OUTBUF_SIZE;
     }
     return Ok(());
   }
 }
 
-pub fn arg_type(arg: &String) -> ArgType {
-  let c0 = arg.chars().nth(0);
+// a positive size in the environment variable @name
+fn env_size(name: &str) -> Option<u32> {
+  match std::env::var(name).ok()?.parse::<u32>() {
+    Ok(n) if (1..OUTBUF_SIZE).contains(&n) => Some(n),
+    _ => None,
+  }
+}
+
+pub fn arg_type(arg: &str) -> ArgType {
+  let c0 = arg.chars().next();
   match c0 {
     None => ArgType::FAIL,
     Some(c0) => match c0 {
@@ -295,7 +1069,7 @@ pub fn arg_type(arg: &String) -> ArgType {
   }
 }
 
-pub fn parse_pid(vals: &Vec<String>) -> Option<Vec<SelectionNode>> {
+pub fn parse_pid(vals: &[String]) -> Option<Vec<SelectionNode>> {
   let mut selection_list: Vec<SelectionNode> = vec![];
   for val in vals {
     let n = match val.parse::<i32>() {
@@ -308,8 +1082,46 @@ pub fn parse_pid(vals: &Vec<String>) -> Option<Vec<SelectionNode>> {
   return Some(selection_list);
 }
 
+// all the ports go in one node, so /proc is scanned only once
+pub fn parse_port(vals: &[String]) -> Option<Vec<SelectionNode>> {
+  let mut ports = vec![];
+  for val in vals {
+    match val.parse::<u16>() {
+      Ok(port) if port > 0 => ports.push(port),
+      _ => return None,
+    }
+  }
+  return Some(vec![SelectionNode::PORT(PortSelection {
+    port: ports,
+    pids: std::sync::OnceLock::new(),
+  })]);
+}
+
+pub fn parse_tty(vals: &[String]) -> Option<Vec<SelectionNode>> {
+  let mut selection_list: Vec<SelectionNode> = vec![];
+  for val in vals {
+    let dev = match devname::tty_to_dev(val) {
+      Some(dev) => dev,
+      None => {
+        log::trace!("unknown tty: {}", val);
+        return None;
+      }
+    };
+    selection_list.push(SelectionNode::TTY(TtySelection { tty: vec![dev] }));
+  }
+  return Some(selection_list);
+}
+
 #[cfg(test)]
 mod tests {
+  fn parse(args: &[&str]) -> Result<super::PsParser, String> {
+    let mut parser = super::PsParser {
+      args: args.iter().map(|s| String::from(*s)).collect(),
+      ..Default::default()
+    };
+    parser.parse().map(|_| parser)
+  }
+
   #[test]
   fn parser_gnu_pid() {
     let mut parser0 = super::PsParser {
@@ -357,4 +1169,361 @@ mod tests {
     parser3.parse().unwrap();
     assert_eq!(parser3.selection_list, b1);
   }
+
+  #[test]
+  fn bsd_pid_list() {
+    let pids = |parser: super::PsParser| -> Vec<i32> {
+      parser
+        .selection_list
+        .iter()
+        .flat_map(|node| match node {
+          super::SelectionNode::PID(sel) => sel.pid.clone(),
+          _ => vec![],
+        })
+        .collect()
+    };
+    assert_eq!(pids(parse(&["ps", "p", "1,2"]).unwrap()), vec![1, 2]);
+    assert_eq!(pids(parse(&["ps", "p1"]).unwrap()), vec![1]);
+    assert_eq!(pids(parse(&["ps", "axp", "3"]).unwrap()), vec![3]);
+    assert!(parse(&["ps", "p"]).unwrap_err().contains("must follow p"));
+  }
+
+  #[test]
+  fn screen_dimensions() {
+    let parser = parse(&["ps", "--cols", "100", "--rows=50"]).unwrap();
+    assert_eq!((parser.screen_cols, parser.screen_rows), (100, 50));
+    assert_eq!(parse(&["ps", "--width=90"]).unwrap().screen_cols, 90);
+    assert_eq!(parse(&["ps", "w"]).unwrap().screen_cols, 132);
+    assert_eq!(parse(&["ps", "--cols=200", "w"]).unwrap().screen_cols, 200);
+    assert_eq!(
+      parse(&["ps", "ww"]).unwrap().screen_cols,
+      super::OUTBUF_SIZE
+    );
+    assert_eq!(
+      parse(&["ps", "w", "w"]).unwrap().screen_cols,
+      super::OUTBUF_SIZE
+    );
+    assert!(parse(&["ps", "--cols=0"]).is_err());
+    assert!(parse(&["ps", "--rows"]).is_err());
+  }
+
+  #[test]
+  fn interval_and_count() {
+    let parser = parse(&["ps", "--interval", "0.5", "--count=30"]).unwrap();
+    assert_eq!(parser.interval, Some(std::time::Duration::from_millis(500)));
+    assert_eq!(parser.count, Some(30));
+    assert!(parse(&["ps", "--interval=0"]).is_err());
+    assert!(parse(&["ps", "--interval=x"]).is_err());
+    assert!(parse(&["ps", "--count=0"]).is_err());
+    assert!(parse(&["ps", "--count"]).is_err());
+  }
+
+  #[test]
+  fn files_pid() {
+    assert_eq!(parse(&["ps"]).unwrap().files, None);
+    assert_eq!(parse(&["ps", "--files", "42"]).unwrap().files, Some(42));
+    assert_eq!(parse(&["ps", "--files=1"]).unwrap().files, Some(1));
+    assert!(parse(&["ps", "--files"]).is_err());
+    assert!(parse(&["ps", "--files=0"]).is_err());
+    assert!(parse(&["ps", "--files=x"]).is_err());
+  }
+
+  #[test]
+  fn pidns_target() {
+    assert_eq!(parse(&["ps"]).unwrap().pidns, None);
+    assert_eq!(parse(&["ps", "--pidns", "812"]).unwrap().pidns, Some(812));
+    assert_eq!(
+      parse(&["ps", "--pidns=4026532301"]).unwrap().pidns,
+      Some(4026532301)
+    );
+    // alone it selects the whole namespace, other selections narrow it
+    assert!(parse(&["ps", "--pidns", "812"]).unwrap().all_process);
+    assert!(
+      !parse(&["ps", "--pidns", "812", "-p", "1"])
+        .unwrap()
+        .all_process
+    );
+    assert!(!parse(&["ps", "--pidns", "812", "x"]).unwrap().all_process);
+    assert!(parse(&["ps", "--pidns"]).is_err());
+    assert!(parse(&["ps", "--pidns=0"]).is_err());
+  }
+
+  #[test]
+  fn limits_pid() {
+    assert_eq!(parse(&["ps"]).unwrap().limits, None);
+    assert_eq!(parse(&["ps", "--limits", "42"]).unwrap().limits, Some(42));
+    assert!(parse(&["ps", "--limits"]).is_err());
+    assert!(parse(&["ps", "--limits=-1"]).is_err());
+  }
+
+  #[test]
+  fn port_selection() {
+    let parser = parse(&["ps", "--port", "80,8080", "--sockets"]).unwrap();
+    assert!(parser.sockets);
+    match &parser.selection_list[..] {
+      [super::SelectionNode::PORT(sel)] => assert_eq!(sel.port, vec![80, 8080]),
+      list => panic!("unexpected selection: {:?}", list),
+    }
+    assert!(!parse(&["ps"]).unwrap().sockets);
+    assert!(parse(&["ps", "--port=0"]).is_err());
+    assert!(parse(&["ps", "--port=70000"]).is_err());
+    assert!(parse(&["ps", "--port"]).is_err());
+  }
+
+  #[test]
+  fn mem_summary_grouping() {
+    use super::MemGroup;
+    assert_eq!(parse(&["ps", "-e"]).unwrap().mem_summary, None);
+    assert_eq!(
+      parse(&["ps", "--mem-summary", "-e"]).unwrap().mem_summary,
+      Some(MemGroup::USER)
+    );
+    assert_eq!(
+      parse(&["ps", "--mem-summary=comm"]).unwrap().mem_summary,
+      Some(MemGroup::COMMAND)
+    );
+    assert!(parse(&["ps", "--mem-summary=tty"]).is_err());
+  }
+
+  #[test]
+  fn conflicting_options() {
+    let check = |args: &[&str]| parse(args).unwrap().arg_check_conflicts();
+    assert!(check(&["ps", "-e"]).is_ok());
+    assert!(check(&["ps", "-em"]).is_ok());
+    assert!(check(&["ps", "-eL", "-o", "pid,lwp"]).is_ok());
+    assert!(check(&["ps", "ax", "--sort", "pid"]).is_ok());
+    assert!(check(&["ps", "axf"]).is_ok());
+    assert!(check(&["ps", "ax", "O", "-pid"]).is_ok());
+    assert!(check(&["ps", "Hm"]).unwrap_err().contains("H with m"));
+    assert!(check(&["ps", "H", "-m"]).unwrap_err().contains("H with m"));
+    assert!(check(&["ps", "m", "-m"])
+      .unwrap_err()
+      .contains("both m and -m"));
+    assert!(check(&["ps", "-LT"])
+      .unwrap_err()
+      .contains("both -L and -T"));
+    assert!(check(&["ps", "axf", "k", "pid"])
+      .unwrap_err()
+      .contains("sort and forest"));
+    assert!(check(&["ps", "-eH", "--sort=-pid"])
+      .unwrap_err()
+      .contains("sort and forest"));
+    assert_eq!(
+      check(&["ps", "-f", "-o", "pid"]).unwrap_err(),
+      "conflicting format options; can't use -o with a preset format like -f, -l, -j, -c or s"
+    );
+    assert!(check(&["ps", "-l", "-o", "pid"])
+      .unwrap_err()
+      .contains("format options"));
+    assert!(check(&["ps", "-c", "-o", "pid"])
+      .unwrap_err()
+      .contains("format options"));
+    assert!(check(&["ps", "s", "--format=pid"])
+      .unwrap_err()
+      .contains("format options"));
+    assert!(check(&["ps", "O", "pid", "--sort=ppid"])
+      .unwrap_err()
+      .contains("sort options"));
+    assert!(check(&["ps", "--interval=1", "--count", "3"]).is_ok());
+    // one report at a time, --pidns selects for the process reports
+    assert!(check(&["ps", "--pidns", "1", "--interval", "1"]).is_ok());
+    assert!(check(&["ps", "--pidns", "1", "--sockets"]).is_ok());
+    assert!(check(&["ps", "--pidns", "1", "--mem-summary"]).is_ok());
+    assert!(check(&["ps", "--sockets", "--interval", "1"])
+      .unwrap_err()
+      .contains("can't combine"));
+    assert!(check(&["ps", "--files", "1", "--limits", "1"])
+      .unwrap_err()
+      .contains("can't combine"));
+    assert!(check(&["ps", "--mem-summary", "--sockets"])
+      .unwrap_err()
+      .contains("can't combine"));
+    assert!(check(&["ps", "--pidns", "1", "--files", "1"])
+      .unwrap_err()
+      .contains("--pidns with --files"));
+    assert!(check(&["ps", "--ndjson", "--interval", "1"]).is_ok());
+    assert!(check(&["ps", "--json", "--interval", "1"])
+      .unwrap_err()
+      .contains("use --ndjson"));
+    assert!(check(&["ps", "--count", "3"])
+      .unwrap_err()
+      .contains("needs --interval"));
+  }
+
+  #[test]
+  fn sysv_formats() {
+    let specs = |args: &[&str]| -> Vec<&str> {
+      let parser = parse(args).unwrap();
+      parser.format_list.iter().map(|n| n.spec).collect()
+    };
+    assert_eq!(
+      specs(&["ps", "-f"]),
+      vec!["uid_hack", "pid", "ppid", "c", "stime", "tname", "time", "cmd"]
+    );
+    assert_eq!(
+      specs(&["ps", "-j"]),
+      vec!["pid", "pgid", "sid", "tname", "time", "ucmd"]
+    );
+    assert_eq!(specs(&["ps", "-l"])[..3], ["f", "s", "uid"]);
+    // the options are combined, in any order
+    assert_eq!(specs(&["ps", "-lf"]), specs(&["ps", "-f", "-l"]));
+    assert_eq!(specs(&["ps", "-lf"])[2], "uid_hack");
+    assert_eq!(specs(&["ps", "-jl"])[4..6], ["pgid", "sid"]);
+    assert!(specs(&["ps", "-fjl"]).contains(&"stime"));
+    // the thread column goes after PPID
+    assert_eq!(
+      specs(&["ps", "-fL"])[..4],
+      ["uid_hack", "pid", "ppid", "lwp"]
+    );
+    assert_eq!(specs(&["ps", "-eT"])[..2], ["pid", "spid"]);
+  }
+
+  #[test]
+  fn personalities() {
+    let parse = |per: &str, args: &[&str]| {
+      let mut parser = super::PsParser {
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        personality: crate::personality::search_personality(per).unwrap(),
+        ..Default::default()
+      };
+      parser.parse().map(|_| parser)
+    };
+    let names = |parser: &super::PsParser| -> Vec<String> {
+      parser.format_list.iter().map(|n| n.name.clone()).collect()
+    };
+
+    // linux: BSD options prefer the BSD format
+    let parser = parse("linux", &["ps", "-e"]).unwrap();
+    assert_eq!(names(&parser), vec!["PID", "TTY", "TIME", "CMD"]);
+    let parser = parse("linux", &["ps", "ax"]).unwrap();
+    assert_eq!(
+      names(&parser),
+      vec!["PID", "TTY", "STAT", "TIME", "COMMAND"]
+    );
+    assert!(parse("linux", &["ps", "m"])
+      .unwrap()
+      .thread_flags
+      .contains(&super::ThreadFlag::B_m));
+    assert_eq!(
+      parse("linux", &["ps", "h"]).unwrap().header_type,
+      super::HeaderType::NONE
+    );
+
+    // bsd: "-ax" is "ax", sorted by terminal, m sorts by memory, h repeats headers
+    let parser = parse("bsd", &["ps", "-ax"]).unwrap();
+    assert!(parser.all_process);
+    assert_eq!(parser.sort_list.len(), 2);
+    assert_eq!(
+      names(&parser),
+      vec!["PID", "TTY", "STAT", "TIME", "COMMAND"]
+    );
+    let parser = parse("bsd", &["ps", "m"]).unwrap();
+    assert_ne!(parser.sf_flags & super::SF_B_m, 0);
+    assert!(!parser.thread_flags.contains(&super::ThreadFlag::B_m));
+    assert_eq!(
+      parse("bsd", &["ps", "h"]).unwrap().header_type,
+      super::HeaderType::MULTI
+    );
+    assert!(parse("bsd", &["ps", "f"]).unwrap().sort_list.is_empty());
+
+    // old, from I_WANT_A_BROKEN_PS: "-aux" is "aux", but m shows threads
+    let parser = parse("old", &["ps", "-m"]).unwrap();
+    assert!(parser.thread_flags.contains(&super::ThreadFlag::B_m));
+    assert!(parse("old", &["ps", "-e"]).is_err());
+
+    // sysv, hpux: CMD is the command name
+    for per in ["sysv", "hpux", "aix"] {
+      let parser = parse(per, &["ps", "-e"]).unwrap();
+      assert_eq!(names(&parser), vec!["PID", "TTY", "TIME", "CMD"]);
+      assert_eq!(parser.format_list[3].spec, "ucmd");
+    }
+    assert_eq!(
+      parse("sysv", &["ps", "-e", "h"]).unwrap().header_type,
+      super::HeaderType::MULTI
+    );
+
+    // posix, sun, unix95: a dash is required
+    for per in ["posix", "sun", "unix95"] {
+      let parser = parse(per, &["ps", "-e"]).unwrap();
+      assert_eq!(parser.format_list[3].spec, "ucmd");
+      assert!(parse(per, &["ps", "ax"])
+        .unwrap_err()
+        .contains("personality"));
+      assert!(parse(per, &["ps", "1"]).is_ok());
+    }
+  }
+
+  #[test]
+  fn default_format_and_sort() {
+    let parse = |args: &[&str], ps_format: Option<&str>| {
+      let mut parser = super::PsParser {
+        args: args.iter().map(|s| String::from(*s)).collect(),
+        config: crate::config::parse_config(
+          "format = pid,comm\nsort = -pid\n[aliases]\nmem = pid,rss,vsz\n",
+        ),
+        ..Default::default()
+      };
+      parser.parse().unwrap();
+      parser.apply_defaults(ps_format).map(|_| parser)
+    };
+    let specs = |parser: &super::PsParser| -> Vec<&str> {
+      parser.format_list.iter().map(|n| n.spec).collect()
+    };
+
+    // the config file is used without options
+    let parser = parse(&["ps"], None).unwrap();
+    assert_eq!(specs(&parser), vec!["pid", "comm"]);
+    assert!(parser.sort_list[0].reverse);
+    // PS_FORMAT is used before the config file, and can use aliases
+    let parser = parse(&["ps"], Some("mem,tty")).unwrap();
+    assert_eq!(specs(&parser), vec!["pid", "rss", "vsz", "tty"]);
+    // explicit options are used before both
+    let parser = parse(&["ps", "-o", "mem"], Some("tty")).unwrap();
+    assert_eq!(specs(&parser), vec!["pid", "rss", "vsz"]);
+    let parser = parse(&["ps", "-c"], Some("tty")).unwrap();
+    assert_eq!(parser.format_list[1].spec, "class");
+    // -L and -T still add their column to PS_FORMAT and the config file
+    let parser = parse(&["ps", "-L"], Some("pid,comm")).unwrap();
+    assert_eq!(specs(&parser), vec!["pid", "lwp", "comm"]);
+    let parser = parse(&["ps", "-T"], None).unwrap();
+    assert_eq!(specs(&parser), vec!["pid", "spid", "comm"]);
+    let parser = parse(&["ps", "-L"], Some("lwp,comm")).unwrap();
+    assert_eq!(specs(&parser), vec!["lwp", "comm"]);
+    let parser = parse(&["ps", "--sort", "ppid"], None).unwrap();
+    assert!(!parser.sort_list[0].reverse);
+    // no default sort with forest
+    assert!(parse(&["ps", "f"], None).unwrap().sort_list.is_empty());
+    assert!(parse(&["ps"], Some("nosuchcolumn")).is_err());
+    // -Z, -M and Z put the label first of any format
+    let parser = parse(&["ps", "-Z"], None).unwrap();
+    assert_eq!(specs(&parser), vec!["label", "pid", "comm"]);
+    let parser = parse(&["ps", "Z", "-o", "pid"], None).unwrap();
+    assert_eq!(specs(&parser), vec!["label", "pid"]);
+    let parser = parse(&["ps", "-M", "-o", "pid,label"], None).unwrap();
+    assert_eq!(specs(&parser), vec!["pid", "label"]);
+    // --decode-caps changes the printers of the capability columns only
+    let p = crate::readproc::PROCT {
+      cap_eff: 1 << 12,
+      ..Default::default()
+    };
+    let parser = parse(&["ps", "--decode-caps", "-o", "pid,capeff"], None).unwrap();
+    assert_eq!((parser.format_list[0].pr)(&p).to_string(), "0");
+    assert_eq!((parser.format_list[1].pr)(&p).to_string(), "cap_net_admin");
+    let parser = parse(&["ps", "-o", "capeff"], None).unwrap();
+    assert_eq!(
+      (parser.format_list[0].pr)(&p).to_string(),
+      "0000000000001000"
+    );
+    // and --decode-signals the printers of the signal columns
+    let p = crate::readproc::PROCT {
+      blocked: 1 << 1,
+      ..Default::default()
+    };
+    let parser = parse(&["ps", "--decode-signals", "-o", "pid,blocked"], None).unwrap();
+    assert_eq!((parser.format_list[0].pr)(&p).to_string(), "0");
+    assert_eq!(
+      (parser.format_list[1].pr)(&p).to_string(),
+      crate::sig::mask_to_names(1 << 1)
+    );
+  }
 }
@@ -0,0 +1,14 @@
+use rustyps::pgrep;
+use simple_logger::SimpleLogger;
+
+fn main() {
+  SimpleLogger::new()
+    .with_level(log::LevelFilter::Warn)
+    .init()
+    .unwrap();
+  // exit quietly when the reader of a pipe goes away
+  unsafe {
+    libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+  }
+  std::process::exit(pgrep::run(pgrep::Mode::PKILL, std::env::args().collect()));
+}
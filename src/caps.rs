@@ -0,0 +1,97 @@
+// capability names, indexed by the capability number, from linux/capability.h
+const CAPLIST: [&str; 41] = [
+  "chown",
+  "dac_override",
+  "dac_read_search",
+  "fowner",
+  "fsetid",
+  "kill",
+  "setgid",
+  "setuid",
+  "setpcap",
+  "linux_immutable",
+  "net_bind_service",
+  "net_broadcast",
+  "net_admin",
+  "net_raw",
+  "ipc_lock",
+  "ipc_owner",
+  "sys_module",
+  "sys_rawio",
+  "sys_chroot",
+  "sys_ptrace",
+  "sys_pacct",
+  "sys_admin",
+  "sys_boot",
+  "sys_nice",
+  "sys_resource",
+  "sys_time",
+  "sys_tty_config",
+  "mknod",
+  "lease",
+  "audit_write",
+  "audit_control",
+  "setfcap",
+  "mac_override",
+  "mac_admin",
+  "syslog",
+  "wake_alarm",
+  "block_suspend",
+  "audit_read",
+  "perfmon",
+  "bpf",
+  "checkpoint_restore",
+];
+
+// name of capability @cap like "cap_net_admin", or "cap_42" if it is newer than this list
+pub fn cap_to_name(cap: u32) -> String {
+  match CAPLIST.get(cap as usize) {
+    Some(name) => format!("cap_{}", name),
+    None => format!("cap_{}", cap),
+  }
+}
+
+// decode a capability set of /proc/<pid>/status into names like
+// "cap_net_admin,cap_sys_ptrace". "-" for an empty set, and "full"
+// if every capability of the list is in it.
+pub fn mask_to_names(mask: u64) -> String {
+  let known = (1u64 << CAPLIST.len()) - 1;
+  if mask == 0 {
+    return String::from("-");
+  }
+  if mask & known == known {
+    return String::from("full");
+  }
+  let names: Vec<String> = (0..64)
+    .filter(|cap| mask & (1 << cap) != 0)
+    .map(cap_to_name)
+    .collect();
+  names.join(",")
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn cap_names() {
+    assert_eq!(super::cap_to_name(0), "cap_chown");
+    assert_eq!(super::cap_to_name(12), "cap_net_admin");
+    assert_eq!(super::cap_to_name(40), "cap_checkpoint_restore");
+    assert_eq!(super::cap_to_name(41), "cap_41");
+  }
+
+  #[test]
+  fn decode_mask() {
+    assert_eq!(super::mask_to_names(0), "-");
+    assert_eq!(
+      super::mask_to_names(1 << 12 | 1 << 19),
+      "cap_net_admin,cap_sys_ptrace"
+    );
+    assert_eq!(super::mask_to_names(0x000001ffffffffff), "full");
+    // the default bounding set of docker
+    assert_eq!(
+      super::mask_to_names(0x00000000a80425fb),
+      "cap_chown,cap_dac_override,cap_fowner,cap_fsetid,cap_kill,cap_setgid,cap_setuid,\
+cap_setpcap,cap_net_bind_service,cap_net_raw,cap_sys_chroot,cap_mknod,cap_audit_write,cap_setfcap"
+    );
+  }
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+// the user config file, ~/.config/rustyps/config:
+//
+//   # default columns, used when no format option is given
+//   format = pid,user,rss,args
+//   # default sort keys, used when no sort option is given
+//   sort = -rss
+//   [aliases]
+//   # named formats, usable in -o and PS_FORMAT
+//   mem = pid,user,rss,vsz,args
+//
+// formats are chosen in this order: -o and preset formats like -c,
+// PS_FORMAT, `format` of this file, then the default of the personality.
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+  pub format: Option<String>,
+  pub sort: Option<String>,
+  pub aliases: HashMap<String, String>,
+}
+
+pub fn parse_config(s: &str) -> Config {
+  let mut config = Config::default();
+  let mut in_aliases = false;
+  for (n, line) in s.lines().enumerate() {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+    if line.starts_with('[') && line.ends_with(']') {
+      in_aliases = &line[1..line.len() - 1] == "aliases";
+      if !in_aliases {
+        log::warn!("config line {}: unknown section {}", n + 1, line);
+      }
+      continue;
+    }
+    let (key, val) = match line.find('=') {
+      Some(ix) => (line[..ix].trim(), line[ix + 1..].trim()),
+      None => {
+        log::warn!("config line {}: missing '='", n + 1);
+        continue;
+      }
+    };
+    if in_aliases {
+      config.aliases.insert(String::from(key), String::from(val));
+      continue;
+    }
+    match key {
+      "format" => config.format = Some(String::from(val)),
+      "sort" => config.sort = Some(String::from(val)),
+      _ => log::warn!("config line {}: unknown key \"{}\"", n + 1, key),
+    }
+  }
+  config
+}
+
+fn config_path() -> Option<std::path::PathBuf> {
+  match std::env::var_os("XDG_CONFIG_HOME") {
+    Some(dir) if !dir.is_empty() => Some(std::path::PathBuf::from(dir).join("rustyps/config")),
+    _ => std::env::var_os("HOME")
+      .map(|home| std::path::PathBuf::from(home).join(".config/rustyps/config")),
+  }
+}
+
+// read the config file. it is optional, so an empty config if not readable.
+pub fn load_config() -> Config {
+  match config_path().map(std::fs::read_to_string) {
+    Some(Ok(s)) => parse_config(&s),
+    _ => {
+      log::trace!("no config file");
+      Config::default()
+    }
+  }
+}
+
+impl Config {
+  // replace the aliases in a format list like "mem,tty" or "mem=MEMORY"
+  pub fn expand_aliases(&self, s: &str) -> String {
+    if self.aliases.is_empty() {
+      return String::from(s);
+    }
+    let (specs, header) = match s.find('=') {
+      Some(ix) => (&s[..ix], &s[ix..]),
+      None => (s, ""),
+    };
+    let mut ret = String::new();
+    let mut rest = specs;
+    // keep the delimiters as they are
+    while let Some(ix) = rest.find(&[',', ' ', '\t'][..]) {
+      let spec = &rest[..ix];
+      ret.push_str(self.aliases.get(spec).map_or(spec, |v| v.as_str()));
+      ret.push_str(&rest[ix..ix + 1]);
+      rest = &rest[ix + 1..];
+    }
+    ret.push_str(self.aliases.get(rest).map_or(rest, |v| v.as_str()));
+    ret.push_str(header);
+    ret
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn parse_config_file() {
+    let config = super::parse_config(
+      "# comment
+format = pid,user,args
+sort=-rss
+nosuchkey = 1
+
+[aliases]
+mem = pid,user,rss,vsz,args
+sig=pid,pending,blocked
+",
+    );
+    assert_eq!(config.format.as_deref(), Some("pid,user,args"));
+    assert_eq!(config.sort.as_deref(), Some("-rss"));
+    assert_eq!(config.aliases.len(), 2);
+    assert_eq!(config.aliases["sig"], "pid,pending,blocked");
+  }
+
+  #[test]
+  fn expand_format_aliases() {
+    let config = super::parse_config("[aliases]\nmem = pid,rss\n");
+    assert_eq!(config.expand_aliases("mem,tty"), "pid,rss,tty");
+    assert_eq!(config.expand_aliases("tty mem"), "tty pid,rss");
+    assert_eq!(
+      config.expand_aliases("mem=MEMORY,mem"),
+      "pid,rss=MEMORY,mem"
+    );
+    assert_eq!(config.expand_aliases("memory"), "memory");
+  }
+}
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::fs::MetadataExt;
+use std::sync::OnceLock;
+
+// abbreviation flags for dev_to_tty()
+pub const ABBREV_DEV: u32 = 0x1; // remove /dev/
+pub const ABBREV_TTY: u32 = 0x2; // remove tty
+pub const ABBREV_PTS: u32 = 0x4; // remove pts/
+
+// one line of /proc/tty/drivers
+#[derive(Debug, PartialEq)]
+pub struct TtyMapNode {
+  pub name: String, // device file without "/dev/", eg: "pts", "ttyS"
+  pub major: u32,
+  pub minor_first: u32,
+  pub minor_last: u32,
+}
+
+static TTY_MAP: OnceLock<Vec<TtyMapNode>> = OnceLock::new();
+static DEV_MAP: OnceLock<HashMap<i32, String>> = OnceLock::new();
+
+// decode major number of `tty_nr` in /proc/<pid>/stat
+pub fn major(dev: i32) -> u32 {
+  ((dev as u32) >> 8) & 0xfff
+}
+
+// decode minor number of `tty_nr` in /proc/<pid>/stat
+pub fn minor(dev: i32) -> u32 {
+  let dev = dev as u32;
+  (dev & 0xff) | ((dev >> 12) & 0xfff00)
+}
+
+// encode major/minor in the same way as `tty_nr`
+pub fn makedev(major: u32, minor: u32) -> i32 {
+  ((minor & 0xff) | ((major & 0xfff) << 8) | ((minor & !0xff) << 12)) as i32
+}
+
+// convert `st_rdev` (glibc dev_t) into `tty_nr` encoding
+pub fn rdev2dev(rdev: u64) -> i32 {
+  let major = ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff);
+  let minor = (rdev & 0xff) | ((rdev >> 12) & !0xff);
+  makedev(major as u32, minor as u32)
+}
+
+pub fn parse_drivers(s: &str) -> Vec<TtyMapNode> {
+  let mut ret = vec![];
+  for line in s.lines() {
+    // driver-name /dev/devfile major minor-range type
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 4 || !fields[1].starts_with("/dev/") {
+      continue;
+    }
+    let major = match fields[2].parse::<u32>() {
+      Ok(n) => n,
+      Err(_) => continue,
+    };
+    let (first, last) = match fields[3].find('-') {
+      Some(ix) => (
+        fields[3][..ix].parse::<u32>(),
+        fields[3][ix + 1..].parse::<u32>(),
+      ),
+      None => (fields[3].parse::<u32>(), fields[3].parse::<u32>()),
+    };
+    if let (Ok(minor_first), Ok(minor_last)) = (first, last) {
+      ret.push(TtyMapNode {
+        name: String::from(&fields[1][5..]),
+        major,
+        minor_first,
+        minor_last,
+      });
+    }
+  }
+  ret
+}
+
+fn tty_map() -> &'static Vec<TtyMapNode> {
+  TTY_MAP.get_or_init(|| match std::fs::read_to_string("/proc/tty/drivers") {
+    Ok(s) => parse_drivers(&s),
+    Err(_) => {
+      log::trace!("failed to read /proc/tty/drivers");
+      vec![]
+    }
+  })
+}
+
+// map of char devices directly under /dev
+fn dev_map() -> &'static HashMap<i32, String> {
+  DEV_MAP.get_or_init(|| {
+    let mut map = HashMap::new();
+    let dir = match std::fs::read_dir("/dev") {
+      Ok(d) => d,
+      Err(_) => return map,
+    };
+    for ent in dir.flatten() {
+      if let Ok(meta) = std::fs::symlink_metadata(ent.path()) {
+        if meta.file_type().is_char_device() {
+          let dev = rdev2dev(meta.rdev());
+          map
+            .entry(dev)
+            .or_insert_with(|| ent.path().to_string_lossy().into_owned());
+        }
+      }
+    }
+    map
+  })
+}
+
+// check @path is a char device whose number is @dev
+fn is_dev(path: &str, dev: i32) -> bool {
+  match std::fs::metadata(path) {
+    Ok(meta) => meta.file_type().is_char_device() && rdev2dev(meta.rdev()) == dev,
+    Err(_) => false,
+  }
+}
+
+// find the name using /proc/tty/drivers
+fn driver_name(dev: i32) -> Option<String> {
+  let (maj, min) = (major(dev), minor(dev));
+  for node in tty_map() {
+    if node.major != maj || min < node.minor_first || node.minor_last < min {
+      continue;
+    }
+    let candidates = [
+      format!("/dev/{}{}", node.name, min),  // like "/dev/tty1"
+      format!("/dev/{}/{}", node.name, min), // like "/dev/pts/3"
+      format!("/dev/{}{}", node.name, min - node.minor_first), // like "/dev/ttyS0"
+      format!("/dev/{}", node.name),         // like "/dev/console"
+    ];
+    for path in candidates.iter() {
+      if is_dev(path, dev) {
+        return Some(path.clone());
+      }
+    }
+  }
+  None
+}
+
+// find the name by the well-known numbering, without touching /dev
+fn guess_name(dev: i32) -> Option<String> {
+  let (maj, min) = (major(dev), minor(dev));
+  match maj {
+    4 if min < 64 => Some(format!("/dev/tty{}", min)),
+    4 => Some(format!("/dev/ttyS{}", min - 64)),
+    136..=143 => Some(format!("/dev/pts/{}", min + (maj - 136) * 256)),
+    _ => None,
+  }
+}
+
+fn abbrev(name: &str, flags: u32) -> String {
+  let mut name = name;
+  if flags & ABBREV_DEV != 0 {
+    name = name.strip_prefix("/dev/").unwrap_or(name);
+  }
+  if flags & ABBREV_TTY != 0 {
+    name = name.strip_prefix("tty").unwrap_or(name);
+  }
+  if flags & ABBREV_PTS != 0 {
+    name = name.strip_prefix("pts/").unwrap_or(name);
+  }
+  String::from(name)
+}
+
+// translate `tty_nr` into a name like "pts/3". "?" means no controlling terminal.
+pub fn dev_to_tty(dev: i32, flags: u32) -> String {
+  if dev == 0 {
+    return String::from("?");
+  }
+  let name = driver_name(dev)
+    .or_else(|| dev_map().get(&dev).cloned())
+    .or_else(|| guess_name(dev));
+  match name {
+    Some(name) => abbrev(&name, flags),
+    None => format!("{},{}", major(dev), minor(dev)),
+  }
+}
+
+// translate a name like "pts/3", "tty1", "1" or "/dev/ttyS0" into `tty_nr`.
+// "-" and "?" mean no controlling terminal.
+pub fn tty_to_dev(name: &str) -> Option<i32> {
+  if name == "-" || name == "?" {
+    return Some(0);
+  }
+  let candidates = if name.starts_with('/') {
+    vec![String::from(name)]
+  } else {
+    vec![
+      format!("/dev/{}", name),
+      format!("/dev/tty{}", name),
+      format!("/dev/pts/{}", name),
+    ]
+  };
+  for path in candidates.iter() {
+    if let Ok(meta) = std::fs::metadata(path) {
+      if meta.file_type().is_char_device() {
+        return Some(rdev2dev(meta.rdev()));
+      }
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn decode_tty_nr() {
+    // pts/3
+    assert_eq!(super::major(34819), 136);
+    assert_eq!(super::minor(34819), 3);
+    assert_eq!(super::makedev(136, 3), 34819);
+    // large minor numbers are split
+    let dev = super::makedev(136, 300);
+    assert_eq!(super::major(dev), 136);
+    assert_eq!(super::minor(dev), 300);
+  }
+
+  #[test]
+  fn rdev_is_converted() {
+    // glibc makedev(136, 300)
+    let rdev: u64 = (300 & 0xff) | ((136 & 0xfff) << 8) | ((300 & !0xff) << 12);
+    assert_eq!(super::rdev2dev(rdev), super::makedev(136, 300));
+  }
+
+  #[test]
+  fn parse_drivers_file() {
+    let drivers = "/dev/tty             /dev/tty        5       0 system:/dev/tty
+serial               /dev/ttyS       4 64-111 serial
+pty_slave            /dev/pts      136 0-1048575 pty:slave
+unknown              /dev/tty        4 1-63 console
+";
+    let map = super::parse_drivers(drivers);
+    assert_eq!(map.len(), 4);
+    assert_eq!(
+      map[1],
+      super::TtyMapNode {
+        name: String::from("ttyS"),
+        major: 4,
+        minor_first: 64,
+        minor_last: 111,
+      }
+    );
+    assert_eq!(map[2].name, "pts");
+    assert_eq!(map[2].minor_last, 1048575);
+  }
+
+  #[test]
+  fn dev_to_tty_names() {
+    assert_eq!(super::dev_to_tty(0, super::ABBREV_DEV), "?");
+    assert_eq!(super::tty_to_dev("?"), Some(0));
+    assert_eq!(
+      super::abbrev("/dev/pts/3", super::ABBREV_DEV | super::ABBREV_PTS),
+      "3"
+    );
+    assert_eq!(
+      super::abbrev("/dev/tty1", super::ABBREV_DEV | super::ABBREV_TTY),
+      "1"
+    );
+    assert_eq!(
+      super::guess_name(super::makedev(4, 65)).unwrap(),
+      "/dev/ttyS1"
+    );
+    assert_eq!(
+      super::guess_name(super::makedev(137, 2)).unwrap(),
+      "/dev/pts/258"
+    );
+  }
+}
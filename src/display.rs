@@ -1,30 +1,1094 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
 use crate::argparser;
+use crate::argparser::{ForestType, HeaderType, MemGroup, OutputMode, ThreadFlag};
+use crate::files;
+use crate::helper;
+use crate::output;
+use crate::pwcache;
 use crate::readproc;
+use crate::readproc::{Limits, ProcIo, Rates, PROCT};
+use crate::sockets;
+use crate::sortformat;
+use crate::sysinfo;
 
-pub fn simple_spew(parser: &argparser::PsParser) -> Result<(), String> {
-  let mut pt = match readproc::openproc(readproc::PROC_FILLSTAT, None, None) {
-    Ok(_pt) => _pt,
-    Err(msg) => return Err(msg),
-  };
+pub fn simple_spew(parser: &argparser::PsParser, needs: u64) -> Result<(), String> {
+  if let Some(pid) = parser.files {
+    return files_spew(pid);
+  }
+  if let Some(pid) = parser.limits {
+    return limits_spew(pid);
+  }
+  if parser.sockets {
+    return sockets_spew(parser, needs);
+  }
+  if parser.mem_summary.is_some() {
+    return mem_summary_spew(parser, needs);
+  }
+  if parser.interval.is_some() {
+    return interval_spew(parser, needs);
+  }
+  // --pidns needs all the processes to find the parents in the namespace
+  if !parser.sort_list.is_empty()
+    || parser.forest_type != ForestType::NONE
+    || parser.pidns.is_some()
+  {
+    return fancy_spew(parser, needs);
+  }
+  let mut pt = readproc::openproc(needs, None, None)?;
   log::trace!("simple_spew: opened PROCTAB");
 
   // display
+  let mut printer = Printer::new(parser);
   loop {
     if let Some(p) = readproc::readproc(&mut pt) {
       log::trace!("success readproc");
-      if readproc::want_this_proc(&p, &parser) {
-        show_one_proc(&p);
+      if readproc::want_this_proc(&p, parser) {
+        for t in each_proc(&mut pt, p, parser) {
+          printer.print(&t);
+        }
       }
     } else {
       log::trace!("fail readproc");
       break;
     }
   }
+  printer.finish();
+  Ok(())
+}
+
+// read everything first, then sort and/or make a tree
+fn fancy_spew(parser: &argparser::PsParser, needs: u64) -> Result<(), String> {
+  let procs = collect_procs(parser, needs)?;
+  print_procs(parser, procs);
+  Ok(())
+}
+
+// the selected processes and threads in the order of /proc,
+// only the ones in the pid namespace of --pidns
+fn collect_procs(parser: &argparser::PsParser, needs: u64) -> Result<Vec<PROCT>, String> {
+  let ns = match parser.pidns {
+    Some(target) => Some(pidns_inode(target)?),
+    None => None,
+  };
+  let mut pt = readproc::openproc(needs, None, None)?;
+  log::trace!("collect_procs: opened PROCTAB");
+
+  let mut procs = vec![];
+  while let Some(p) = readproc::readproc(&mut pt) {
+    if ns.is_some_and(|ns| p.ns[readproc::NS_PID] != ns) {
+      continue;
+    }
+    if readproc::want_this_proc(&p, parser) {
+      procs.append(&mut each_proc(&mut pt, p, parser));
+    }
+  }
+  Ok(procs)
+}
+
+fn print_procs(parser: &argparser::PsParser, mut procs: Vec<PROCT>) {
+  if parser.pidns.is_some() {
+    rebase_pids(&mut procs);
+  }
+  // stable, so the order of /proc is kept for equal keys
+  procs.sort_by(|a, b| sortformat::compare_procs(&parser.sort_list, a, b));
+
+  let mut printer = Printer::new(parser);
+  if parser.forest_type == ForestType::NONE {
+    for p in procs.iter() {
+      printer.print(p);
+    }
+  } else {
+    let forest = Forest::new(&procs);
+    printer.print_forest(&procs, &forest);
+  }
+  printer.finish();
+}
+
+// --interval: rescan /proc and print a report for each interval,
+// with the rates of the interval. the first scan is only the baseline.
+fn interval_spew(parser: &argparser::PsParser, needs: u64) -> Result<(), String> {
+  let interval = parser.interval.unwrap();
+  let mut prev = sample_map(collect_procs(parser, needs)?);
+  let mut prev_time = Instant::now();
+  let mut reports = 0;
+  loop {
+    std::thread::sleep(interval);
+    let mut procs = collect_procs(parser, needs)?;
+    let now = Instant::now();
+    let elapsed = now.duration_since(prev_time).as_secs_f64();
+    let uptime = sysinfo::uptime().unwrap_or(0.0);
+    for p in procs.iter_mut() {
+      let rates = compute_rates(prev.get(&(p.tid, p.start_time)), p, elapsed, uptime);
+      p.rates = Some(rates);
+    }
+    // processes which exited in the interval are forgotten here
+    prev = sample_map(procs.clone());
+    prev_time = now;
+
+    if reports > 0 && parser.output_mode == OutputMode::TEXT {
+      println!();
+    }
+    print_procs(parser, procs);
+    reports += 1;
+    if parser.count.is_some_and(|count| reports >= count) {
+      return Ok(());
+    }
+  }
+}
+
+// --files: the open files of a process
+fn files_spew(pid: i32) -> Result<(), String> {
+  for line in files::format_files(&files::read_fds(pid)?) {
+    println!("{}", line);
+  }
   Ok(())
 }
 
-// XXX
-pub fn show_one_proc(p: &readproc::PROCT) {
-  log::trace!("show_one_proc()");
-  println!("{}: {}", p.cmd, p.tgid);
+// pids are at most this, so a larger --pidns is a namespace inode
+const PID_MAX_LIMIT: u64 = 4194304;
+
+// the inode of the pid namespace of --pidns, given as a pid or an inode
+fn pidns_inode(target: u64) -> Result<u64, String> {
+  if target > PID_MAX_LIMIT {
+    return Ok(target);
+  }
+  let flags = readproc::PROC_FILLSTAT | readproc::PROC_FILLNS | readproc::PROC_PID;
+  let mut pt = readproc::openproc(flags, Some(vec![target as i32]), None)?;
+  match readproc::readproc(&mut pt) {
+    Some(p) if p.ns[readproc::NS_PID] != 0 => Ok(p.ns[readproc::NS_PID]),
+    Some(_) => Err(format!("/proc/{}/ns/pid: not readable", target)),
+    None => Err(format!("process {} not found", target)),
+  }
+}
+
+// replace the pids by the innermost ones of NSpid and the like. all of @procs
+// are in one pid namespace, and a parent outside of it is 0, like getppid(2).
+pub fn rebase_pids(procs: &mut [PROCT]) {
+  let inner = |ids: &[i32], id: i32| ids.last().copied().unwrap_or(id);
+  let tgids: HashMap<i32, i32> = procs
+    .iter()
+    .map(|p| (p.tgid, inner(&p.nstgid, p.tgid)))
+    .collect();
+  for p in procs.iter_mut() {
+    p.ppid = tgids.get(&p.ppid).copied().unwrap_or(0);
+    p.tgid = inner(&p.nstgid, p.tgid);
+    p.tid = inner(&p.nspid, p.tid);
+    p.pgrp = inner(&p.nspgid, p.pgrp);
+    p.session = inner(&p.nssid, p.session);
+  }
+}
+
+// --limits: the resource limits of a process
+fn limits_spew(pid: i32) -> Result<(), String> {
+  let flags = readproc::PROC_FILLSTAT | readproc::PROC_FILLLIMITS | readproc::PROC_PID;
+  let mut pt = readproc::openproc(flags, Some(vec![pid]), None)?;
+  let limits = match readproc::readproc(&mut pt) {
+    Some(PROCT {
+      limits: Some(limits),
+      ..
+    }) => limits,
+    Some(_) => return Err(format!("/proc/{}/limits: not readable", pid)),
+    None => return Err(format!("process {} not found", pid)),
+  };
+  for line in format_limits(&limits) {
+    println!("{}", line);
+  }
+  Ok(())
+}
+
+// the lines of the --limits report, like prlimit
+pub fn format_limits(limits: &Limits) -> Vec<String> {
+  let rows: Vec<(&readproc::ResourceInfo, String, String)> = readproc::RESOURCES
+    .iter()
+    .map(|info| {
+      let rlim = limits.get(info.res);
+      (info, rlim.soft.to_string(), rlim.hard.to_string())
+    })
+    .collect();
+  let desc_width = rows
+    .iter()
+    .map(|(info, _, _)| info.desc.len())
+    .max()
+    .unwrap_or(0);
+  let soft_width = rows
+    .iter()
+    .map(|(_, soft, _)| soft.len())
+    .max()
+    .unwrap_or(0)
+    .max(4);
+  let hard_width = rows
+    .iter()
+    .map(|(_, _, hard)| hard.len())
+    .max()
+    .unwrap_or(0)
+    .max(4);
+  let mut lines = vec![format!(
+    "{:<10} {:<dw$} {:>sw$} {:>hw$} UNITS",
+    "RESOURCE",
+    "DESCRIPTION",
+    "SOFT",
+    "HARD",
+    dw = desc_width,
+    sw = soft_width,
+    hw = hard_width
+  )];
+  for (info, soft, hard) in rows {
+    let line = format!(
+      "{:<10} {:<dw$} {:>sw$} {:>hw$} {}",
+      info.name,
+      info.desc,
+      soft,
+      hard,
+      info.units,
+      dw = desc_width,
+      sw = soft_width,
+      hw = hard_width
+    );
+    lines.push(String::from(line.trim_end()));
+  }
+  lines
+}
+
+// --sockets: the sockets of the selected processes. with --port,
+// only the sockets on the ports.
+fn sockets_spew(parser: &argparser::PsParser, needs: u64) -> Result<(), String> {
+  let ports: Vec<u16> = parser
+    .selection_list
+    .iter()
+    .filter_map(|node| match node {
+      argparser::SelectionNode::PORT(sel) => Some(sel.port.clone()),
+      _ => None,
+    })
+    .flatten()
+    .collect();
+  let procs = collect_procs(parser, needs)?;
+  let mut tables = sockets::SocketTables::new();
+  let mut rows = vec![];
+  let mut unreadable = 0;
+  for p in procs.iter().filter(|p| p.tid == p.tgid) {
+    let inodes = match files::socket_inodes(p.tgid) {
+      Some(inodes) => inodes,
+      None => {
+        unreadable += 1;
+        continue;
+      }
+    };
+    let table = tables.of_pid(p.tgid);
+    for inode in inodes {
+      // netlink and others are not in the tables
+      let socket = match table.get(&inode) {
+        Some(socket) => socket.clone(),
+        None => continue,
+      };
+      if ports.is_empty() || socket.local_port.is_some_and(|port| ports.contains(&port)) {
+        rows.push((p, socket));
+      }
+    }
+  }
+  let rows: Vec<(&PROCT, &sockets::Socket)> = rows.iter().map(|(p, s)| (*p, s)).collect();
+  for line in sockets::format_sockets(&rows) {
+    println!("{}", line);
+  }
+  if unreadable > 0 {
+    eprintln!("left out for unreadable fds: {}", unreadable);
+  }
+  Ok(())
+}
+
+// --mem-summary: memory of the selected processes by user or command
+fn mem_summary_spew(parser: &argparser::PsParser, needs: u64) -> Result<(), String> {
+  let procs = collect_procs(parser, needs)?;
+  // threads share the memory of their process
+  let procs: Vec<PROCT> = procs.into_iter().filter(|p| p.tid == p.tgid).collect();
+  for line in format_mem_summary(&procs, parser.mem_summary.unwrap()) {
+    println!("{}", line);
+  }
+  let unreadable = procs.iter().filter(|p| p.smaps.is_none()).count();
+  if unreadable > 0 {
+    eprintln!("left out for unreadable smaps: {}", unreadable);
+  }
+  Ok(())
+}
+
+// processes, rss, pss, uss, swap of a --mem-summary group
+type MemSums = (usize, u64, u64, u64, u64);
+
+// the lines of the --mem-summary report in KiB, the largest PSS first
+pub fn format_mem_summary(procs: &[PROCT], group: MemGroup) -> Vec<String> {
+  let mut groups: HashMap<String, MemSums> = HashMap::new();
+  for p in procs.iter() {
+    let smaps = match &p.smaps {
+      Some(smaps) => smaps,
+      None => continue,
+    };
+    let name = match group {
+      MemGroup::USER => pwcache::user_from_uid(p.euid),
+      MemGroup::COMMAND => p.cmd.clone(),
+    };
+    let sum = groups.entry(name).or_default();
+    sum.0 += 1;
+    sum.1 += smaps.rss;
+    sum.2 += smaps.pss;
+    sum.3 += smaps.uss;
+    sum.4 += smaps.swap;
+  }
+  let mut groups: Vec<(String, MemSums)> = groups.into_iter().collect();
+  groups.sort_by(|a, b| b.1 .2.cmp(&a.1 .2).then(a.0.cmp(&b.0)));
+  let total = groups.iter().fold((0, 0, 0, 0, 0), |t, (_, g)| {
+    (t.0 + g.0, t.1 + g.1, t.2 + g.2, t.3 + g.3, t.4 + g.4)
+  });
+
+  let head = match group {
+    MemGroup::USER => "USER",
+    MemGroup::COMMAND => "COMMAND",
+  };
+  let width = groups
+    .iter()
+    .map(|(name, _)| name.chars().count())
+    .fold(head.len().max(8), usize::max);
+  let line = |name: &str, g: &MemSums| {
+    format!(
+      "{:<width$} {:>5} {:>9} {:>9} {:>9} {:>9}",
+      name,
+      g.0,
+      g.1,
+      g.2,
+      g.3,
+      g.4,
+      width = width
+    )
+  };
+  let mut lines = vec![format!(
+    "{:<width$} {:>5} {:>9} {:>9} {:>9} {:>9}",
+    head,
+    "PROCS",
+    "RSS",
+    "PSS",
+    "USS",
+    "SWAP",
+    width = width
+  )];
+  for (name, g) in groups.iter() {
+    lines.push(line(name, g));
+  }
+  lines.push(line("total", &total));
+  lines
+}
+
+// a pid is reused by a new process, so a process is known by its pid and start time
+fn sample_map(procs: Vec<PROCT>) -> HashMap<(i32, u64), PROCT> {
+  procs
+    .into_iter()
+    .map(|p| ((p.tid, p.start_time), p))
+    .collect()
+}
+
+// the rates of @p since @prev, its previous scan, @elapsed seconds ago.
+// a process without @prev started in the interval, so its counters are
+// counted from zero over its age.
+pub fn compute_rates(prev: Option<&PROCT>, p: &PROCT, elapsed: f64, uptime: f64) -> Rates {
+  let hz = sysinfo::hertz() as f64;
+  let zero = PROCT::default();
+  let (base, secs) = match prev {
+    Some(q) => (q, elapsed),
+    None => {
+      let age = uptime - p.start_time as f64 / hz;
+      (&zero, age.min(elapsed))
+    }
+  };
+  // at least a tick, not to divide by zero
+  let secs = secs.max(1.0 / hz);
+  let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / secs;
+  let io_rate = |now: fn(&ProcIo) -> u64| match (&p.io, &base.io, prev) {
+    (Some(io), Some(before), _) => Some(rate(now(io), now(before))),
+    (Some(io), None, None) => Some(rate(now(io), 0)),
+    _ => None,
+  };
+  Rates {
+    pcpu: rate(p.utime + p.stime, base.utime + base.stime) * 100.0 / hz,
+    min_flt: rate(p.min_flt, base.min_flt),
+    maj_flt: rate(p.maj_flt, base.maj_flt),
+    nvcsw: rate(p.nvcsw, base.nvcsw),
+    nivcsw: rate(p.nivcsw, base.nivcsw),
+    read_bytes: io_rate(|io| io.read_bytes),
+    write_bytes: io_rate(|io| io.write_bytes),
+  }
+}
+
+// the process itself and/or its threads, depending on the thread options
+fn each_proc(pt: &mut readproc::PROCTAB, p: PROCT, parser: &argparser::PsParser) -> Vec<PROCT> {
+  let flags = &parser.thread_flags;
+  let show_proc = flags.is_empty() || flags.contains(&ThreadFlag::SHOW_PROC);
+  let show_task =
+    flags.contains(&ThreadFlag::SHOW_TASK) || flags.contains(&ThreadFlag::LOOSE_TASKS);
+  if !show_task {
+    return vec![p];
+  }
+  let mut ret = vec![];
+  while let Some(t) = readproc::readtask(pt, &p) {
+    ret.push(t);
+  }
+  // H shows threads in place of the process
+  if show_proc && !flags.contains(&ThreadFlag::LOOSE_TASKS) {
+    ret.insert(0, p);
+  }
+  ret
+}
+
+// parent/children relation of the collected processes, by index
+pub(crate) struct Forest {
+  pub(crate) roots: Vec<usize>,
+  pub(crate) children: Vec<Vec<usize>>,
+}
+
+impl Forest {
+  pub(crate) fn new(procs: &[PROCT]) -> Forest {
+    let mut first: HashMap<i32, usize> = HashMap::new();
+    for (ix, p) in procs.iter().enumerate() {
+      first.entry(p.tid).or_insert(ix);
+    }
+    let mut children = vec![vec![]; procs.len()];
+    let mut has_parent = vec![false; procs.len()];
+    for (ix, p) in procs.iter().enumerate() {
+      // a thread hangs under its process, a process under its parent
+      let parent = if first[&p.tid] != ix {
+        Some(first[&p.tid])
+      } else if p.tid != p.tgid {
+        first.get(&p.tgid).copied()
+      } else {
+        first.get(&p.ppid).copied()
+      };
+      if let Some(parent) = parent {
+        if parent != ix {
+          children[parent].push(ix);
+          has_parent[ix] = true;
+        }
+      }
+    }
+    let mut roots: Vec<usize> = (0..procs.len()).filter(|ix| !has_parent[*ix]).collect();
+
+    // a cycle made by reused pids has no root; break it where it is found
+    let mut visited = vec![false; procs.len()];
+    let mut stack: Vec<usize> = roots.clone();
+    while let Some(ix) = stack.pop() {
+      visited[ix] = true;
+      stack.extend(children[ix].iter().filter(|c| !visited[**c]));
+    }
+    for ix in 0..procs.len() {
+      if !visited[ix] {
+        roots.push(ix);
+        let mut stack = vec![ix];
+        while let Some(ix) = stack.pop() {
+          visited[ix] = true;
+          stack.extend(children[ix].iter().filter(|c| !visited[**c]));
+        }
+      }
+    }
+    Forest { roots, children }
+  }
+}
+
+// the prefix of the command column in the forest mode.
+// @last tells whether each ancestor (and the node itself) is the last sibling.
+pub fn forest_prefix(forest_type: &ForestType, last: &[bool]) -> String {
+  let level = last.len();
+  if level == 0 {
+    return String::new();
+  }
+  match forest_type {
+    ForestType::UNIX => "  ".repeat(level),
+    ForestType::BSD => {
+      let mut s = String::new();
+      for is_last in last[..level - 1].iter() {
+        s.push_str(if *is_last { "    " } else { " |  " });
+      }
+      s.push_str(" \\_ ");
+      s
+    }
+    ForestType::NONE => String::new(),
+  }
+}
+
+// a line of the text output
+pub fn format_text(p: &PROCT, parser: &argparser::PsParser, prefix: &str) -> String {
+  let vals: Vec<String> = parser
+    .format_list
+    .iter()
+    .map(|node| {
+      let mut val = (node.pr)(p).to_string();
+      if node.flags & output::CF_FOREST != 0 {
+        val.insert_str(0, prefix);
+      }
+      val
+    })
+    .collect();
+  layout_line(parser, &vals)
+}
+
+// the header line. None if it should not be shown,
+// including when every column is renamed to empty.
+pub fn format_header(parser: &argparser::PsParser) -> Option<String> {
+  if parser.header_type == HeaderType::NONE
+    || parser.format_list.iter().all(|node| node.name.is_empty())
+  {
+    return None;
+  }
+  let names: Vec<String> = parser
+    .format_list
+    .iter()
+    .map(|node| node.name.clone())
+    .collect();
+  Some(layout_line(parser, &names))
+}
+
+// put @vals into the columns.
+// a column longer than its width pushes the following ones, which then try to
+// get back to their positions. the line is cut at the screen width, which
+// is unlimited with ww or when the output is not a terminal.
+fn layout_line(parser: &argparser::PsParser, vals: &[String]) -> String {
+  let last = parser.format_list.len().saturating_sub(1);
+  let screen_cols = parser.screen_cols as usize;
+  let mut line = String::new();
+  let mut cur = 0; // length of @line in chars
+  let mut goal = 0; // where the current column should end
+  for (ix, (node, val)) in parser.format_list.iter().zip(vals).enumerate() {
+    if ix != 0 {
+      line.push(' ');
+      cur += 1;
+      goal += 1;
+    }
+    goal += node.width;
+    // only the last column can be longer than its width if unlimited
+    let val: String = if node.flags & output::CF_UNLIMITED != 0 && ix != last {
+      val.chars().take(node.width).collect()
+    } else {
+      val.clone()
+    };
+    let len = val.chars().count();
+    let pad = if node.flags & output::CF_LEFT != 0 {
+      0
+    } else {
+      goal.saturating_sub(cur + len)
+    };
+    let mut text = format!("{:1$}{2}", "", pad, val);
+    if ix != last && node.flags & output::CF_LEFT != 0 && len < node.width {
+      text.push_str(&" ".repeat(goal.saturating_sub(cur + len)));
+    }
+    cur += text.chars().count();
+    line.push_str(&text);
+  }
+  if cur > screen_cols {
+    line = line.chars().take(screen_cols).collect();
+  }
+  line
+}
+
+// a record of CSV, without the line break
+pub fn format_csv(p: &PROCT, parser: &argparser::PsParser) -> String {
+  let fields: Vec<String> = parser
+    .format_list
+    .iter()
+    .map(|node| match (node.pr)(p) {
+      output::Value::None => String::new(),
+      val => helper::csv_escape(&val.to_string()),
+    })
+    .collect();
+  fields.join(",")
+}
+
+// a JSON object whose keys are the headers.
+// @children is given in the forest mode.
+pub fn format_json(p: &PROCT, parser: &argparser::PsParser, children: Option<&[String]>) -> String {
+  let mut fields: Vec<String> = parser
+    .format_list
+    .iter()
+    .zip(json_keys(parser))
+    .map(|(node, key)| format!("{}:{}", helper::json_escape(&key), (node.pr)(p).to_json()))
+    .collect();
+  if let Some(children) = children {
+    fields.push(format!("\"children\":[{}]", children.join(",")));
+  }
+  format!("{{{}}}", fields.join(","))
+}
+
+// the headers, or the specs if the header is renamed to empty or is used
+// by an earlier column, like COMMAND of comm,args. a spec used twice gets
+// a number, like pid_2.
+fn json_keys(parser: &argparser::PsParser) -> Vec<String> {
+  let mut keys: Vec<String> = vec![];
+  for node in parser.format_list.iter() {
+    let mut key = if node.name.is_empty() || keys.contains(&node.name) {
+      String::from(node.spec)
+    } else {
+      node.name.clone()
+    };
+    let mut n = 1;
+    while keys.contains(&key) {
+      n += 1;
+      key = format!("{}_{}", node.spec, n);
+    }
+    keys.push(key);
+  }
+  keys
+}
+
+// writes records in the output mode
+struct Printer<'a> {
+  parser: &'a argparser::PsParser,
+  count: usize,
+  header: Option<String>,
+  lines_to_next_header: u32,
+}
+
+impl<'a> Printer<'a> {
+  fn new(parser: &'a argparser::PsParser) -> Printer<'a> {
+    let header = format_header(parser);
+    if parser.output_mode == OutputMode::TEXT {
+      if let Some(header) = &header {
+        println!("{}", header);
+      }
+    }
+    if parser.output_mode == OutputMode::CSV && parser.header_type != HeaderType::NONE {
+      let names: Vec<String> = parser
+        .format_list
+        .iter()
+        .map(|node| helper::csv_escape(&node.name))
+        .collect();
+      print!("{}\r\n", names.join(","));
+    }
+    Printer {
+      parser,
+      count: 0,
+      header,
+      lines_to_next_header: header_gap(parser),
+    }
+  }
+
+  // a line of the text output, after the header of the next page if needed
+  fn print_text(&mut self, line: &str) {
+    if self.parser.header_type == HeaderType::MULTI {
+      if self.lines_to_next_header == 0 {
+        if let Some(header) = &self.header {
+          println!("{}", header);
+        }
+        self.lines_to_next_header = header_gap(self.parser);
+      }
+      self.lines_to_next_header -= 1;
+    }
+    println!("{}", line);
+  }
+
+  fn print(&mut self, p: &PROCT) {
+    match self.parser.output_mode {
+      OutputMode::TEXT => self.print_text(&format_text(p, self.parser, "")),
+      OutputMode::CSV => print!("{}\r\n", format_csv(p, self.parser)),
+      _ => self.print_json(&format_json(p, self.parser, None)),
+    }
+  }
+
+  fn print_json(&mut self, obj: &str) {
+    match self.parser.output_mode {
+      OutputMode::NDJSON => println!("{}", obj),
+      _ => {
+        if self.count == 0 {
+          print!("[\n{}", obj);
+        } else {
+          print!(",\n{}", obj);
+        }
+      }
+    }
+    self.count += 1;
+  }
+
+  fn print_forest(&mut self, procs: &[PROCT], forest: &Forest) {
+    match self.parser.output_mode {
+      OutputMode::JSON | OutputMode::NDJSON => {
+        for root in forest.roots.iter() {
+          let obj = self.json_tree(procs, forest, *root);
+          self.print_json(&obj);
+        }
+      }
+      _ => {
+        for root in forest.roots.iter() {
+          self.text_tree(procs, forest, *root, &mut vec![]);
+        }
+      }
+    }
+  }
+
+  fn json_tree(&self, procs: &[PROCT], forest: &Forest, ix: usize) -> String {
+    let children: Vec<String> = forest.children[ix]
+      .iter()
+      .map(|c| self.json_tree(procs, forest, *c))
+      .collect();
+    format_json(&procs[ix], self.parser, Some(&children))
+  }
+
+  fn text_tree(&mut self, procs: &[PROCT], forest: &Forest, ix: usize, last: &mut Vec<bool>) {
+    if self.parser.output_mode == OutputMode::CSV {
+      // no art in CSV, just the tree order
+      print!("{}\r\n", format_csv(&procs[ix], self.parser));
+    } else {
+      let prefix = forest_prefix(&self.parser.forest_type, last);
+      self.print_text(&format_text(&procs[ix], self.parser, &prefix));
+    }
+    let n = forest.children[ix].len();
+    for (i, c) in forest.children[ix].iter().enumerate() {
+      last.push(i + 1 == n);
+      self.text_tree(procs, forest, *c, last);
+      last.pop();
+    }
+  }
+
+  fn finish(&self) {
+    if self.parser.output_mode == OutputMode::JSON {
+      if self.count == 0 {
+        println!("[]");
+      } else {
+        println!("\n]");
+      }
+    }
+  }
+}
+
+// number of lines between the headers, with --headers
+fn header_gap(parser: &argparser::PsParser) -> u32 {
+  std::cmp::max(parser.screen_rows, 2) - 1
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::argparser::{ForestType, HeaderType, PsParser};
+  use crate::readproc::PROCT;
+  use crate::sortformat;
+
+  fn parser_of(fmt: &str) -> PsParser {
+    PsParser {
+      format_list: sortformat::parse_formatted(fmt).unwrap(),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn machine_readable_records() {
+    let parser = parser_of("pid,comm,time");
+    let p = PROCT {
+      tgid: 42,
+      cmd: String::from("a \"b\",c"),
+      ..Default::default()
+    };
+    assert_eq!(
+      super::format_json(&p, &parser, None),
+      "{\"PID\":42,\"COMMAND\":\"a \\\"b\\\",c\",\"TIME\":0}"
+    );
+    assert_eq!(
+      super::format_json(&p, &parser, Some(&[])),
+      "{\"PID\":42,\"COMMAND\":\"a \\\"b\\\",c\",\"TIME\":0,\"children\":[]}"
+    );
+    let parser = parser_of("pid,comm=");
+    assert_eq!(
+      super::format_json(&p, &parser, None),
+      "{\"PID\":42,\"comm\":\"a \\\"b\\\",c\"}"
+    );
+    // no key is used twice
+    let parser = parser_of("comm,args,pid,pid");
+    assert_eq!(
+      super::format_json(&p, &parser, None),
+      "{\"COMMAND\":\"a \\\"b\\\",c\",\"args\":\"[a \\\"b\\\",c]\",\"PID\":42,\"pid\":42}"
+    );
+    let parser = parser_of("pid,pid,pid");
+    assert_eq!(
+      super::format_json(&p, &parser, None),
+      "{\"PID\":42,\"pid\":42,\"pid_2\":42}"
+    );
+    let parser = parser_of("pid,comm,time");
+    assert_eq!(
+      super::format_csv(&p, &parser),
+      "42,\"a \"\"b\"\",c\",00:00:00"
+    );
+  }
+
+  #[test]
+  fn text_layout() {
+    let mut parser = parser_of("pid,tname,args");
+    let p = PROCT {
+      tgid: 1234567,
+      tty: 0,
+      cmdline: vec![String::from("sleep"), String::from("100")],
+      ..Default::default()
+    };
+    // PID overflows by 2 and TTY eats it up
+    assert_eq!(
+      super::format_text(&p, &parser, ""),
+      "1234567 ?      sleep 100"
+    );
+    parser.screen_cols = 20;
+    assert_eq!(super::format_text(&p, &parser, ""), "1234567 ?      sleep");
+    parser.screen_cols = 10;
+    assert_eq!(super::format_text(&p, &parser, ""), "1234567 ? ");
+    // any column is cut, not only the last
+    parser.screen_cols = 5;
+    assert_eq!(super::format_text(&p, &parser, ""), "12345");
+    assert_eq!(super::format_header(&parser).unwrap(), "  PID");
+    // args is cut at its width when not the last column
+    let parser = parser_of("args,pid");
+    let p = PROCT {
+      tgid: 1,
+      cmdline: vec!["x".repeat(40)],
+      ..Default::default()
+    };
+    assert_eq!(
+      super::format_text(&p, &parser, ""),
+      format!("{}     1", "x".repeat(27))
+    );
+  }
+
+  #[test]
+  fn header_line() {
+    let mut parser = parser_of("pid,tname,args");
+    assert_eq!(
+      super::format_header(&parser).unwrap(),
+      "  PID TTY      COMMAND"
+    );
+    parser.header_type = HeaderType::NONE;
+    assert_eq!(super::format_header(&parser), None);
+    let parser = parser_of("pid,args=");
+    assert_eq!(super::format_header(&parser).unwrap(), "  PID ");
+    let mut parser = parser_of("pid=");
+    parser
+      .format_list
+      .append(&mut parser_of("args=").format_list);
+    assert_eq!(super::format_header(&parser), None);
+  }
+
+  #[test]
+  fn forest_relation() {
+    let procs = vec![
+      PROCT {
+        tgid: 1,
+        tid: 1,
+        ppid: 0,
+        ..Default::default()
+      },
+      PROCT {
+        tgid: 3,
+        tid: 3,
+        ppid: 2,
+        ..Default::default()
+      },
+      PROCT {
+        tgid: 2,
+        tid: 2,
+        ppid: 1,
+        ..Default::default()
+      },
+      PROCT {
+        tgid: 2,
+        tid: 5,
+        ppid: 1,
+        ..Default::default()
+      },
+      // a cycle without a root
+      PROCT {
+        tgid: 7,
+        tid: 7,
+        ppid: 8,
+        ..Default::default()
+      },
+      PROCT {
+        tgid: 8,
+        tid: 8,
+        ppid: 7,
+        ..Default::default()
+      },
+    ];
+    let forest = super::Forest::new(&procs);
+    assert_eq!(forest.roots, vec![0, 4]);
+    assert_eq!(forest.children[0], vec![2]);
+    assert_eq!(forest.children[2], vec![1, 3]);
+    assert_eq!(forest.children[4], vec![5]);
+  }
+
+  #[test]
+  fn forest_art() {
+    assert_eq!(super::forest_prefix(&ForestType::BSD, &[]), "");
+    assert_eq!(super::forest_prefix(&ForestType::BSD, &[true]), " \\_ ");
+    assert_eq!(
+      super::forest_prefix(&ForestType::BSD, &[false, true]),
+      " |   \\_ "
+    );
+    assert_eq!(
+      super::forest_prefix(&ForestType::BSD, &[true, false]),
+      "     \\_ "
+    );
+    assert_eq!(
+      super::forest_prefix(&ForestType::UNIX, &[true, true]),
+      "    "
+    );
+  }
+
+  #[test]
+  fn interval_rates() {
+    use crate::readproc::ProcIo;
+    let hz = crate::sysinfo::hertz();
+    let before = PROCT {
+      tid: 10,
+      start_time: 100 * hz,
+      utime: 5 * hz,
+      maj_flt: 10,
+      nvcsw: 100,
+      io: Some(ProcIo {
+        read_bytes: 4096,
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+    let after = PROCT {
+      utime: 6 * hz,
+      stime: hz,
+      maj_flt: 30,
+      nvcsw: 140,
+      io: Some(ProcIo {
+        read_bytes: 4096 + 8192,
+        ..Default::default()
+      }),
+      ..before.clone()
+    };
+    // two seconds of CPU in four seconds
+    let rates = super::compute_rates(Some(&before), &after, 4.0, 1000.0);
+    assert_eq!(rates.pcpu, 50.0);
+    assert_eq!((rates.maj_flt, rates.nvcsw), (5.0, 10.0));
+    assert_eq!(rates.read_bytes, Some(2048.0));
+    assert_eq!(rates.write_bytes, Some(0.0));
+
+    // started a second ago, after the previous scan
+    let born = PROCT {
+      start_time: 999 * hz,
+      utime: hz / 2,
+      min_flt: 300,
+      ..Default::default()
+    };
+    let rates = super::compute_rates(None, &born, 4.0, 1000.0);
+    assert_eq!((rates.pcpu, rates.min_flt), (50.0, 300.0));
+    assert_eq!(rates.read_bytes, None);
+
+    // io became unreadable
+    let gone = PROCT {
+      io: None,
+      ..after.clone()
+    };
+    assert_eq!(
+      super::compute_rates(Some(&before), &gone, 4.0, 1000.0).read_bytes,
+      None
+    );
+  }
+
+  #[test]
+  fn rebased_pids() {
+    let proc_of = |tgid: i32, ppid: i32, nstgid: Vec<i32>| PROCT {
+      tgid,
+      tid: tgid,
+      ppid,
+      pgrp: tgid,
+      session: tgid,
+      nspid: nstgid.clone(),
+      nspgid: nstgid.clone(),
+      nssid: nstgid.clone(),
+      nstgid,
+      ..Default::default()
+    };
+    let mut procs = vec![
+      // the init of a container, started by a shim outside of it
+      proc_of(41234, 41200, vec![41234, 1]),
+      proc_of(41250, 41234, vec![41250, 17]),
+      // no NSpid and the like
+      proc_of(41260, 41250, vec![]),
+    ];
+    super::rebase_pids(&mut procs);
+    let ids: Vec<(i32, i32, i32, i32)> = procs
+      .iter()
+      .map(|p| (p.tgid, p.tid, p.ppid, p.session))
+      .collect();
+    assert_eq!(
+      ids,
+      vec![(1, 1, 0, 1), (17, 17, 1, 17), (41260, 41260, 17, 41260)]
+    );
+  }
+
+  #[test]
+  fn limits_report() {
+    let limits = crate::readproc::limits2proc(
+      "Max stack size            8388608              unlimited            bytes
+Max file size             unlimited            unlimited            bytes
+Max open files            1024                 1048576              files
+Max nice priority         0                    0
+",
+    );
+    let lines = super::format_limits(&limits);
+    assert_eq!(lines.len(), 17);
+    assert_eq!(
+      lines[0],
+      "RESOURCE   DESCRIPTION                             SOFT      HARD UNITS"
+    );
+    assert_eq!(
+      lines[1],
+      "AS         address space limit                        -         - bytes"
+    );
+    assert_eq!(
+      lines[5],
+      "FSIZE      max file size                      unlimited unlimited bytes"
+    );
+    assert_eq!(
+      lines[9],
+      "NICE       max nice prio allowed to raise             0         0"
+    );
+    assert_eq!(
+      lines[10],
+      "NOFILE     max number of open files                1024   1048576 files"
+    );
+    assert_eq!(
+      lines[16],
+      "STACK      max stack size                       8388608 unlimited bytes"
+    );
+  }
+
+  #[test]
+  fn mem_summary() {
+    use crate::argparser::MemGroup;
+    use crate::readproc::SmapsRollup;
+    let proct = |uid: u32, cmd: &str, pss: u64, uss: u64| PROCT {
+      euid: uid,
+      cmd: String::from(cmd),
+      smaps: Some(SmapsRollup {
+        rss: pss * 2,
+        pss,
+        uss,
+        ..Default::default()
+      }),
+      ..Default::default()
+    };
+    let procs = vec![
+      proct(0, "sshd", 100, 50),
+      proct(0, "postgres", 4000, 3000),
+      proct(0, "postgres", 2000, 1000),
+      PROCT {
+        cmd: String::from("kthreadd"),
+        ..Default::default()
+      },
+    ];
+    assert_eq!(
+      super::format_mem_summary(&procs, MemGroup::COMMAND),
+      vec![
+        "COMMAND  PROCS       RSS       PSS       USS      SWAP",
+        "postgres     2     12000      6000      4000         0",
+        "sshd         1       200       100        50         0",
+        "total        3     12200      6100      4050         0",
+      ]
+    );
+    let lines = super::format_mem_summary(&procs, MemGroup::USER);
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("root         3     12200      6100"));
+  }
 }
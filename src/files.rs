@@ -0,0 +1,338 @@
+// the open files of a process, from /proc/<pid>/fd and /proc/<pid>/fdinfo.
+// this is the --files mode of ps, a small lsof.
+
+use std::os::unix::fs::MetadataExt;
+
+// what an fd refers to
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum FdKind {
+  REG,
+  DIR,
+  CHR,
+  BLK,
+  FIFO,
+  SOCK,
+  PIPE,
+  EVENTFD,
+  A_INODE, // other anon_inode, like epoll or signalfd
+  UNKNOWN,
+}
+
+impl std::fmt::Display for FdKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let s = match self {
+      FdKind::REG => "REG",
+      FdKind::DIR => "DIR",
+      FdKind::CHR => "CHR",
+      FdKind::BLK => "BLK",
+      FdKind::FIFO => "FIFO",
+      FdKind::SOCK => "SOCK",
+      FdKind::PIPE => "PIPE",
+      FdKind::EVENTFD => "EVENTFD",
+      FdKind::A_INODE => "A_INODE",
+      FdKind::UNKNOWN => "UNKNOWN",
+    };
+    f.pad(s)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FdEntry {
+  pub fd: i32,
+  pub target: String, // the link of /proc/<pid>/fd/<fd>
+  pub kind: FdKind,
+  pub flags: Option<u32>, // from fdinfo, None if it is not readable
+  pub pos: Option<u64>,   // from fdinfo
+}
+
+// glibc leaves O_LARGEFILE out on 64-bit, but the kernel still reports it
+const O_LARGEFILE: i32 = if libc::O_LARGEFILE != 0 {
+  libc::O_LARGEFILE
+} else {
+  0o100000
+};
+
+// the open flags after the access mode. O_TMPFILE includes O_DIRECTORY,
+// so it goes first.
+const OPEN_FLAGS: &[(i32, &str)] = &[
+  (libc::O_TMPFILE, "O_TMPFILE"),
+  (libc::O_CREAT, "O_CREAT"),
+  (libc::O_EXCL, "O_EXCL"),
+  (libc::O_NOCTTY, "O_NOCTTY"),
+  (libc::O_TRUNC, "O_TRUNC"),
+  (libc::O_APPEND, "O_APPEND"),
+  (libc::O_NONBLOCK, "O_NONBLOCK"),
+  (libc::O_DSYNC, "O_DSYNC"),
+  (libc::O_ASYNC, "O_ASYNC"),
+  (libc::O_DIRECT, "O_DIRECT"),
+  (O_LARGEFILE, "O_LARGEFILE"),
+  (libc::O_DIRECTORY, "O_DIRECTORY"),
+  (libc::O_NOFOLLOW, "O_NOFOLLOW"),
+  (libc::O_NOATIME, "O_NOATIME"),
+  (libc::O_CLOEXEC, "O_CLOEXEC"),
+  (libc::O_PATH, "O_PATH"),
+];
+
+// the kind of an fd by its link, like "socket:[1234]" or "/dev/null".
+// @mode is st_mode of the file it refers to, if stat succeeded.
+pub fn classify(target: &str, mode: Option<u32>) -> FdKind {
+  if target.starts_with("socket:[") {
+    return FdKind::SOCK;
+  }
+  if target.starts_with("pipe:[") {
+    return FdKind::PIPE;
+  }
+  if target == "anon_inode:[eventfd]" {
+    return FdKind::EVENTFD;
+  }
+  if target.starts_with("anon_inode:") {
+    return FdKind::A_INODE;
+  }
+  let mode = match mode {
+    Some(mode) => mode & libc::S_IFMT,
+    None => return FdKind::UNKNOWN,
+  };
+  match mode {
+    libc::S_IFREG => FdKind::REG,
+    libc::S_IFDIR => FdKind::DIR,
+    libc::S_IFCHR => FdKind::CHR,
+    libc::S_IFBLK => FdKind::BLK,
+    libc::S_IFIFO => FdKind::FIFO,
+    libc::S_IFSOCK => FdKind::SOCK,
+    _ => FdKind::UNKNOWN,
+  }
+}
+
+// (flags, pos) of a fdinfo file, like "pos:\t0\nflags:\t0100002\nmnt_id:\t25\n".
+// flags are in octal.
+pub fn parse_fdinfo(s: &str) -> (Option<u32>, Option<u64>) {
+  let mut flags = None;
+  let mut pos = None;
+  for line in s.lines() {
+    let (key, val) = match line.split_once(':') {
+      Some(kv) => kv,
+      None => continue,
+    };
+    match key {
+      "flags" => flags = u32::from_str_radix(val.trim(), 8).ok(),
+      "pos" => pos = val.trim().parse::<u64>().ok(),
+      _ => {}
+    }
+  }
+  (flags, pos)
+}
+
+// "O_RDWR|O_APPEND|O_CLOEXEC". the unknown bits are left at the end in octal.
+pub fn decode_flags(flags: u32) -> String {
+  let flags = flags as i32;
+  let mut names = vec![match flags & libc::O_ACCMODE {
+    libc::O_RDONLY => String::from("O_RDONLY"),
+    libc::O_WRONLY => String::from("O_WRONLY"),
+    libc::O_RDWR => String::from("O_RDWR"),
+    _ => String::from("O_ACCMODE"),
+  }];
+  let mut rest = flags & !libc::O_ACCMODE;
+  for (bit, name) in OPEN_FLAGS {
+    if *bit != 0 && rest & bit == *bit {
+      names.push(String::from(*name));
+      rest &= !bit;
+    }
+  }
+  if rest != 0 {
+    names.push(format!("0{:o}", rest));
+  }
+  names.join("|")
+}
+
+// the inode of a "socket:[inode]" link
+pub fn socket_inode(target: &str) -> Option<u64> {
+  target
+    .strip_prefix("socket:[")?
+    .strip_suffix(']')?
+    .parse::<u64>()
+    .ok()
+}
+
+// the inodes of the sockets of @pid, None if the fds are not readable
+pub fn socket_inodes(pid: i32) -> Option<Vec<u64>> {
+  let entries = std::fs::read_dir(format!("/proc/{}/fd", pid)).ok()?;
+  let mut fds: Vec<(i32, u64)> = entries
+    .flatten()
+    .filter_map(|entry| {
+      let fd = entry.file_name().to_str()?.parse::<i32>().ok()?;
+      let target = std::fs::read_link(entry.path()).ok()?;
+      Some((fd, socket_inode(target.to_str()?)?))
+    })
+    .collect();
+  fds.sort_unstable();
+  Some(fds.into_iter().map(|(_, inode)| inode).collect())
+}
+
+// the open files of @pid in the order of fd
+pub fn read_fds(pid: i32) -> Result<Vec<FdEntry>, String> {
+  let dir = format!("/proc/{}/fd", pid);
+  let entries = match std::fs::read_dir(&dir) {
+    Ok(entries) => entries,
+    Err(e) => return Err(format!("{}: {}", dir, e)),
+  };
+  let mut fds = vec![];
+  for entry in entries.flatten() {
+    let fd = match entry
+      .file_name()
+      .to_str()
+      .and_then(|s| s.parse::<i32>().ok())
+    {
+      Some(fd) => fd,
+      None => continue,
+    };
+    // the fd may be closed while reading
+    let target = match std::fs::read_link(entry.path()) {
+      Ok(target) => target.to_string_lossy().into_owned(),
+      Err(_) => continue,
+    };
+    let mode = std::fs::metadata(entry.path()).ok().map(|m| m.mode());
+    let (flags, pos) = match std::fs::read_to_string(format!("/proc/{}/fdinfo/{}", pid, fd)) {
+      Ok(s) => parse_fdinfo(&s),
+      Err(_) => (None, None),
+    };
+    fds.push(FdEntry {
+      fd,
+      kind: classify(&target, mode),
+      target,
+      flags,
+      pos,
+    });
+  }
+  fds.sort_by_key(|f| f.fd);
+  Ok(fds)
+}
+
+// the lines of the --files report
+pub fn format_files(fds: &[FdEntry]) -> Vec<String> {
+  let flags: Vec<String> = fds
+    .iter()
+    .map(|f| match f.flags {
+      Some(flags) => decode_flags(flags),
+      None => String::from("-"),
+    })
+    .collect();
+  let flags_width = flags.iter().map(|s| s.len()).max().unwrap_or(0).max(5);
+  let mut lines = vec![format!(
+    "{:>4} {:<7} {:<fw$} {:>10} TARGET",
+    "FD",
+    "TYPE",
+    "FLAGS",
+    "POS",
+    fw = flags_width
+  )];
+  for (f, flags) in fds.iter().zip(flags.iter()) {
+    let pos = match f.pos {
+      Some(pos) => pos.to_string(),
+      None => String::from("-"),
+    };
+    lines.push(format!(
+      "{:>4} {:<7} {:<fw$} {:>10} {}",
+      f.fd,
+      f.kind,
+      flags,
+      pos,
+      f.target,
+      fw = flags_width
+    ));
+  }
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  use super::FdKind;
+
+  #[test]
+  fn classify_targets() {
+    let classify = super::classify;
+    assert_eq!(classify("socket:[81234]", None), FdKind::SOCK);
+    assert_eq!(classify("pipe:[5521]", None), FdKind::PIPE);
+    assert_eq!(classify("anon_inode:[eventfd]", None), FdKind::EVENTFD);
+    assert_eq!(classify("anon_inode:[eventpoll]", None), FdKind::A_INODE);
+    assert_eq!(classify("anon_inode:inotify", None), FdKind::A_INODE);
+    assert_eq!(
+      classify("/var/log/syslog", Some(libc::S_IFREG | 0o644)),
+      FdKind::REG
+    );
+    assert_eq!(
+      classify("/dev/null", Some(libc::S_IFCHR | 0o666)),
+      FdKind::CHR
+    );
+    assert_eq!(
+      classify("/dev/sda", Some(libc::S_IFBLK | 0o660)),
+      FdKind::BLK
+    );
+    assert_eq!(classify("/tmp", Some(libc::S_IFDIR | 0o1777)), FdKind::DIR);
+    assert_eq!(
+      classify("/run/initctl", Some(libc::S_IFIFO | 0o600)),
+      FdKind::FIFO
+    );
+    // deleted and not stat-able
+    assert_eq!(classify("/tmp/x (deleted)", None), FdKind::UNKNOWN);
+    assert_eq!(super::socket_inode("socket:[81234]"), Some(81234));
+    assert_eq!(super::socket_inode("pipe:[81234]"), None);
+  }
+
+  #[test]
+  fn fdinfo_and_flags() {
+    let info = "pos:\t4096\nflags:\t02100002\nmnt_id:\t25\nino:\t1234\n";
+    let (flags, pos) = super::parse_fdinfo(info);
+    assert_eq!(pos, Some(4096));
+    assert_eq!(flags, Some(0o2100002));
+    assert_eq!(super::parse_fdinfo("garbage"), (None, None));
+
+    let decode = |flags: i32| super::decode_flags(flags as u32);
+    assert_eq!(decode(libc::O_RDONLY), "O_RDONLY");
+    assert_eq!(
+      decode(libc::O_WRONLY | libc::O_APPEND | libc::O_CLOEXEC),
+      "O_WRONLY|O_APPEND|O_CLOEXEC"
+    );
+    assert_eq!(
+      decode(libc::O_RDWR | libc::O_NONBLOCK | super::O_LARGEFILE),
+      "O_RDWR|O_NONBLOCK|O_LARGEFILE"
+    );
+    assert_eq!(decode(libc::O_RDWR | libc::O_TMPFILE), "O_RDWR|O_TMPFILE");
+    assert_eq!(decode(libc::O_RDWR | 0o10000000000), "O_RDWR|010000000000");
+  }
+
+  #[test]
+  fn format_report() {
+    let fds = vec![
+      super::FdEntry {
+        fd: 0,
+        target: String::from("/dev/pts/0"),
+        kind: FdKind::CHR,
+        flags: Some(libc::O_RDWR as u32),
+        pos: Some(0),
+      },
+      super::FdEntry {
+        fd: 12,
+        target: String::from("socket:[81234]"),
+        kind: FdKind::SOCK,
+        flags: None,
+        pos: None,
+      },
+    ];
+    assert_eq!(
+      super::format_files(&fds),
+      vec![
+        "  FD TYPE    FLAGS         POS TARGET",
+        "   0 CHR     O_RDWR          0 /dev/pts/0",
+        "  12 SOCK    -               - socket:[81234]",
+      ]
+    );
+  }
+
+  #[test]
+  fn own_fds() {
+    let fds = super::read_fds(std::process::id() as i32).unwrap();
+    assert!(!fds.is_empty());
+    assert!(super::read_fds(0).is_err());
+  }
+}
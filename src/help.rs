@@ -0,0 +1,171 @@
+use crate::output;
+
+const USAGE: &str = "
+Usage:
+ ps [options]
+";
+
+const TRY_HELP: &str = "
+ Try 'ps --help <simple|list|output|threads|misc|all>'
+  or 'ps --help <s|l|o|t|m|a>'
+ for additional help text.
+";
+
+const SIMPLE: &str = "
+Basic options:
+ -A, -e               all processes
+ -a                   all with tty, except session leaders
+  a                   all with tty, including other users
+ -d                   all except session leaders
+  x                   processes without controlling ttys
+";
+
+const LIST: &str = "
+Selection by list:
+ -p, p, --pid <PID>   process id
+ -t, t, --tty <tty>   terminal
+     --port <port>    processes with a tcp or udp socket on the port
+     --pidns <pid|ns> processes in the pid namespace of the pid, or of the
+                      namespace inode, with the pids seen in it; other
+                      selections narrow it
+
+  The selection options take as their argument either:
+    a comma-separated list e.g. '-p 1,2' or
+    a blank-separated list e.g. '-p \"1 2\"'
+";
+
+const OUTPUT: &str = "
+Output formats:
+ -f                   full-format, with -l or -j too
+ -j                   jobs format
+ -l                   long format
+ -c                   scheduler class format
+  f, --forest         ascii art process tree
+ -H                   show process hierarchy
+ -o, o, --format <format>
+                      user-defined format, col=NAME renames a column
+  s                   signal format
+ -M, -Z, Z            add a column of the security label
+  h, --no-headers     do not print header at all
+     --headers        repeat header lines, one per page
+     --cols, --columns, --width <num>
+                      set screen width
+     --rows, --lines <num>
+                      set screen height
+     --decode-signals show signal masks as names
+     --decode-caps    show capability sets as names
+     --json           a JSON array of objects
+     --ndjson         a JSON object per line
+                      JSON has times in seconds and sizes in bytes
+     --csv            comma-separated values, formatted like the text
+     --interval <secs>
+                      repeat every interval, with the rates of the interval
+                      (with --ndjson, not --json)
+     --count <num>    stop after the number of reports
+     --mem-summary[=user|comm]
+                      PSS, USS and swap of the processes by user or command
+     --files <pid>    open files of a process, with their flags and position
+     --sockets        sockets of the selected processes, like ss -p
+     --limits <pid>   resource limits of a process, like prlimit
+";
+
+const THREADS: &str = "
+Show threads:
+  H                   as if they were processes
+ -L                   possibly with LWP column
+ -m, m                after processes
+ -T                   possibly with SPID column
+";
+
+const MISC: &str = "
+Miscellaneous options:
+  k, --sort           specify sort order as: [+|-]key[,[+|-]key[,...]]
+  O <keys>            BSD style sort order
+  L                   show format specifiers
+ -V, V, --version     display version information and exit
+  w                   wide output, twice for unlimited width
+
+     --help <simple|list|output|threads|misc|all>
+                      display help and exit
+";
+
+const FOOTER: &str = "
+For more details see ps(1).";
+
+// every format specifier with its header and description
+pub fn format_specifiers() -> String {
+  let mut s = String::new();
+  for fs in output::FORMAT_ARRAY.iter() {
+    s.push_str(&format!("{:<10} {:<8} {}\n", fs.spec, fs.head, fs.desc));
+  }
+  s
+}
+
+// the help text of a section given by --help.
+// the short usage for None or an unknown section.
+pub fn help_text(opt: Option<&str>) -> String {
+  let sections = match opt {
+    Some("s") | Some("simple") => vec![SIMPLE],
+    Some("l") | Some("list") => vec![LIST],
+    Some("o") | Some("output") => vec![OUTPUT],
+    Some("t") | Some("threads") => vec![THREADS],
+    Some("m") | Some("misc") => vec![MISC],
+    Some("a") | Some("all") => vec![SIMPLE, LIST, OUTPUT, THREADS, MISC],
+    _ => vec![TRY_HELP],
+  };
+  let mut s = String::from(USAGE);
+  for section in sections {
+    s.push_str(section);
+  }
+  if matches!(opt, Some("o") | Some("output")) {
+    s.push_str("\nFormat specifiers:\n");
+    s.push_str(&format_specifiers());
+  }
+  s.push_str(FOOTER);
+  s
+}
+
+// print the help and exit with @rc. it goes to stderr on errors.
+pub fn do_help(opt: Option<&str>, rc: i32) -> ! {
+  log::trace!("do_help called with opt:{:?}, rc:{}", opt, rc);
+  if rc == 0 {
+    println!("{}", help_text(opt));
+  } else {
+    eprintln!("{}", help_text(opt));
+  }
+  std::process::exit(rc);
+}
+
+pub fn do_version() -> ! {
+  println!("ps from rustyps {}", env!("CARGO_PKG_VERSION"));
+  std::process::exit(0);
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn help_sections() {
+    let usage = super::help_text(None);
+    assert!(usage.contains("Try 'ps --help"));
+    assert_eq!(super::help_text(Some("nosuchsection")), usage);
+    assert!(super::help_text(Some("threads")).contains("Show threads:"));
+    assert_eq!(
+      super::help_text(Some("t")),
+      super::help_text(Some("threads"))
+    );
+    let all = super::help_text(Some("all"));
+    for head in [
+      "Basic options:",
+      "Selection by list:",
+      "Output formats:",
+      "Miscellaneous options:",
+    ] {
+      assert!(all.contains(head));
+    }
+    // every specifier is listed in --help output
+    let output = super::help_text(Some("output"));
+    for fs in crate::output::FORMAT_ARRAY.iter() {
+      assert!(output.contains(&format!("\n{:<10} {:<8} ", fs.spec, fs.head)));
+    }
+  }
+}
@@ -1,7 +1,7 @@
 // find position of one of @dlims.
-pub fn strpbrk(msg: &String, delims: &str) -> Option<usize> {
+pub fn strpbrk(msg: &str, delims: &str) -> Option<usize> {
   for (ix, c) in msg.chars().enumerate() {
-    if let Some(_) = delims.find(c) {
+    if delims.find(c).is_some() {
       return Some(ix);
     }
   }
@@ -9,16 +9,43 @@ pub fn strpbrk(msg: &String, delims: &str) -> Option<usize> {
 }
 
 // find all positions of one of @dlims.
-pub fn strpbrk_all(msg: &String, delims: &str) -> Vec<usize> {
+pub fn strpbrk_all(msg: &str, delims: &str) -> Vec<usize> {
   let mut ret = vec![];
   for (ix, c) in msg.chars().enumerate() {
-    if let Some(p) = delims.find(c) {
+    if delims.find(c).is_some() {
       ret.push(ix);
     }
   }
   ret
 }
 
+// quote @s as a JSON string
+pub fn json_escape(s: &str) -> String {
+  let mut ret = String::from("\"");
+  for c in s.chars() {
+    match c {
+      '"' => ret.push_str("\\\""),
+      '\\' => ret.push_str("\\\\"),
+      '\n' => ret.push_str("\\n"),
+      '\r' => ret.push_str("\\r"),
+      '\t' => ret.push_str("\\t"),
+      c if (c as u32) < 0x20 => ret.push_str(&format!("\\u{:04x}", c as u32)),
+      c => ret.push(c),
+    }
+  }
+  ret.push('"');
+  ret
+}
+
+// quote @s as a CSV field of RFC 4180 if needed
+pub fn csv_escape(s: &str) -> String {
+  if s.contains(&[',', '"', '\r', '\n'][..]) {
+    format!("\"{}\"", s.replace('"', "\"\""))
+  } else {
+    String::from(s)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   #[test]
@@ -26,4 +53,15 @@ mod tests {
     let s = String::from("012,45:7:,");
     assert_eq!(super::strpbrk_all(&s, ",:"), vec![3, 6, 8, 9]);
   }
+
+  #[test]
+  fn test_escape() {
+    assert_eq!(
+      super::json_escape("a\"b\\c\n\u{1}"),
+      "\"a\\\"b\\\\c\\n\\u0001\""
+    );
+    assert_eq!(super::csv_escape("plain"), "plain");
+    assert_eq!(super::csv_escape("a,b"), "\"a,b\"");
+    assert_eq!(super::csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+  }
 }
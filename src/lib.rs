@@ -0,0 +1,25 @@
+#[macro_use]
+extern crate scan_fmt;
+
+pub mod argparser;
+pub mod caps;
+pub mod config;
+pub mod devname;
+pub mod display;
+pub mod files;
+pub mod help;
+pub mod helper;
+pub mod output;
+pub mod personality;
+pub mod pgrep;
+pub mod pidof;
+pub mod pmap;
+pub mod pstree;
+pub mod pwcache;
+pub mod readproc;
+pub mod sig;
+pub mod sockets;
+pub mod sortformat;
+pub mod sysinfo;
+pub mod top;
+pub mod wchan;
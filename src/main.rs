@@ -1,28 +1,28 @@
-#[macro_use]
-extern crate scan_fmt;
-
-pub mod argparser;
-pub mod display;
-pub mod helper;
-pub mod readproc;
+#![allow(clippy::needless_return)]
 
+use rustyps::{argparser, display, help, readproc};
 use simple_logger::SimpleLogger;
 
 #[derive(Debug)]
 pub struct Ps {
   parser: argparser::PsParser,
+  needs: u64,
 }
 
 impl Ps {
   fn new() -> Ps {
     Ps {
       parser: argparser::PsParser::from(std::env::args()),
+      needs: 0,
     }
   }
   pub fn run(&mut self) -> i32 {
     match self.parser.parse() {
       Ok(()) => log::trace!("success parse"),
-      Err(msg) => println!("Err: {}", msg),
+      Err(msg) => {
+        eprintln!("error: {}", msg);
+        help::do_help(None, 1);
+      }
     }
     self.arg_check_conflicts();
 
@@ -30,22 +30,45 @@ impl Ps {
     self.init_output();
     self.lists_and_needs();
 
-    match display::simple_spew(&self.parser) {
+    match display::simple_spew(&self.parser, self.needs) {
       Ok(()) => log::trace!("simple_spew finish"),
       Err(msg) => println!("{}", msg),
     }
     return 0;
   }
 
-  // XXX
+  // exit with an error if options conflict
   pub fn arg_check_conflicts(&mut self) {
-    return;
+    if let Err(msg) = self.parser.arg_check_conflicts() {
+      eprintln!("error: {}", msg);
+      std::process::exit(1);
+    }
+  }
+  // apply PS_FORMAT and the config file, and set up the output
+  pub fn init_output(&mut self) {
+    let ps_format = std::env::var("PS_FORMAT").ok();
+    if let Err(msg) = self.parser.apply_defaults(ps_format.as_deref()) {
+      eprintln!("error: {}", msg);
+      std::process::exit(1);
+    }
   }
-  // XXX
-  pub fn init_output(&mut self) {}
 
-  // XXX
-  pub fn lists_and_needs(&mut self) {}
+  // collect PROC_FILL* flags needed by the columns
+  pub fn lists_and_needs(&mut self) {
+    self.needs = readproc::PROC_FILLSTAT;
+    for node in self.parser.format_list.iter() {
+      self.needs |= node.need;
+    }
+    for node in self.parser.sort_list.iter() {
+      self.needs |= node.need;
+    }
+    if self.parser.mem_summary.is_some() {
+      self.needs |= readproc::PROC_FILLSMAPS;
+    }
+    if self.parser.pidns.is_some() {
+      self.needs |= readproc::PROC_FILLSTATUS | readproc::PROC_FILLNS;
+    }
+  }
 }
 
 fn main() {
@@ -57,16 +80,14 @@ fn main() {
   // XXX have to register atexit func?
   /* */
 
-  let myname = std::env::current_exe();
+  let _myname = std::env::current_exe();
   //log::trace!("{:?}", myname);
 
   // XXX must set sighandlers
-  /* */
+  // exit quietly when the reader of a pipe goes away, like `ps | head`
+  unsafe {
+    libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+  }
   let mut ps = Ps::new();
   ps.run();
 }
-
-pub fn do_help(opt: String, rc: i32) {
-  log::trace!("do_help called with opt:{}, rc:{}", opt, rc);
-  std::process::exit(rc);
-}
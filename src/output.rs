@@ -0,0 +1,1212 @@
+use std::cmp::Ordering;
+
+use crate::caps;
+use crate::devname;
+use crate::helper;
+use crate::pwcache;
+use crate::readproc;
+use crate::readproc::{Resource, RlimValue, PROCT};
+use crate::sig;
+use crate::sysinfo;
+use crate::wchan;
+
+// PROC_FILL* flags needed by each column
+const ARG: u64 = readproc::PROC_FILLARG;
+const STAT: u64 = readproc::PROC_FILLSTAT;
+const STA: u64 = readproc::PROC_FILLSTAT | readproc::PROC_FILLSTATUS;
+const WCH: u64 = readproc::PROC_FILLSTAT | readproc::PROC_FILLWCHAN;
+const IO: u64 = readproc::PROC_FILLSTAT | readproc::PROC_FILLIO;
+const SMP: u64 = readproc::PROC_FILLSTAT | readproc::PROC_FILLSMAPS;
+const FDS: u64 = readproc::PROC_FILLSTAT | readproc::PROC_FILLFDS;
+const LIM: u64 = readproc::PROC_FILLSTAT | readproc::PROC_FILLLIMITS;
+const PFD: u64 = FDS | LIM; // %fd
+const ATR: u64 = readproc::PROC_FILLSTAT | readproc::PROC_FILLATTR;
+const NS: u64 = readproc::PROC_FILLSTAT | readproc::PROC_FILLNS;
+
+// column flags
+pub const CF_LEFT: u32 = 0x1; // left-justify the value
+pub const CF_RIGHT: u32 = 0x2; // right-justify the value
+pub const CF_UNLIMITED: u32 = 0x4; // the value can be longer than the width
+pub const CF_SIGNAL: u32 = 0x8; // signal mask
+pub const CF_FOREST: u32 = 0x10; // command name, prefixed by the forest art
+
+// a value of a column. it keeps its type for sorting and machine-readable output,
+// and `Display` gives the text of the normal output.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Int(i64),
+  Str(String),
+  Time(u64),  // seconds, shown as [DD-]HH:MM:SS
+  Hex(u64),   // shown as 16 digits
+  Float(f64), // shown with a decimal
+  Bytes(u64), // shown with a unit like "1.5M"
+  Unlimited,  // a resource limit, above any number
+  None,       // shown as "-"
+}
+
+impl std::fmt::Display for Value {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Value::Int(n) => write!(f, "{}", n),
+      Value::Str(s) => write!(f, "{}", s),
+      Value::Time(t) => {
+        let (dd, hh, mm, ss) = (t / 86400, t / 3600 % 24, t / 60 % 60, t % 60);
+        if dd != 0 {
+          write!(f, "{}-{:02}:{:02}:{:02}", dd, hh, mm, ss)
+        } else {
+          write!(f, "{:02}:{:02}:{:02}", hh, mm, ss)
+        }
+      }
+      Value::Hex(n) => write!(f, "{:016x}", n),
+      Value::Float(x) => write!(f, "{:.1}", x),
+      Value::Bytes(n) => write!(f, "{}", scale_bytes(*n)),
+      Value::Unlimited => write!(f, "unlimited"),
+      Value::None => write!(f, "-"),
+    }
+  }
+}
+
+impl Value {
+  pub fn compare(&self, other: &Value) -> Ordering {
+    match (self, other) {
+      (Value::Int(a), Value::Int(b)) => a.cmp(b),
+      (Value::Str(a), Value::Str(b)) => a.cmp(b),
+      (Value::Time(a), Value::Time(b)) => a.cmp(b),
+      (Value::Hex(a), Value::Hex(b)) => a.cmp(b),
+      (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+      (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+      (Value::None, Value::None) => Ordering::Equal,
+      (Value::None, _) => Ordering::Less,
+      (_, Value::None) => Ordering::Greater,
+      (Value::Unlimited, Value::Unlimited) => Ordering::Equal,
+      (Value::Unlimited, _) => Ordering::Greater,
+      (_, Value::Unlimited) => Ordering::Less,
+      (a, b) => a.to_string().cmp(&b.to_string()),
+    }
+  }
+
+  // times are in seconds and sizes in bytes, unlike the text and CSV
+  pub fn to_json(&self) -> String {
+    match self {
+      Value::Int(n) => n.to_string(),
+      Value::Time(t) => t.to_string(),
+      Value::Float(f) if f.is_finite() => f.to_string(),
+      Value::Float(_) => String::from("null"),
+      Value::Bytes(n) => n.to_string(),
+      Value::None => String::from("null"),
+      v => helper::json_escape(&v.to_string()),
+    }
+  }
+}
+
+// bytes in at most 5 columns, with a binary unit from 1024 on
+pub fn scale_bytes(n: u64) -> String {
+  if n < 1024 {
+    return n.to_string();
+  }
+  let mut val = n as f64;
+  for unit in ["K", "M", "G", "T", "P"] {
+    val /= 1024.0;
+    if val < 10.0 {
+      return format!("{:.1}{}", val, unit);
+    }
+    // not "1024K", which is 1.0M
+    if val.round() < 1024.0 {
+      return format!("{:.0}{}", val, unit);
+    }
+  }
+  format!("{:.0}E", val / 1024.0)
+}
+
+// an entry of the format registry
+#[derive(Debug)]
+pub struct FormatStruct {
+  pub spec: &'static str,      // name used in -o
+  pub head: &'static str,      // default header
+  pub pr: fn(&PROCT) -> Value, // printer
+  pub width: usize,            // default width
+  pub need: u64,               // PROC_FILL* flags needed by `pr`
+  pub flags: u32,              // CF_* flags
+  pub desc: &'static str,      // shown in --help output
+}
+
+fn pr_pid(p: &PROCT) -> Value {
+  Value::Int(p.tgid as i64)
+}
+
+// lwp, spid, tid
+fn pr_tid(p: &PROCT) -> Value {
+  Value::Int(p.tid as i64)
+}
+
+// nlwp, thcount
+fn pr_nlwp(p: &PROCT) -> Value {
+  Value::Int(p.nlwp as i64)
+}
+
+fn pr_ppid(p: &PROCT) -> Value {
+  Value::Int(p.ppid as i64)
+}
+
+// pgid, pgrp
+fn pr_pgid(p: &PROCT) -> Value {
+  Value::Int(p.pgrp as i64)
+}
+
+// sid, sess, session
+fn pr_sid(p: &PROCT) -> Value {
+  Value::Int(p.session as i64)
+}
+
+// f, flags: 1 if forked but not exec'd, 4 if it used super-user privileges
+fn pr_flag(p: &PROCT) -> Value {
+  Value::Int(((p.flags >> 6) & 0x7) as i64)
+}
+
+// addr: the kernel no longer shows it, so "-" like procps
+fn pr_addr(_p: &PROCT) -> Value {
+  Value::None
+}
+
+// uid, euid
+fn pr_euid(p: &PROCT) -> Value {
+  Value::Int(p.euid as i64)
+}
+
+// user, euser, uname: name of the effective user
+fn pr_euser(p: &PROCT) -> Value {
+  Value::Str(pwcache::user_from_uid(p.euid))
+}
+
+// cmd, args, command
+fn pr_args(p: &PROCT) -> Value {
+  let mut s = if p.cmdline.is_empty() {
+    format!("[{}]", p.cmd)
+  } else {
+    p.cmdline.join(" ")
+  };
+  if p.state == readproc::ProcState::ZOMBIE {
+    s.push_str(" <defunct>");
+  }
+  Value::Str(s)
+}
+
+// comm, ucmd, ucomm
+fn pr_comm(p: &PROCT) -> Value {
+  Value::Str(p.cmd.clone())
+}
+
+// rss, rsz, rssize: resident set size in KiB
+fn pr_rss(p: &PROCT) -> Value {
+  Value::Int(p.rss * sysinfo::page_size() as i64 / 1024)
+}
+
+// sz: virtual memory size in pages
+fn pr_sz(p: &PROCT) -> Value {
+  Value::Int((p.vsize / sysinfo::page_size()) as i64)
+}
+
+// vsz, vsize: virtual memory size in KiB
+fn pr_vsz(p: &PROCT) -> Value {
+  Value::Int((p.vsize / 1024) as i64)
+}
+
+// cumulative CPU time
+fn pr_time(p: &PROCT) -> Value {
+  Value::Time((p.utime + p.stime) / sysinfo::hertz())
+}
+
+// stime, start_time: when the process started
+fn pr_stime(p: &PROCT) -> Value {
+  let start = sysinfo::boot_time() + p.start_time / sysinfo::hertz();
+  let now = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs());
+  Value::Str(format_stime(start as i64, now as i64))
+}
+
+const MONTHS: [&str; 12] = [
+  "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+fn localtime(t: i64) -> libc::tm {
+  let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+  unsafe { libc::localtime_r(&(t as libc::time_t), &mut tm) };
+  tm
+}
+
+// the local time of @start like procps: "HH:MM" within a day of @now,
+// "MonDD" within a year, else the year
+pub fn format_stime(start: i64, now: i64) -> String {
+  let tm = localtime(start);
+  let age = now - start;
+  if age < 24 * 3600 {
+    format!("{:02}:{:02}", tm.tm_hour, tm.tm_min)
+  } else if age < 365 * 24 * 3600 {
+    format!("{}{:02}", MONTHS[tm.tm_mon as usize], tm.tm_mday)
+  } else {
+    (tm.tm_year + 1900).to_string()
+  }
+}
+
+// tty, tt, tname
+fn pr_tty8(p: &PROCT) -> Value {
+  Value::Str(devname::dev_to_tty(p.tty, devname::ABBREV_DEV))
+}
+
+// s, state
+fn pr_s(p: &PROCT) -> Value {
+  Value::Str(p.state.to_string())
+}
+
+// stat: state with BSD flags
+fn pr_stat(p: &PROCT) -> Value {
+  let mut s = p.state.to_string();
+  if p.nice < 0 {
+    s.push('<'); // high priority
+  }
+  if p.nice > 0 {
+    s.push('N'); // low priority
+  }
+  if p.vm_lock != 0 {
+    s.push('L'); // has pages locked into memory
+  }
+  if p.session == p.tgid {
+    s.push('s'); // session leader
+  }
+  if p.nlwp > 1 {
+    s.push('l'); // multi-threaded
+  }
+  if p.pgrp == p.tpgid {
+    s.push('+'); // in the foreground process group
+  }
+  Value::Str(s)
+}
+
+// class, cls, policy
+fn pr_class(p: &PROCT) -> Value {
+  let s = match p.sched {
+    0 => "TS",  // SCHED_OTHER
+    1 => "FF",  // SCHED_FIFO
+    2 => "RR",  // SCHED_RR
+    3 => "B",   // SCHED_BATCH
+    4 => "ISO", // SCHED_ISO, reserved
+    5 => "IDL", // SCHED_IDLE
+    6 => "DLN", // SCHED_DEADLINE
+    _ => "?",
+  };
+  Value::Str(String::from(s))
+}
+
+// numeric scheduling policy
+fn pr_sched(p: &PROCT) -> Value {
+  Value::Int(p.sched as i64)
+}
+
+// realtime priority, "-" for SCHED_OTHER
+fn pr_rtprio(p: &PROCT) -> Value {
+  if p.sched == 0 {
+    return Value::None;
+  }
+  Value::Int(p.rtprio as i64)
+}
+
+// ni, nice. "-" for any policy but SCHED_OTHER, like procps, even BATCH and IDLE.
+// the policy is -1 (u64::MAX) if unknown.
+fn pr_nice(p: &PROCT) -> Value {
+  if p.sched != 0 && p.sched != u64::MAX {
+    return Value::None;
+  }
+  Value::Int(p.nice)
+}
+
+// the raw `priority` of stat (-100..39)
+fn pr_priority(p: &PROCT) -> Value {
+  Value::Int(p.priority)
+}
+
+// intpri, opri (-40..99)
+fn pr_opri(p: &PROCT) -> Value {
+  Value::Int(60 + p.priority)
+}
+
+// pri, used by -l and -c (0..139, larger is higher)
+fn pr_pri(p: &PROCT) -> Value {
+  Value::Int(39 - p.priority)
+}
+
+// pri_api, match up with the realtime API (-40..99)
+fn pr_pri_api(p: &PROCT) -> Value {
+  Value::Int(-1 - p.priority)
+}
+
+// pri_bar, realtime priorities are negative (-99..40)
+fn pr_pri_bar(p: &PROCT) -> Value {
+  Value::Int(p.priority + 1)
+}
+
+// pri_baz, the kernel's `prio` (0..139)
+fn pr_pri_baz(p: &PROCT) -> Value {
+  Value::Int(p.priority + 100)
+}
+
+// pri_foo, match up with nice values of normal processes (-120..19)
+fn pr_pri_foo(p: &PROCT) -> Value {
+  Value::Int(p.priority - 20)
+}
+
+// processor last executed on
+fn pr_psr(p: &PROCT) -> Value {
+  Value::Int(p.processor as i64)
+}
+
+// pending, sig
+fn pr_sig(p: &PROCT) -> Value {
+  Value::Hex(p.signal)
+}
+
+// sig_pend: pending signals of the thread itself
+fn pr_sigpnd(p: &PROCT) -> Value {
+  Value::Hex(p.sigpnd)
+}
+
+// blocked, sigmask, sig_block
+fn pr_blocked(p: &PROCT) -> Value {
+  Value::Hex(p.blocked)
+}
+
+// ignored, sigignore, sig_ignore
+fn pr_sigignore(p: &PROCT) -> Value {
+  Value::Hex(p.sigignore)
+}
+
+// caught, sigcatch, sig_catch
+fn pr_sigcatch(p: &PROCT) -> Value {
+  Value::Hex(p.sigcatch)
+}
+
+fn pr_capinh(p: &PROCT) -> Value {
+  Value::Hex(p.cap_inh)
+}
+
+fn pr_capprm(p: &PROCT) -> Value {
+  Value::Hex(p.cap_prm)
+}
+
+fn pr_capeff(p: &PROCT) -> Value {
+  Value::Hex(p.cap_eff)
+}
+
+fn pr_capbnd(p: &PROCT) -> Value {
+  Value::Hex(p.cap_bnd)
+}
+
+fn pr_capamb(p: &PROCT) -> Value {
+  Value::Hex(p.cap_amb)
+}
+
+// the printer of a signal column that prints names instead of hex, for --decode-signals
+pub fn decoded_signals_pr(spec: &str) -> Option<fn(&PROCT) -> Value> {
+  let pr: fn(&PROCT) -> Value = match spec {
+    "pending" | "sig" => |p| Value::Str(sig::mask_to_names(p.signal)),
+    "sig_pend" => |p| Value::Str(sig::mask_to_names(p.sigpnd)),
+    "blocked" | "sigmask" | "sig_block" => |p| Value::Str(sig::mask_to_names(p.blocked)),
+    "ignored" | "sigignore" | "sig_ignore" => |p| Value::Str(sig::mask_to_names(p.sigignore)),
+    "caught" | "sigcatch" | "sig_catch" => |p| Value::Str(sig::mask_to_names(p.sigcatch)),
+    _ => return None,
+  };
+  Some(pr)
+}
+
+// the printer of a capability column that prints names instead of hex, for --decode-caps
+pub fn decoded_caps_pr(spec: &str) -> Option<fn(&PROCT) -> Value> {
+  let pr: fn(&PROCT) -> Value = match spec {
+    "capinh" => |p| Value::Str(caps::mask_to_names(p.cap_inh)),
+    "capprm" => |p| Value::Str(caps::mask_to_names(p.cap_prm)),
+    "capeff" => |p| Value::Str(caps::mask_to_names(p.cap_eff)),
+    "capbnd" => |p| Value::Str(caps::mask_to_names(p.cap_bnd)),
+    "capamb" => |p| Value::Str(caps::mask_to_names(p.cap_amb)),
+    _ => return None,
+  };
+  Some(pr)
+}
+
+// label, the LSM security context
+fn pr_label(p: &PROCT) -> Value {
+  if p.label.is_empty() {
+    Value::None
+  } else {
+    Value::Str(p.label.clone())
+  }
+}
+
+fn pr_seccomp(p: &PROCT) -> Value {
+  match p.seccomp {
+    Some(0) => Value::Str(String::from("disabled")),
+    Some(1) => Value::Str(String::from("strict")),
+    Some(2) => Value::Str(String::from("filter")),
+    Some(n) => Value::Int(n as i64),
+    None => Value::None,
+  }
+}
+
+// no_new_privs
+fn pr_nnp(p: &PROCT) -> Value {
+  match p.no_new_privs {
+    Some(n) => Value::Int(n as i64),
+    None => Value::None,
+  }
+}
+
+// an id in the innermost pid namespace of the process, "-" if the kernel
+// does not show it
+fn pr_ns_id(ids: &[i32]) -> Value {
+  match ids.last() {
+    Some(id) => Value::Int(*id as i64),
+    None => Value::None,
+  }
+}
+
+fn pr_nspid(p: &PROCT) -> Value {
+  pr_ns_id(&p.nspid)
+}
+
+fn pr_nstgid(p: &PROCT) -> Value {
+  pr_ns_id(&p.nstgid)
+}
+
+fn pr_nspgid(p: &PROCT) -> Value {
+  pr_ns_id(&p.nspgid)
+}
+
+fn pr_nssid(p: &PROCT) -> Value {
+  pr_ns_id(&p.nssid)
+}
+
+// inode of the pid namespace
+fn pr_pidns(p: &PROCT) -> Value {
+  match p.ns[readproc::NS_PID] {
+    0 => Value::None,
+    ino => Value::Int(ino as i64),
+  }
+}
+
+// symbolic wait channel, "-" if not waiting
+fn pr_wchan(p: &PROCT) -> Value {
+  if p.wchan_name.is_empty() {
+    Value::None
+  } else {
+    Value::Str(String::from(wchan::strip_wchan(&p.wchan_name)))
+  }
+}
+
+// numeric wait channel. recent kernels hide the address.
+fn pr_nwchan(p: &PROCT) -> Value {
+  if p.wchan == 0 {
+    Value::None
+  } else {
+    Value::Str(format!("{:x}", p.wchan))
+  }
+}
+
+// %cpu, pcpu: over the last interval with --interval, else over the lifetime
+// like procps. it can be over 100 for multi-threaded processes.
+fn pr_pcpu(p: &PROCT) -> Value {
+  if let Some(rates) = &p.rates {
+    return Value::Float(rates.pcpu);
+  }
+  let hz = sysinfo::hertz();
+  let alive = sysinfo::seconds_since_boot() - (p.start_time / hz) as f64;
+  if alive <= 0.0 {
+    return Value::Float(0.0);
+  }
+  let pcpu = (p.utime + p.stime) as f64 * 100.0 / hz as f64 / alive;
+  Value::Float(pcpu.min(999.9))
+}
+
+// c: integer %CPU, at most 99 like procps
+fn pr_c(p: &PROCT) -> Value {
+  match pr_pcpu(p) {
+    Value::Float(pcpu) => Value::Int((pcpu as i64).min(99)),
+    val => val,
+  }
+}
+
+// min_flt, minflt
+fn pr_minflt(p: &PROCT) -> Value {
+  Value::Int(p.min_flt as i64)
+}
+
+// maj_flt, majflt
+fn pr_majflt(p: &PROCT) -> Value {
+  Value::Int(p.maj_flt as i64)
+}
+
+// rates of --interval, "-" without it
+fn pr_rate(p: &PROCT, rate: fn(&readproc::Rates) -> f64) -> Value {
+  match &p.rates {
+    Some(rates) => Value::Float(rate(rates)),
+    None => Value::None,
+  }
+}
+
+fn pr_minflt_rate(p: &PROCT) -> Value {
+  pr_rate(p, |r| r.min_flt)
+}
+
+fn pr_majflt_rate(p: &PROCT) -> Value {
+  pr_rate(p, |r| r.maj_flt)
+}
+
+fn pr_nvcsw_rate(p: &PROCT) -> Value {
+  pr_rate(p, |r| r.nvcsw)
+}
+
+fn pr_nivcsw_rate(p: &PROCT) -> Value {
+  pr_rate(p, |r| r.nivcsw)
+}
+
+// bytes per second, "-" if /proc/<pid>/io is not readable
+fn pr_io_rate(p: &PROCT, rate: fn(&readproc::Rates) -> Option<f64>) -> Value {
+  match p.rates.as_ref().and_then(rate) {
+    Some(bytes) => Value::Bytes(bytes.round() as u64),
+    None => Value::None,
+  }
+}
+
+// counters of /proc/<pid>/io, "-" if it is not readable.
+// it needs the same permission as ptrace.
+fn pr_io(p: &PROCT, val: fn(&readproc::ProcIo) -> Value) -> Value {
+  match &p.io {
+    Some(io) => val(io),
+    None => Value::None,
+  }
+}
+
+fn pr_rchar(p: &PROCT) -> Value {
+  pr_io(p, |io| Value::Bytes(io.rchar))
+}
+
+fn pr_wchar(p: &PROCT) -> Value {
+  pr_io(p, |io| Value::Bytes(io.wchar))
+}
+
+fn pr_syscr(p: &PROCT) -> Value {
+  pr_io(p, |io| Value::Int(io.syscr as i64))
+}
+
+fn pr_syscw(p: &PROCT) -> Value {
+  pr_io(p, |io| Value::Int(io.syscw as i64))
+}
+
+fn pr_rbytes(p: &PROCT) -> Value {
+  pr_io(p, |io| Value::Bytes(io.read_bytes))
+}
+
+fn pr_wbytes(p: &PROCT) -> Value {
+  pr_io(p, |io| Value::Bytes(io.write_bytes))
+}
+
+fn pr_cwbytes(p: &PROCT) -> Value {
+  pr_io(p, |io| Value::Bytes(io.cancelled_write_bytes))
+}
+
+fn pr_read_rate(p: &PROCT) -> Value {
+  pr_io_rate(p, |r| r.read_bytes)
+}
+
+fn pr_write_rate(p: &PROCT) -> Value {
+  pr_io_rate(p, |r| r.write_bytes)
+}
+
+// columns of smaps_rollup in KiB, "-" if it is not readable
+fn pr_smaps(p: &PROCT, kb: fn(&readproc::SmapsRollup) -> u64) -> Value {
+  match &p.smaps {
+    Some(smaps) => Value::Int(kb(smaps) as i64),
+    None => Value::None,
+  }
+}
+
+fn pr_pss(p: &PROCT) -> Value {
+  pr_smaps(p, |s| s.pss)
+}
+
+fn pr_uss(p: &PROCT) -> Value {
+  pr_smaps(p, |s| s.uss)
+}
+
+fn pr_swap(p: &PROCT) -> Value {
+  pr_smaps(p, |s| s.swap)
+}
+
+fn pr_swappss(p: &PROCT) -> Value {
+  pr_smaps(p, |s| s.swap_pss)
+}
+
+fn pr_anon(p: &PROCT) -> Value {
+  pr_smaps(p, |s| s.anonymous)
+}
+
+// resident file mappings, from status
+fn pr_rssfile(p: &PROCT) -> Value {
+  Value::Int(p.rss_file as i64)
+}
+
+// open file descriptors, "-" if /proc/<pid>/fd is not readable
+fn pr_nfd(p: &PROCT) -> Value {
+  match p.nfd {
+    Some(n) => Value::Int(n as i64),
+    None => Value::None,
+  }
+}
+
+// open file descriptors in percent of the soft RLIMIT_NOFILE
+fn pr_pfd(p: &PROCT) -> Value {
+  let limit = p.limits.as_ref().map(|l| l.get(Resource::NOFILE).soft);
+  match (p.nfd, limit) {
+    (Some(n), Some(RlimValue::LIMIT(limit))) if limit > 0 => {
+      Value::Float(n as f64 * 100.0 / limit as f64)
+    }
+    _ => Value::None,
+  }
+}
+
+// the soft limit of a resource, "-" if it is not known
+fn pr_limit(p: &PROCT, res: Resource) -> Value {
+  let soft = match &p.limits {
+    Some(limits) => limits.get(res).soft,
+    None => return Value::None,
+  };
+  match (soft, res) {
+    (RlimValue::UNLIMITED, _) => Value::Unlimited,
+    (RlimValue::UNKNOWN, _) => Value::None,
+    (RlimValue::LIMIT(n), Resource::CORE | Resource::MEMLOCK | Resource::STACK) => Value::Bytes(n),
+    (RlimValue::LIMIT(n), _) => Value::Int(n as i64),
+  }
+}
+
+fn pr_nofile(p: &PROCT) -> Value {
+  pr_limit(p, Resource::NOFILE)
+}
+
+fn pr_nproc(p: &PROCT) -> Value {
+  pr_limit(p, Resource::NPROC)
+}
+
+fn pr_core(p: &PROCT) -> Value {
+  pr_limit(p, Resource::CORE)
+}
+
+fn pr_memlock(p: &PROCT) -> Value {
+  pr_limit(p, Resource::MEMLOCK)
+}
+
+fn pr_stack(p: &PROCT) -> Value {
+  pr_limit(p, Resource::STACK)
+}
+
+// the format registry. keep it sorted by `spec`.
+#[rustfmt::skip]
+pub static FORMAT_ARRAY: &[FormatStruct] = &[
+  FormatStruct { spec: "%cpu",       head: "%CPU",    pr: pr_pcpu,      width: 4,  need: STAT, flags: CF_RIGHT,                           desc: "CPU usage, over the interval with --interval" },
+  FormatStruct { spec: "%fd",        head: "%FD",     pr: pr_pfd,       width: 4,  need: PFD,  flags: CF_RIGHT,                           desc: "open files in percent of the RLIMIT_NOFILE soft limit" },
+  FormatStruct { spec: "addr",       head: "ADDR",    pr: pr_addr,      width: 4,  need: 0,    flags: CF_RIGHT,                           desc: "address of the process in the kernel, always -" },
+  FormatStruct { spec: "anon",       head: "ANON",    pr: pr_anon,      width: 6,  need: SMP,  flags: CF_RIGHT,                           desc: "anonymous memory in KiB" },
+  FormatStruct { spec: "args",       head: "COMMAND", pr: pr_args,      width: 27, need: ARG,  flags: CF_LEFT | CF_UNLIMITED | CF_FOREST, desc: "command with all its arguments" },
+  FormatStruct { spec: "blocked",    head: "BLOCKED", pr: pr_blocked,   width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the blocked signals" },
+  FormatStruct { spec: "c",          head: "C",       pr: pr_c,         width: 2,  need: STAT, flags: CF_RIGHT,                           desc: "integer percent of CPU usage, at most 99" },
+  FormatStruct { spec: "capamb",     head: "CAPAMB",  pr: pr_capamb,    width: 16, need: STA,  flags: CF_RIGHT,                           desc: "ambient capabilities" },
+  FormatStruct { spec: "capbnd",     head: "CAPBND",  pr: pr_capbnd,    width: 16, need: STA,  flags: CF_RIGHT,                           desc: "capability bounding set" },
+  FormatStruct { spec: "capeff",     head: "CAPEFF",  pr: pr_capeff,    width: 16, need: STA,  flags: CF_RIGHT,                           desc: "effective capabilities" },
+  FormatStruct { spec: "capinh",     head: "CAPINH",  pr: pr_capinh,    width: 16, need: STA,  flags: CF_RIGHT,                           desc: "inheritable capabilities" },
+  FormatStruct { spec: "capprm",     head: "CAPPRM",  pr: pr_capprm,    width: 16, need: STA,  flags: CF_RIGHT,                           desc: "permitted capabilities" },
+  FormatStruct { spec: "caught",     head: "CAUGHT",  pr: pr_sigcatch,  width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the caught signals" },
+  FormatStruct { spec: "class",      head: "CLS",     pr: pr_class,     width: 3,  need: STAT, flags: CF_LEFT,                            desc: "scheduling class of the process" },
+  FormatStruct { spec: "cls",        head: "CLS",     pr: pr_class,     width: 3,  need: STAT, flags: CF_LEFT,                            desc: "scheduling class of the process" },
+  FormatStruct { spec: "cmd",        head: "CMD",     pr: pr_args,      width: 27, need: ARG,  flags: CF_LEFT | CF_UNLIMITED | CF_FOREST, desc: "command with all its arguments" },
+  FormatStruct { spec: "comm",       head: "COMMAND", pr: pr_comm,      width: 15, need: STAT, flags: CF_LEFT | CF_FOREST,                desc: "command name, the executable name only" },
+  FormatStruct { spec: "command",    head: "COMMAND", pr: pr_args,      width: 27, need: ARG,  flags: CF_LEFT | CF_UNLIMITED | CF_FOREST, desc: "command with all its arguments" },
+  FormatStruct { spec: "core",       head: "CORE",    pr: pr_core,      width: 9,  need: LIM,  flags: CF_RIGHT,                           desc: "soft limit of the core file size" },
+  FormatStruct { spec: "cswch/s",    head: "CSWCH/s", pr: pr_nvcsw_rate, width: 7, need: STA,  flags: CF_RIGHT,                           desc: "voluntary context switches per second, with --interval" },
+  FormatStruct { spec: "cwbytes",    head: "CWBYTES", pr: pr_cwbytes,   width: 7,  need: IO,   flags: CF_RIGHT,                           desc: "bytes of cancelled writes to storage" },
+  FormatStruct { spec: "euid",       head: "EUID",    pr: pr_euid,      width: 5,  need: 0,    flags: CF_RIGHT,                           desc: "effective user ID" },
+  FormatStruct { spec: "euser",      head: "EUSER",   pr: pr_euser,     width: 8,  need: 0,    flags: CF_LEFT,                            desc: "effective user name" },
+  FormatStruct { spec: "f",          head: "F",       pr: pr_flag,      width: 1,  need: STAT, flags: CF_RIGHT,                           desc: "process flags, 1 forked but not exec'd, 4 used super-user privileges" },
+  FormatStruct { spec: "flags",      head: "F",       pr: pr_flag,      width: 1,  need: STAT, flags: CF_RIGHT,                           desc: "process flags, 1 forked but not exec'd, 4 used super-user privileges" },
+  FormatStruct { spec: "ignored",    head: "IGNORED", pr: pr_sigignore, width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the ignored signals" },
+  FormatStruct { spec: "intpri",     head: "PRI",     pr: pr_opri,      width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "priority, higher number means lower priority" },
+  FormatStruct { spec: "label",      head: "LABEL",   pr: pr_label,     width: 31, need: ATR,  flags: CF_LEFT,                            desc: "security label, like SELinux context or AppArmor profile" },
+  FormatStruct { spec: "lwp",        head: "LWP",     pr: pr_tid,       width: 5,  need: 0,    flags: CF_RIGHT,                           desc: "light weight process (thread) ID" },
+  FormatStruct { spec: "majflt",     head: "MAJFLT",  pr: pr_majflt,    width: 6,  need: STAT, flags: CF_RIGHT,                           desc: "number of major page faults" },
+  FormatStruct { spec: "majflt/s",   head: "MAJFLT/s", pr: pr_majflt_rate, width: 8, need: STAT, flags: CF_RIGHT,                         desc: "major page faults per second, with --interval" },
+  FormatStruct { spec: "memlock",    head: "MEMLOCK", pr: pr_memlock,   width: 9,  need: LIM,  flags: CF_RIGHT,                           desc: "soft limit of the locked memory" },
+  FormatStruct { spec: "minflt",     head: "MINFLT",  pr: pr_minflt,    width: 6,  need: STAT, flags: CF_RIGHT,                           desc: "number of minor page faults" },
+  FormatStruct { spec: "minflt/s",   head: "MINFLT/s", pr: pr_minflt_rate, width: 8, need: STAT, flags: CF_RIGHT,                         desc: "minor page faults per second, with --interval" },
+  FormatStruct { spec: "nfd",        head: "NFD",     pr: pr_nfd,       width: 4,  need: FDS,  flags: CF_RIGHT,                           desc: "number of open file descriptors" },
+  FormatStruct { spec: "ni",         head: "NI",      pr: pr_nice,      width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "nice value" },
+  FormatStruct { spec: "nice",       head: "NI",      pr: pr_nice,      width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "nice value" },
+  FormatStruct { spec: "nlwp",       head: "NLWP",    pr: pr_nlwp,      width: 4,  need: STAT, flags: CF_RIGHT,                           desc: "number of threads in the process" },
+  FormatStruct { spec: "nnp",        head: "NNP",     pr: pr_nnp,       width: 3,  need: STA,  flags: CF_RIGHT,                           desc: "no_new_privs flag" },
+  FormatStruct { spec: "nofile",     head: "NOFILE",  pr: pr_nofile,    width: 6,  need: LIM,  flags: CF_RIGHT,                           desc: "soft limit of the open files" },
+  FormatStruct { spec: "nproc",      head: "NPROC",   pr: pr_nproc,     width: 6,  need: LIM,  flags: CF_RIGHT,                           desc: "soft limit of the processes of the user" },
+  FormatStruct { spec: "nspgid",     head: "NSPGID",  pr: pr_nspgid,    width: 6,  need: STA,  flags: CF_RIGHT,                           desc: "process group ID in the pid namespace of the process" },
+  FormatStruct { spec: "nspid",      head: "NSPID",   pr: pr_nspid,     width: 5,  need: STA,  flags: CF_RIGHT,                           desc: "thread ID in the pid namespace of the process" },
+  FormatStruct { spec: "nssid",      head: "NSSID",   pr: pr_nssid,     width: 5,  need: STA,  flags: CF_RIGHT,                           desc: "session ID in the pid namespace of the process" },
+  FormatStruct { spec: "nstgid",     head: "NSTGID",  pr: pr_nstgid,    width: 6,  need: STA,  flags: CF_RIGHT,                           desc: "process ID in the pid namespace of the process" },
+  FormatStruct { spec: "nvcswch/s",  head: "NVCSWCH/s", pr: pr_nivcsw_rate, width: 9, need: STA, flags: CF_RIGHT,                         desc: "involuntary context switches per second, with --interval" },
+  FormatStruct { spec: "nwchan",     head: "WCHAN",   pr: pr_nwchan,    width: 6,  need: STAT, flags: CF_RIGHT,                           desc: "address of the kernel function where the process sleeps" },
+  FormatStruct { spec: "opri",       head: "PRI",     pr: pr_opri,      width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "priority, higher number means lower priority" },
+  FormatStruct { spec: "pcpu",       head: "%CPU",    pr: pr_pcpu,      width: 4,  need: STAT, flags: CF_RIGHT,                           desc: "CPU usage, over the interval with --interval" },
+  FormatStruct { spec: "pending",    head: "PENDING", pr: pr_sig,       width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the pending signals of the process" },
+  FormatStruct { spec: "pgid",       head: "PGID",    pr: pr_pgid,      width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "process group ID" },
+  FormatStruct { spec: "pgrp",       head: "PGRP",    pr: pr_pgid,      width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "process group ID" },
+  FormatStruct { spec: "pid",        head: "PID",     pr: pr_pid,       width: 5,  need: 0,    flags: CF_RIGHT,                           desc: "process ID" },
+  FormatStruct { spec: "pidns",      head: "PIDNS",   pr: pr_pidns,     width: 10, need: NS,   flags: CF_RIGHT,                           desc: "inode number of the pid namespace" },
+  FormatStruct { spec: "policy",     head: "POL",     pr: pr_class,     width: 3,  need: STAT, flags: CF_LEFT,                            desc: "scheduling class of the process" },
+  FormatStruct { spec: "ppid",       head: "PPID",    pr: pr_ppid,      width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "parent process ID" },
+  FormatStruct { spec: "pri",        head: "PRI",     pr: pr_pri,       width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "priority, higher number means higher priority" },
+  FormatStruct { spec: "pri_api",    head: "API",     pr: pr_pri_api,   width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "priority as seen by the scheduling API" },
+  FormatStruct { spec: "pri_bar",    head: "BAR",     pr: pr_pri_bar,   width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "priority, -99 to 40" },
+  FormatStruct { spec: "pri_baz",    head: "BAZ",     pr: pr_pri_baz,   width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "priority, 0 to 139" },
+  FormatStruct { spec: "pri_foo",    head: "FOO",     pr: pr_pri_foo,   width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "priority, -120 to 19" },
+  FormatStruct { spec: "priority",   head: "PRI",     pr: pr_priority,  width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "priority as reported by the kernel" },
+  FormatStruct { spec: "psr",        head: "PSR",     pr: pr_psr,       width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "processor the process last ran on" },
+  FormatStruct { spec: "pss",        head: "PSS",     pr: pr_pss,       width: 6,  need: SMP,  flags: CF_RIGHT,                           desc: "proportional share of the resident memory in KiB" },
+  FormatStruct { spec: "rbytes",     head: "RBYTES",  pr: pr_rbytes,    width: 6,  need: IO,   flags: CF_RIGHT,                           desc: "bytes read from storage" },
+  FormatStruct { spec: "rbytes/s",   head: "RBYTES/s", pr: pr_read_rate, width: 8, need: IO,   flags: CF_RIGHT,                           desc: "bytes read from storage per second, with --interval" },
+  FormatStruct { spec: "rchar",      head: "RCHAR",   pr: pr_rchar,     width: 5,  need: IO,   flags: CF_RIGHT,                           desc: "bytes read by read(2) and the like" },
+  FormatStruct { spec: "rss",        head: "RSS",     pr: pr_rss,       width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "resident set size in KiB" },
+  FormatStruct { spec: "rssfile",    head: "RSSFILE", pr: pr_rssfile,   width: 7,  need: STA,  flags: CF_RIGHT,                           desc: "resident file mappings in KiB" },
+  FormatStruct { spec: "rssize",     head: "RSS",     pr: pr_rss,       width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "resident set size in KiB" },
+  FormatStruct { spec: "rsz",        head: "RSZ",     pr: pr_rss,       width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "resident set size in KiB" },
+  FormatStruct { spec: "rtprio",     head: "RTPRIO",  pr: pr_rtprio,    width: 6,  need: STAT, flags: CF_RIGHT,                           desc: "realtime priority" },
+  FormatStruct { spec: "s",          head: "S",       pr: pr_s,         width: 1,  need: STAT, flags: CF_LEFT,                            desc: "one-character process state" },
+  FormatStruct { spec: "sched",      head: "SCH",     pr: pr_sched,     width: 3,  need: STAT, flags: CF_RIGHT,                           desc: "scheduling policy of the process" },
+  FormatStruct { spec: "seccomp",    head: "SECCOMP", pr: pr_seccomp,   width: 8,  need: STA,  flags: CF_LEFT,                            desc: "seccomp mode: disabled, strict or filter" },
+  FormatStruct { spec: "sess",       head: "SESS",    pr: pr_sid,       width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "session ID" },
+  FormatStruct { spec: "session",    head: "SESS",    pr: pr_sid,       width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "session ID" },
+  FormatStruct { spec: "sid",        head: "SID",     pr: pr_sid,       width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "session ID" },
+  FormatStruct { spec: "sig",        head: "PENDING", pr: pr_sig,       width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the pending signals of the process" },
+  FormatStruct { spec: "sig_block",  head: "BLOCKED", pr: pr_blocked,   width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the blocked signals" },
+  FormatStruct { spec: "sig_catch",  head: "CATCHED", pr: pr_sigcatch,  width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the caught signals" },
+  FormatStruct { spec: "sig_ignore", head: "IGNORED", pr: pr_sigignore, width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the ignored signals" },
+  FormatStruct { spec: "sig_pend",   head: "SIGNAL",  pr: pr_sigpnd,    width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the pending signals of the thread" },
+  FormatStruct { spec: "sigcatch",   head: "CAUGHT",  pr: pr_sigcatch,  width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the caught signals" },
+  FormatStruct { spec: "sigignore",  head: "IGNORED", pr: pr_sigignore, width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the ignored signals" },
+  FormatStruct { spec: "sigmask",    head: "BLOCKED", pr: pr_blocked,   width: 16, need: STA,  flags: CF_RIGHT | CF_SIGNAL,               desc: "mask of the blocked signals" },
+  FormatStruct { spec: "spid",       head: "SPID",    pr: pr_tid,       width: 5,  need: 0,    flags: CF_RIGHT,                           desc: "light weight process (thread) ID" },
+  FormatStruct { spec: "stack",      head: "STACK",   pr: pr_stack,     width: 5,  need: LIM,  flags: CF_RIGHT,                           desc: "soft limit of the stack size" },
+  FormatStruct { spec: "start_time", head: "START",   pr: pr_stime,     width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "starting time or date" },
+  FormatStruct { spec: "stat",       head: "STAT",    pr: pr_stat,      width: 4,  need: STA,  flags: CF_LEFT,                            desc: "multi-character process state" },
+  FormatStruct { spec: "state",      head: "S",       pr: pr_s,         width: 1,  need: STAT, flags: CF_LEFT,                            desc: "one-character process state" },
+  FormatStruct { spec: "stime",      head: "STIME",   pr: pr_stime,     width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "starting time or date" },
+  FormatStruct { spec: "swap",       head: "SWAP",    pr: pr_swap,      width: 5,  need: SMP,  flags: CF_RIGHT,                           desc: "swapped out memory in KiB" },
+  FormatStruct { spec: "swappss",    head: "SWAPPSS", pr: pr_swappss,   width: 7,  need: SMP,  flags: CF_RIGHT,                           desc: "proportional share of the swapped out memory in KiB" },
+  FormatStruct { spec: "syscr",      head: "SYSCR",   pr: pr_syscr,     width: 5,  need: IO,   flags: CF_RIGHT,                           desc: "number of read system calls" },
+  FormatStruct { spec: "syscw",      head: "SYSCW",   pr: pr_syscw,     width: 5,  need: IO,   flags: CF_RIGHT,                           desc: "number of write system calls" },
+  FormatStruct { spec: "sz",         head: "SZ",      pr: pr_sz,        width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "virtual memory size in pages" },
+  FormatStruct { spec: "thcount",    head: "THCNT",   pr: pr_nlwp,      width: 5,  need: STAT, flags: CF_RIGHT,                           desc: "number of threads in the process" },
+  FormatStruct { spec: "tid",        head: "TID",     pr: pr_tid,       width: 5,  need: 0,    flags: CF_RIGHT,                           desc: "light weight process (thread) ID" },
+  FormatStruct { spec: "time",       head: "TIME",    pr: pr_time,      width: 8,  need: STAT, flags: CF_RIGHT,                           desc: "cumulative CPU time" },
+  FormatStruct { spec: "tname",      head: "TTY",     pr: pr_tty8,      width: 8,  need: STAT, flags: CF_LEFT,                            desc: "controlling terminal" },
+  FormatStruct { spec: "tt",         head: "TT",      pr: pr_tty8,      width: 8,  need: STAT, flags: CF_LEFT,                            desc: "controlling terminal" },
+  FormatStruct { spec: "tty",        head: "TT",      pr: pr_tty8,      width: 8,  need: STAT, flags: CF_LEFT,                            desc: "controlling terminal" },
+  FormatStruct { spec: "ucmd",       head: "CMD",     pr: pr_comm,      width: 15, need: STAT, flags: CF_LEFT | CF_FOREST,                desc: "command name, the executable name only" },
+  FormatStruct { spec: "ucomm",      head: "COMMAND", pr: pr_comm,      width: 15, need: STAT, flags: CF_LEFT | CF_FOREST,                desc: "command name, the executable name only" },
+  FormatStruct { spec: "uid",        head: "UID",     pr: pr_euid,      width: 5,  need: 0,    flags: CF_RIGHT,                           desc: "effective user ID" },
+  FormatStruct { spec: "uid_hack",   head: "UID",     pr: pr_euser,     width: 8,  need: 0,    flags: CF_LEFT,                            desc: "effective user name under the UID header, used by -f" },
+  FormatStruct { spec: "uname",      head: "USER",    pr: pr_euser,     width: 8,  need: 0,    flags: CF_LEFT,                            desc: "effective user name" },
+  FormatStruct { spec: "user",       head: "USER",    pr: pr_euser,     width: 8,  need: 0,    flags: CF_LEFT,                            desc: "effective user name" },
+  FormatStruct { spec: "uss",        head: "USS",     pr: pr_uss,       width: 6,  need: SMP,  flags: CF_RIGHT,                           desc: "memory private to the process in KiB" },
+  FormatStruct { spec: "vsize",      head: "VSZ",     pr: pr_vsz,       width: 6,  need: STAT, flags: CF_RIGHT,                           desc: "virtual memory size in KiB" },
+  FormatStruct { spec: "vsz",        head: "VSZ",     pr: pr_vsz,       width: 6,  need: STAT, flags: CF_RIGHT,                           desc: "virtual memory size in KiB" },
+  FormatStruct { spec: "wbytes",     head: "WBYTES",  pr: pr_wbytes,    width: 6,  need: IO,   flags: CF_RIGHT,                           desc: "bytes written to storage" },
+  FormatStruct { spec: "wbytes/s",   head: "WBYTES/s", pr: pr_write_rate, width: 8, need: IO,  flags: CF_RIGHT,                           desc: "bytes written to storage per second, with --interval" },
+  FormatStruct { spec: "wchan",      head: "WCHAN",   pr: pr_wchan,     width: 6,  need: WCH,  flags: CF_LEFT,                            desc: "name of the kernel function where the process sleeps" },
+  FormatStruct { spec: "wchar",      head: "WCHAR",   pr: pr_wchar,     width: 5,  need: IO,   flags: CF_RIGHT,                           desc: "bytes written by write(2) and the like" },
+];
+
+pub fn search_format_array(spec: &str) -> Option<&'static FormatStruct> {
+  FORMAT_ARRAY.iter().find(|fs| fs.spec == spec)
+}
+
+#[cfg(test)]
+mod tests {
+  fn value(spec: &str, p: &crate::readproc::PROCT) -> super::Value {
+    (super::search_format_array(spec).unwrap().pr)(p)
+  }
+
+  fn pr(spec: &str, p: &crate::readproc::PROCT) -> String {
+    value(spec, p).to_string()
+  }
+
+  #[test]
+  fn format_array_is_sorted() {
+    let specs: Vec<&str> = super::FORMAT_ARRAY.iter().map(|fs| fs.spec).collect();
+    let mut sorted = specs.clone();
+    sorted.sort_unstable();
+    assert_eq!(specs, sorted);
+  }
+
+  #[test]
+  fn print_tty_and_time() {
+    let p = crate::readproc::PROCT {
+      tty: crate::devname::makedev(136, 3),
+      utime: 90061 * crate::sysinfo::hertz(),
+      ..Default::default()
+    };
+    let tty = super::search_format_array("tname").unwrap();
+    assert_eq!(
+      (tty.pr)(&crate::readproc::PROCT::default()).to_string(),
+      "?"
+    );
+    assert_eq!(tty.head, "TTY");
+    let time = super::search_format_array("time").unwrap();
+    assert_eq!((time.pr)(&p).to_string(), "1-01:01:01");
+    assert_eq!((time.pr)(&p), super::Value::Time(90061));
+  }
+
+  #[test]
+  fn print_sysv_columns() {
+    let p = crate::readproc::PROCT {
+      pgrp: 30,
+      session: 20,
+      flags: 0x400140, // PF_FORKNOEXEC | PF_SUPERPRIV
+      vsize: 10 * crate::sysinfo::page_size(),
+      ..Default::default()
+    };
+    assert_eq!(pr("pgid", &p), "30");
+    assert_eq!(pr("sid", &p), "20");
+    assert_eq!(pr("f", &p), "5");
+    assert_eq!(pr("sz", &p), "10");
+    assert_eq!(pr("addr", &p), "-");
+
+    let now = 1_760_000_000;
+    let stime = super::format_stime(now - 600, now);
+    assert_eq!((stime.len(), &stime[2..3]), (5, ":"));
+    let stime = super::format_stime(now - 30 * 86400, now);
+    assert!(super::MONTHS.contains(&&stime[..3]));
+    assert_eq!(super::format_stime(now - 3 * 365 * 86400, now), "2022");
+  }
+
+  #[test]
+  fn value_json_and_order() {
+    use super::Value;
+    assert_eq!(Value::Int(-3).to_json(), "-3");
+    assert_eq!(Value::Time(62).to_json(), "62");
+    assert_eq!(Value::None.to_json(), "null");
+    assert_eq!(Value::Hex(0x10).to_json(), "\"0000000000000010\"");
+    assert_eq!(Value::Str(String::from("a\"b")).to_json(), "\"a\\\"b\"");
+    assert_eq!(
+      Value::Int(9).compare(&Value::Int(10)),
+      std::cmp::Ordering::Less
+    );
+    assert_eq!(
+      Value::Time(86400).compare(&Value::Time(3600)),
+      std::cmp::Ordering::Greater
+    );
+    assert_eq!(
+      Value::None.compare(&Value::Int(0)),
+      std::cmp::Ordering::Less
+    );
+  }
+
+  #[test]
+  fn print_stat_flags() {
+    let stat = super::search_format_array("stat").unwrap();
+    let p = crate::readproc::PROCT {
+      tgid: 100,
+      state: crate::readproc::ProcState::SLEEPING,
+      nice: -5,
+      vm_lock: 4,
+      session: 100,
+      nlwp: 3,
+      pgrp: 100,
+      tpgid: 100,
+      ..Default::default()
+    };
+    assert_eq!((stat.pr)(&p).to_string(), "S<Lsl+");
+    let p = crate::readproc::PROCT {
+      tgid: 101,
+      state: crate::readproc::ProcState::WAITING,
+      nice: 19,
+      session: 100,
+      nlwp: 1,
+      pgrp: 100,
+      tpgid: -1,
+      ..Default::default()
+    };
+    assert_eq!((stat.pr)(&p).to_string(), "DN");
+    let s = super::search_format_array("s").unwrap();
+    assert_eq!((s.pr)(&p).to_string(), "D");
+  }
+
+  #[test]
+  fn print_sched() {
+    // SCHED_OTHER, nice 5
+    let p = crate::readproc::PROCT {
+      sched: 0,
+      nice: 5,
+      priority: 25,
+      ..Default::default()
+    };
+    assert_eq!(pr("cls", &p), "TS");
+    assert_eq!(pr("rtprio", &p), "-");
+    assert_eq!(pr("ni", &p), "5");
+    assert_eq!(pr("pri", &p), "14");
+    assert_eq!(pr("opri", &p), "85");
+    assert_eq!(pr("priority", &p), "25");
+    assert_eq!(pr("pri_baz", &p), "125");
+    // SCHED_FIFO, rtprio 50
+    let p = crate::readproc::PROCT {
+      sched: 1,
+      rtprio: 50,
+      priority: -51,
+      ..Default::default()
+    };
+    assert_eq!(pr("policy", &p), "FF");
+    assert_eq!(pr("rtprio", &p), "50");
+    assert_eq!(pr("ni", &p), "-");
+    assert_eq!(pr("pri", &p), "90");
+    assert_eq!(pr("pri_api", &p), "50");
+    assert_eq!(pr("pri_bar", &p), "-50");
+    assert_eq!(pr("pri_foo", &p), "-71");
+    // SCHED_BATCH and SCHED_IDLE show no nice, like procps
+    for (sched, cls) in [(3, "B"), (5, "IDL")] {
+      let p = crate::readproc::PROCT {
+        sched,
+        nice: 19,
+        priority: 39,
+        ..Default::default()
+      };
+      assert_eq!(pr("cls", &p), cls);
+      assert_eq!(pr("ni", &p), "-");
+      assert_eq!(pr("rtprio", &p), "0");
+    }
+    // SCHED_DEADLINE
+    let p = crate::readproc::PROCT {
+      sched: 6,
+      priority: -101,
+      ..Default::default()
+    };
+    assert_eq!(pr("ni", &p), "-");
+    // unknown policy
+    let p = crate::readproc::PROCT {
+      sched: u64::MAX,
+      nice: 3,
+      ..Default::default()
+    };
+    assert_eq!(pr("ni", &p), "3");
+  }
+
+  #[test]
+  fn print_signals() {
+    let p = crate::readproc::PROCT {
+      sigignore: 0x4001,
+      ..Default::default()
+    };
+    let ignored = super::search_format_array("ignored").unwrap();
+    assert_eq!((ignored.pr)(&p).to_string(), "0000000000004001");
+    assert_eq!(
+      super::search_format_array("pending").unwrap().head,
+      "PENDING"
+    );
+    let decoded = |spec: &str, p: &crate::readproc::PROCT| {
+      (super::decoded_signals_pr(spec).unwrap())(p).to_string()
+    };
+    assert_eq!(decoded("ignored", &p), crate::sig::mask_to_names(0x4001));
+    assert_eq!(decoded("sig_ignore", &p), decoded("sigignore", &p));
+    assert!(super::decoded_signals_pr("pid").is_none());
+  }
+
+  #[test]
+  fn print_security() {
+    let mut p = crate::readproc::PROCT {
+      cap_eff: 1 << 12 | 1 << 19,
+      cap_bnd: 0x1ffffffffff,
+      ..Default::default()
+    };
+    assert_eq!(pr("capeff", &p), "0000000000081000");
+    assert_eq!(pr("label", &p), "-");
+    assert_eq!(pr("seccomp", &p), "-");
+    assert_eq!(pr("nnp", &p), "-");
+    p.label = String::from("docker-default (enforce)");
+    p.seccomp = Some(2);
+    p.no_new_privs = Some(1);
+    assert_eq!(pr("label", &p), "docker-default (enforce)");
+    assert_eq!(pr("seccomp", &p), "filter");
+    assert_eq!(pr("nnp", &p), "1");
+    let decoded = |spec: &str, p: &crate::readproc::PROCT| {
+      (super::decoded_caps_pr(spec).unwrap())(p).to_string()
+    };
+    assert_eq!(decoded("capeff", &p), "cap_net_admin,cap_sys_ptrace");
+    assert_eq!(decoded("capbnd", &p), "full");
+    assert_eq!(decoded("capamb", &p), "-");
+    assert!(super::decoded_caps_pr("label").is_none());
+  }
+
+  #[test]
+  fn print_ns_ids() {
+    let mut p = crate::readproc::PROCT::default();
+    assert_eq!(pr("nspid", &p), "-");
+    assert_eq!(pr("pidns", &p), "-");
+    p.nspid = vec![41240, 13];
+    p.nstgid = vec![41234, 7];
+    p.nspgid = vec![41234, 7];
+    p.nssid = vec![41200, 0];
+    p.ns[crate::readproc::NS_PID] = 4026532301;
+    assert_eq!(pr("nspid", &p), "13");
+    assert_eq!(pr("nstgid", &p), "7");
+    assert_eq!(pr("nspgid", &p), "7");
+    assert_eq!(pr("nssid", &p), "0");
+    assert_eq!(pr("pidns", &p), "4026532301");
+  }
+
+  #[test]
+  fn print_wchan() {
+    let p = crate::readproc::PROCT {
+      wchan_name: String::from("do_select"),
+      ..Default::default()
+    };
+    let wchan = super::search_format_array("wchan").unwrap();
+    assert_eq!((wchan.pr)(&p).to_string(), "select");
+    assert_eq!(
+      (wchan.pr)(&crate::readproc::PROCT::default()).to_string(),
+      "-"
+    );
+    let nwchan = super::search_format_array("nwchan").unwrap();
+    assert_eq!(
+      (nwchan.pr)(&crate::readproc::PROCT::default()).to_string(),
+      "-"
+    );
+  }
+
+  #[test]
+  fn print_rates() {
+    let mut p = crate::readproc::PROCT {
+      maj_flt: 12,
+      ..Default::default()
+    };
+    assert_eq!(pr("majflt", &p), "12");
+    assert_eq!(pr("majflt/s", &p), "-");
+    assert_eq!(pr("rbytes/s", &p), "-");
+    p.rates = Some(crate::readproc::Rates {
+      pcpu: 12.34,
+      maj_flt: 2.5,
+      nvcsw: 40.0,
+      read_bytes: Some(1000.4),
+      ..Default::default()
+    });
+    assert_eq!(pr("%cpu", &p), "12.3");
+    assert_eq!(pr("pcpu", &p), "12.3");
+    assert_eq!(pr("majflt/s", &p), "2.5");
+    assert_eq!(pr("cswch/s", &p), "40.0");
+    assert_eq!(pr("rbytes/s", &p), "1000");
+    assert_eq!(pr("wbytes/s", &p), "-");
+    // the text is rounded, JSON keeps the number
+    assert_eq!(super::Value::Float(0.25).to_string(), "0.2");
+    assert_eq!(super::Value::Float(0.25).to_json(), "0.25");
+    assert_eq!(super::Value::Float(12.0).to_json(), "12");
+    assert_eq!(super::Value::Float(f64::NAN).to_json(), "null");
+  }
+
+  #[test]
+  fn print_smaps() {
+    let mut p = crate::readproc::PROCT {
+      rss_file: 2100,
+      ..Default::default()
+    };
+    assert_eq!(pr("pss", &p), "-");
+    assert_eq!(pr("rssfile", &p), "2100");
+    p.smaps = Some(crate::readproc::SmapsRollup {
+      pss: 1200,
+      uss: 1400,
+      swap_pss: 20,
+      ..Default::default()
+    });
+    assert_eq!(pr("pss", &p), "1200");
+    assert_eq!(pr("uss", &p), "1400");
+    assert_eq!(pr("swappss", &p), "20");
+    assert_eq!(pr("anon", &p), "0");
+  }
+
+  #[test]
+  fn print_io() {
+    // not readable
+    let mut p = crate::readproc::PROCT::default();
+    assert_eq!(pr("rchar", &p), "-");
+    assert_eq!(pr("syscw", &p), "-");
+    p.io = Some(crate::readproc::ProcIo {
+      rchar: 1000,
+      wchar: 1536,
+      syscw: 12,
+      read_bytes: 20 * 1024 * 1024,
+      write_bytes: 3 * 1024 * 1024 * 1024,
+      ..Default::default()
+    });
+    assert_eq!(pr("rchar", &p), "1000");
+    assert_eq!(pr("wchar", &p), "1.5K");
+    assert_eq!(pr("syscw", &p), "12");
+    assert_eq!(pr("rbytes", &p), "20M");
+    assert_eq!(pr("wbytes", &p), "3.0G");
+    assert_eq!(pr("cwbytes", &p), "0");
+    let wbytes = super::search_format_array("wbytes").unwrap();
+    assert_eq!((wbytes.pr)(&p).to_json(), "3221225472");
+    assert_eq!(super::scale_bytes(1023), "1023");
+    assert_eq!(super::scale_bytes(1023 * 1024 + 511), "1023K");
+    assert_eq!(super::scale_bytes(1023 * 1024 + 512), "1.0M");
+  }
+
+  #[test]
+  fn print_fds() {
+    let mut p = crate::readproc::PROCT::default();
+    assert_eq!(pr("nfd", &p), "-");
+    assert_eq!(pr("%fd", &p), "-");
+    p.nfd = Some(256);
+    // limits not read
+    assert_eq!(pr("nfd", &p), "256");
+    assert_eq!(pr("%fd", &p), "-");
+    // unlimited
+    p.limits = Some(crate::readproc::limits2proc(
+      "Max open files            unlimited            unlimited            files\n",
+    ));
+    assert_eq!(pr("%fd", &p), "-");
+    p.limits = Some(crate::readproc::limits2proc(
+      "Max open files            1024                 4096                 files\n",
+    ));
+    assert_eq!(pr("%fd", &p), "25.0");
+  }
+
+  #[test]
+  fn print_limits() {
+    let mut p = crate::readproc::PROCT::default();
+    assert_eq!(value("nofile", &p), super::Value::None);
+    p.limits = Some(crate::readproc::limits2proc(
+      "Max stack size            8388608              unlimited            bytes
+Max core file size        unlimited            unlimited            bytes
+Max processes             63448                63448                processes
+Max open files            1024                 1048576              files
+Max locked memory         65536                65536                bytes
+",
+    ));
+    assert_eq!(value("nofile", &p).to_string(), "1024");
+    assert_eq!(value("nproc", &p).to_string(), "63448");
+    assert_eq!(value("core", &p).to_string(), "unlimited");
+    assert_eq!(value("core", &p).to_json(), "\"unlimited\"");
+    assert_eq!(value("memlock", &p).to_string(), "64K");
+    assert_eq!(value("stack", &p).to_string(), "8.0M");
+    assert_eq!(value("stack", &p).to_json(), "8388608");
+    // not in the file
+    p.limits = Some(crate::readproc::limits2proc(
+      "Max open files            1024                 1048576              files\n",
+    ));
+    assert_eq!(value("nproc", &p), super::Value::None);
+    // unlimited is the largest
+    let order = super::Value::Unlimited.compare(&super::Value::Bytes(u64::MAX));
+    assert_eq!(order, std::cmp::Ordering::Greater);
+    assert_eq!(
+      super::Value::None.compare(&super::Value::Unlimited),
+      std::cmp::Ordering::Less
+    );
+  }
+}
@@ -0,0 +1,120 @@
+// emulation of other Unixes, chosen by PS_PERSONALITY or CMD_ENV like procps
+
+// personality flags
+#[allow(non_upper_case_globals)]
+pub const PER_BSD_h: u32 = 0x0002; // h repeats the header instead of removing it
+#[allow(non_upper_case_globals)]
+pub const PER_BSD_m: u32 = 0x0004; // m sorts by memory instead of showing threads
+pub const PER_FORCE_BSD: u32 = 0x0010; // every option is BSD style, even with a dash
+#[allow(non_upper_case_globals)]
+pub const PER_OLD_m: u32 = 0x0040; // m shows threads
+pub const PER_NEED_DASH: u32 = 0x2000; // options must start with a dash, as POSIX says
+
+// default format of SysV style Unixes, where CMD is the command name only
+pub const SYSV_DFMT: &str = "pid,tname,time,ucmd";
+
+#[derive(Debug, PartialEq)]
+pub struct Personality {
+  pub name: &'static str,
+  pub flags: u32,
+  pub prefer_bsd_defaults: bool,  // BSD default format without options
+  pub dfmt: Option<&'static str>, // default format instead of DFMT
+  pub sort: Option<&'static str>, // default sort keys
+}
+
+const fn per(
+  name: &'static str,
+  flags: u32,
+  prefer_bsd_defaults: bool,
+  dfmt: Option<&'static str>,
+  sort: Option<&'static str>,
+) -> Personality {
+  Personality {
+    name,
+    flags,
+    prefer_bsd_defaults,
+    dfmt,
+    sort,
+  }
+}
+
+// keep it sorted by `name`
+#[rustfmt::skip]
+pub static PERSONALITY_TABLE: &[Personality] = &[
+  per("390",      0,                                     false, None,            None),
+  per("aix",      0,                                     false, Some(SYSV_DFMT), None),
+  per("bsd",      PER_FORCE_BSD | PER_BSD_h | PER_BSD_m, true,  None,            Some("tname,pid")),
+  per("compaq",   PER_BSD_h | PER_BSD_m,                 false, None,            None),
+  per("debian",   PER_OLD_m,                             true,  None,            None),
+  per("default",  0,                                     false, None,            None),
+  per("digital",  PER_BSD_h | PER_BSD_m,                 false, None,            None),
+  per("gnu",      PER_OLD_m,                             true,  None,            None),
+  per("hp",       PER_BSD_h | PER_BSD_m,                 false, Some(SYSV_DFMT), None),
+  per("hp-ux",    PER_BSD_h | PER_BSD_m,                 false, Some(SYSV_DFMT), None),
+  per("hpux",     PER_BSD_h | PER_BSD_m,                 false, Some(SYSV_DFMT), None),
+  per("irix",     PER_BSD_h | PER_BSD_m,                 false, Some(SYSV_DFMT), None),
+  per("linux",    0,                                     false, None,            None),
+  per("old",      PER_FORCE_BSD | PER_OLD_m,             true,  None,            None),
+  per("os390",    0,                                     false, None,            None),
+  per("posix",    PER_NEED_DASH,                         false, Some(SYSV_DFMT), None),
+  per("s390",     0,                                     false, None,            None),
+  per("sco",      PER_BSD_h | PER_BSD_m,                 false, Some(SYSV_DFMT), None),
+  per("sgi",      PER_BSD_h | PER_BSD_m,                 false, Some(SYSV_DFMT), None),
+  per("solaris2", PER_NEED_DASH,                         false, Some(SYSV_DFMT), None),
+  per("sun",      PER_NEED_DASH,                         false, Some(SYSV_DFMT), None),
+  per("sunos4",   PER_NEED_DASH,                         false, Some(SYSV_DFMT), None),
+  per("svr4",     PER_BSD_h | PER_BSD_m,                 false, Some(SYSV_DFMT), None),
+  per("sysv",     PER_BSD_h | PER_BSD_m,                 false, Some(SYSV_DFMT), None),
+  per("tru64",    PER_BSD_h | PER_BSD_m,                 false, None,            None),
+  per("unix",     PER_NEED_DASH,                         false, Some(SYSV_DFMT), None),
+  per("unix95",   PER_NEED_DASH,                         false, Some(SYSV_DFMT), None),
+  per("unix98",   PER_NEED_DASH,                         false, Some(SYSV_DFMT), None),
+  per("unknown",  0,                                     false, None,            None),
+];
+
+pub fn search_personality(name: &str) -> Option<&'static Personality> {
+  let name = name.to_lowercase();
+  PERSONALITY_TABLE.iter().find(|per| per.name == name)
+}
+
+// the personality given by the environment.
+// I_WANT_A_BROKEN_PS asks for the old Linux ps, which takes "-aux" as "aux".
+pub fn personality_from_env() -> &'static Personality {
+  let name = if std::env::var_os("I_WANT_A_BROKEN_PS").is_some() {
+    String::from("old")
+  } else {
+    match std::env::var("PS_PERSONALITY") {
+      Ok(s) if !s.is_empty() => s,
+      _ => std::env::var("CMD_ENV").unwrap_or_default(),
+    }
+  };
+  if name.is_empty() {
+    return search_personality("unknown").unwrap();
+  }
+  match search_personality(&name) {
+    Some(per) => per,
+    None => {
+      log::warn!("unknown personality \"{}\", using linux", name);
+      search_personality("linux").unwrap()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn personality_table_is_sorted() {
+    let names: Vec<&str> = super::PERSONALITY_TABLE.iter().map(|p| p.name).collect();
+    let mut sorted = names.clone();
+    sorted.sort_unstable();
+    assert_eq!(names, sorted);
+  }
+
+  #[test]
+  fn search_by_name() {
+    assert_eq!(super::search_personality("BSD").unwrap().name, "bsd");
+    assert!(super::search_personality("vms").is_none());
+    let old = super::search_personality("old").unwrap();
+    assert_ne!(old.flags & super::PER_FORCE_BSD, 0);
+  }
+}
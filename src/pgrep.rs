@@ -0,0 +1,632 @@
+// pgrep and pkill, a port of procps pgrep.c.
+// both share the options and the matching, only the action differs.
+
+use regex::{Regex, RegexBuilder};
+
+use crate::argparser;
+use crate::argparser::{GidSelection, PidSelection, SelectionNode, TtySelection, UidSelection};
+use crate::devname;
+use crate::pwcache;
+use crate::readproc;
+use crate::readproc::PROCT;
+use crate::sig;
+
+// exit status
+pub const EXIT_MATCH: i32 = 0;
+pub const EXIT_NO_MATCH: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_FATAL: i32 = 3;
+
+// the longest name in /proc/<pid>/stat
+const COMM_LEN: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+  PGREP,
+  PKILL,
+}
+
+impl Mode {
+  fn name(self) -> &'static str {
+    match self {
+      Mode::PGREP => "pgrep",
+      Mode::PKILL => "pkill",
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct PgrepOptions {
+  pub mode: Mode,
+  pub pattern: Option<String>,
+  pub full: bool,        // -f: match the full command line
+  pub exact: bool,       // -x
+  pub ignore_case: bool, // -i
+  pub inverse: bool,     // -v
+  pub newest: bool,      // -n
+  pub oldest: bool,      // -o
+  pub count: bool,       // -c
+  pub delimiter: String, // -d
+  pub list_name: bool,   // -l
+  pub list_full: bool,   // -a
+  pub echo: bool,        // -e
+  pub signal: u32,       // --signal, -SIGNAL
+  // -u -U -G -g -s -P -t in selection_list, checked by want_this_proc
+  pub selection: argparser::PsParser,
+}
+
+impl PgrepOptions {
+  pub fn new(mode: Mode) -> PgrepOptions {
+    PgrepOptions {
+      mode,
+      pattern: None,
+      full: false,
+      exact: false,
+      ignore_case: false,
+      inverse: false,
+      newest: false,
+      oldest: false,
+      count: false,
+      delimiter: String::from("\n"),
+      list_name: false,
+      list_full: false,
+      echo: false,
+      signal: libc::SIGTERM as u32,
+      selection: argparser::PsParser {
+        all_process: true,
+        match_every_list: true,
+        ..Default::default()
+      },
+    }
+  }
+
+  // PROC_FILL* flags needed for matching and output
+  pub fn needs(&self) -> u64 {
+    let mut needs = readproc::PROC_FILLSTAT;
+    if self.full || self.list_full {
+      needs |= readproc::PROC_FILLARG;
+    }
+    let real_ids = self
+      .selection
+      .selection_list
+      .iter()
+      .any(|node| matches!(node, SelectionNode::RUID(_) | SelectionNode::RGID(_)));
+    if real_ids {
+      needs |= readproc::PROC_FILLSTATUS;
+    }
+    needs
+  }
+
+  pub fn build_regex(&self) -> Result<Option<Regex>, String> {
+    let pattern = match &self.pattern {
+      Some(s) => s,
+      None => return Ok(None),
+    };
+    let pattern = if self.exact {
+      format!("^(?:{})$", pattern)
+    } else {
+      pattern.clone()
+    };
+    match RegexBuilder::new(&pattern)
+      .case_insensitive(self.ignore_case)
+      .build()
+    {
+      Ok(re) => Ok(Some(re)),
+      Err(e) => Err(format!("invalid pattern: {}", e)),
+    }
+  }
+
+  // check the selection and the pattern
+  pub fn match_proc(&self, p: &PROCT, re: Option<&Regex>) -> bool {
+    let ok = readproc::want_this_proc(p, &self.selection)
+      && match re {
+        Some(re) => re.is_match(&match_target(p, self.full)),
+        None => true,
+      };
+    ok != self.inverse
+  }
+}
+
+// the string matched against the pattern
+fn match_target(p: &PROCT, full: bool) -> String {
+  if full && !p.cmdline.is_empty() {
+    p.cmdline.join(" ")
+  } else {
+    p.cmd.clone()
+  }
+}
+
+fn parse_list<T>(arg: &str, f: impl Fn(&str) -> Option<T>, what: &str) -> Result<Vec<T>, String> {
+  let mut list = vec![];
+  for item in arg.split(',') {
+    match f(item) {
+      Some(v) => list.push(v),
+      None => return Err(format!("invalid {}: {}", what, item)),
+    }
+  }
+  Ok(list)
+}
+
+// 0 means the group or session of pgrep itself
+fn parse_id_or_self(item: &str, own: i32) -> Option<i32> {
+  match item.parse::<i32>() {
+    Ok(0) => Some(own),
+    Ok(n) if n > 0 => Some(n),
+    _ => None,
+  }
+}
+
+pub fn parse_args(mode: Mode, args: &[String]) -> Result<PgrepOptions, String> {
+  let mut opts = PgrepOptions::new(mode);
+  let mut ix = 1;
+
+  // pkill -SIGNAL, only as the first argument
+  if mode == Mode::PKILL && args.len() > 1 {
+    if let Some(name) = args[1].strip_prefix('-') {
+      if let Some(signo) = sig::signal_name_to_number(name) {
+        opts.signal = signo;
+        ix = 2;
+      }
+    }
+  }
+
+  let mut euids = vec![]; // -u
+  let mut ruids = vec![]; // -U
+  let mut rgids = vec![]; // -G
+  let mut pgrps = vec![]; // -g
+  let mut sessions = vec![]; // -s
+  let mut ppids = vec![]; // -P
+  let mut ttys = vec![]; // -t
+  let mut only_args = false;
+  while ix < args.len() {
+    let arg = &args[ix];
+    ix += 1;
+    if only_args || !arg.starts_with('-') || arg == "-" {
+      if opts.pattern.is_some() {
+        return Err(String::from("only one pattern can be provided"));
+      }
+      opts.pattern = Some(arg.clone());
+      continue;
+    }
+    if arg == "--" {
+      only_args = true;
+      continue;
+    }
+
+    // (option name, value attached to it)
+    let mut flags: Vec<(String, Option<String>)> = vec![];
+    if let Some(long) = arg.strip_prefix("--") {
+      match long.find('=') {
+        Some(eq) => flags.push((
+          String::from(&long[..eq]),
+          Some(String::from(&long[eq + 1..])),
+        )),
+        None => flags.push((String::from(long), None)),
+      }
+    } else {
+      let chars: Vec<char> = arg[1..].chars().collect();
+      for (cix, c) in chars.iter().enumerate() {
+        if "dgGPstuU".contains(*c) {
+          let rest: String = chars[cix + 1..].iter().collect();
+          flags.push((
+            c.to_string(),
+            if rest.is_empty() { None } else { Some(rest) },
+          ));
+          break;
+        }
+        flags.push((c.to_string(), None));
+      }
+    }
+
+    for (name, val) in flags {
+      let pgrep_only = matches!(
+        name.as_str(),
+        "d" | "delimiter" | "l" | "list-name" | "a" | "list-full" | "v" | "inverse"
+      );
+      if pgrep_only && mode != Mode::PGREP || (name == "e" || name == "echo") && mode != Mode::PKILL
+      {
+        return Err(format!("invalid option -- '{}'", name));
+      }
+      let takes_arg = matches!(
+        name.as_str(),
+        "d"
+          | "delimiter"
+          | "g"
+          | "pgroup"
+          | "G"
+          | "group"
+          | "P"
+          | "parent"
+          | "s"
+          | "session"
+          | "t"
+          | "terminal"
+          | "u"
+          | "euid"
+          | "U"
+          | "uid"
+          | "signal"
+      );
+      let val = if !takes_arg {
+        String::new()
+      } else if let Some(val) = val {
+        val
+      } else if ix < args.len() {
+        ix += 1;
+        args[ix - 1].clone()
+      } else {
+        return Err(format!("option requires an argument -- '{}'", name));
+      };
+      match name.as_str() {
+        "c" | "count" => opts.count = true,
+        "f" | "full" => opts.full = true,
+        "i" | "ignore-case" => opts.ignore_case = true,
+        "l" | "list-name" => opts.list_name = true,
+        "a" | "list-full" => opts.list_full = true,
+        "n" | "newest" => opts.newest = true,
+        "o" | "oldest" => opts.oldest = true,
+        "v" | "inverse" => opts.inverse = true,
+        "x" | "exact" => opts.exact = true,
+        "e" | "echo" => opts.echo = true,
+        "d" | "delimiter" => opts.delimiter = val,
+        "signal" => {
+          opts.signal = match sig::signal_name_to_number(&val) {
+            Some(signo) => signo,
+            None => return Err(format!("Unknown signal \"{}\".", val)),
+          }
+        }
+        "u" | "euid" => euids.append(&mut parse_list(&val, pwcache::uid_from_user, "user name")?),
+        "U" | "uid" => ruids.append(&mut parse_list(&val, pwcache::uid_from_user, "user name")?),
+        "G" | "group" => rgids.append(&mut parse_list(
+          &val,
+          pwcache::gid_from_group,
+          "group name",
+        )?),
+        "g" | "pgroup" => {
+          let own = unsafe { libc::getpgrp() };
+          pgrps.append(&mut parse_list(
+            &val,
+            |s| parse_id_or_self(s, own),
+            "process group",
+          )?)
+        }
+        "s" | "session" => {
+          let own = unsafe { libc::getsid(0) };
+          sessions.append(&mut parse_list(
+            &val,
+            |s| parse_id_or_self(s, own),
+            "session",
+          )?)
+        }
+        "P" | "parent" => ppids.append(&mut parse_list(
+          &val,
+          |s| s.parse::<i32>().ok(),
+          "parent process ID",
+        )?),
+        "t" | "terminal" => ttys.append(&mut parse_list(&val, devname::tty_to_dev, "terminal")?),
+        "h" | "help" => {
+          print!("{}", usage(mode));
+          std::process::exit(EXIT_MATCH);
+        }
+        "V" | "version" => {
+          println!("{} from rustyps {}", mode.name(), env!("CARGO_PKG_VERSION"));
+          std::process::exit(EXIT_MATCH);
+        }
+        _ => return Err(format!("invalid option -- '{}'", name)),
+      }
+    }
+  }
+
+  // a process must be in each of the lists, and in any item of a list
+  let list = &mut opts.selection.selection_list;
+  if !euids.is_empty() {
+    list.push(SelectionNode::EUID(UidSelection { uid: euids }));
+  }
+  if !ruids.is_empty() {
+    list.push(SelectionNode::RUID(UidSelection { uid: ruids }));
+  }
+  if !rgids.is_empty() {
+    list.push(SelectionNode::RGID(GidSelection { gid: rgids }));
+  }
+  if !pgrps.is_empty() {
+    list.push(SelectionNode::PGRP(PidSelection { pid: pgrps }));
+  }
+  if !sessions.is_empty() {
+    list.push(SelectionNode::SESS(PidSelection { pid: sessions }));
+  }
+  if !ppids.is_empty() {
+    list.push(SelectionNode::PPID(PidSelection { pid: ppids }));
+  }
+  if !ttys.is_empty() {
+    list.push(SelectionNode::TTY(TtySelection { tty: ttys }));
+  }
+  opts.selection.all_process = list.is_empty();
+
+  if opts.pattern.is_none() && opts.selection.all_process {
+    return Err(String::from("no matching criteria specified"));
+  }
+  if opts.newest && opts.oldest {
+    return Err(String::from("-n and -o can not be used together"));
+  }
+  Ok(opts)
+}
+
+pub fn usage(mode: Mode) -> String {
+  let mut s = format!(
+    "
+Usage:
+ {} [options] <pattern>
+
+Options:
+",
+    mode.name()
+  );
+  if mode == Mode::PGREP {
+    s.push_str(
+      " -d, --delimiter <string>  specify output delimiter
+ -l, --list-name           list PID and process name
+ -a, --list-full           list PID and full command line
+ -v, --inverse             negates the matching
+",
+    );
+  } else {
+    s.push_str(
+      " -<sig>, --signal <sig>    signal to send (either number or name)
+ -e, --echo                display what is killed
+",
+    );
+  }
+  s.push_str(
+    " -c, --count               count of matching processes
+ -f, --full                use full process name to match
+ -g, --pgroup <PGID,...>   match listed process group IDs
+ -G, --group <GID,...>     match real group IDs
+ -i, --ignore-case         match case insensitively
+ -n, --newest              select most recently started
+ -o, --oldest              select least recently started
+ -P, --parent <PPID,...>   match only child processes of the given parent
+ -s, --session <SID,...>   match session IDs
+ -t, --terminal <tty,...>  match by controlling terminal
+ -u, --euid <ID,...>       match by effective IDs
+ -U, --uid <ID,...>        match by real IDs
+ -x, --exact               match exactly with the command name
+
+ -h, --help     display this help and exit
+ -V, --version  output version information and exit
+",
+  );
+  s
+}
+
+// find the matching processes, except ourselves
+pub fn select_procs(opts: &PgrepOptions, re: Option<&Regex>) -> Result<Vec<PROCT>, String> {
+  let mut pt = readproc::openproc(opts.needs(), None, None)?;
+  let myself = std::process::id() as i32;
+  let mut procs = vec![];
+  while let Some(p) = readproc::readproc(&mut pt) {
+    if p.tgid != myself && opts.match_proc(&p, re) {
+      procs.push(p);
+    }
+  }
+  Ok(pick_one(procs, opts))
+}
+
+// -n and -o leave only the newest or the oldest
+fn pick_one(procs: Vec<PROCT>, opts: &PgrepOptions) -> Vec<PROCT> {
+  let key = |p: &PROCT| (p.start_time, p.tgid);
+  let picked = if opts.newest {
+    procs.into_iter().max_by_key(key)
+  } else if opts.oldest {
+    procs.into_iter().min_by_key(key)
+  } else {
+    return procs;
+  };
+  picked.into_iter().collect()
+}
+
+// a line of pgrep output
+pub fn format_proc(p: &PROCT, opts: &PgrepOptions) -> String {
+  if opts.list_full && !p.cmdline.is_empty() {
+    format!("{} {}", p.tgid, p.cmdline.join(" "))
+  } else if opts.list_name || opts.list_full {
+    format!("{} {}", p.tgid, p.cmd)
+  } else {
+    p.tgid.to_string()
+  }
+}
+
+// main of pgrep and pkill. returns the exit status.
+pub fn run(mode: Mode, args: Vec<String>) -> i32 {
+  let opts = match parse_args(mode, &args) {
+    Ok(opts) => opts,
+    Err(msg) => {
+      eprintln!("{}: {}", mode.name(), msg);
+      eprintln!("Try `{} --help' for more information.", mode.name());
+      return EXIT_USAGE;
+    }
+  };
+  if let Some(pattern) = &opts.pattern {
+    if !opts.full && pattern.len() > COMM_LEN {
+      eprintln!(
+        "{}: pattern that searches for process name longer than {} characters will result in zero matches",
+        mode.name(),
+        COMM_LEN
+      );
+      eprintln!(
+        "Try `{} -f' option to match against the complete command line.",
+        mode.name()
+      );
+    }
+  }
+  let re = match opts.build_regex() {
+    Ok(re) => re,
+    Err(msg) => {
+      eprintln!("{}: {}", mode.name(), msg);
+      return EXIT_USAGE;
+    }
+  };
+  let procs = match select_procs(&opts, re.as_ref()) {
+    Ok(procs) => procs,
+    Err(msg) => {
+      eprintln!("{}: {}", mode.name(), msg);
+      return EXIT_FATAL;
+    }
+  };
+
+  match mode {
+    Mode::PGREP => {
+      if opts.count {
+        println!("{}", procs.len());
+      } else if !procs.is_empty() {
+        let lines: Vec<String> = procs.iter().map(|p| format_proc(p, &opts)).collect();
+        println!("{}", lines.join(&opts.delimiter));
+      }
+    }
+    Mode::PKILL => {
+      for p in procs.iter() {
+        if unsafe { libc::kill(p.tgid, opts.signal as i32) } == 0 {
+          if opts.echo {
+            println!("{} killed (pid {})", p.cmd, p.tgid);
+          }
+        } else {
+          let err = std::io::Error::last_os_error();
+          eprintln!("pkill: killing pid {} failed: {}", p.tgid, err);
+        }
+      }
+      if opts.count {
+        println!("{}", procs.len());
+      }
+    }
+  }
+  if procs.is_empty() {
+    EXIT_NO_MATCH
+  } else {
+    EXIT_MATCH
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Mode;
+  use crate::argparser::{PidSelection, SelectionNode, UidSelection};
+  use crate::readproc::PROCT;
+
+  fn args(v: &[&str]) -> Vec<String> {
+    v.iter().map(|s| String::from(*s)).collect()
+  }
+
+  #[test]
+  fn parse_pgrep_options() {
+    let opts = super::parse_args(
+      Mode::PGREP,
+      &args(&["pgrep", "-lf", "-d,", "-u", "root,42", "sshd"]),
+    )
+    .unwrap();
+    assert!(opts.list_name && opts.full);
+    assert_eq!(opts.delimiter, ",");
+    assert_eq!(
+      opts.selection.selection_list,
+      vec![SelectionNode::EUID(UidSelection { uid: vec![0, 42] })]
+    );
+    assert_eq!(opts.pattern.as_deref(), Some("sshd"));
+    let opts = super::parse_args(Mode::PGREP, &args(&["pgrep", "--parent=1", "-P2"])).unwrap();
+    assert_eq!(
+      opts.selection.selection_list,
+      vec![SelectionNode::PPID(PidSelection { pid: vec![1, 2] })]
+    );
+    assert!(opts.pattern.is_none());
+
+    assert!(super::parse_args(Mode::PGREP, &args(&["pgrep"])).is_err());
+    assert!(super::parse_args(Mode::PGREP, &args(&["pgrep", "a", "b"])).is_err());
+    assert!(super::parse_args(Mode::PGREP, &args(&["pgrep", "-e", "a"])).is_err());
+    assert!(super::parse_args(Mode::PGREP, &args(&["pgrep", "-u"])).is_err());
+    assert!(super::parse_args(Mode::PGREP, &args(&["pgrep", "-no", "a"])).is_err());
+    assert!(super::parse_args(Mode::PGREP, &args(&["pgrep", "-u", "no-such-user"])).is_err());
+  }
+
+  #[test]
+  fn parse_pkill_options() {
+    let opts = super::parse_args(Mode::PKILL, &args(&["pkill", "-9", "-e", "sleep"])).unwrap();
+    assert_eq!(opts.signal, 9);
+    assert!(opts.echo);
+    let opts = super::parse_args(Mode::PKILL, &args(&["pkill", "-HUP", "sleep"])).unwrap();
+    assert_eq!(opts.signal, 1);
+    let opts =
+      super::parse_args(Mode::PKILL, &args(&["pkill", "--signal", "SIGUSR1", "x"])).unwrap();
+    assert_eq!(opts.signal, 10);
+    assert_eq!(
+      super::parse_args(Mode::PKILL, &args(&["pkill", "x"]))
+        .unwrap()
+        .signal,
+      15
+    );
+    assert!(super::parse_args(Mode::PKILL, &args(&["pkill", "-l", "x"])).is_err());
+    assert!(super::parse_args(Mode::PKILL, &args(&["pkill", "-v", "x"])).is_err());
+    assert!(super::parse_args(Mode::PKILL, &args(&["pkill", "--signal=NOSUCH", "x"])).is_err());
+  }
+
+  #[test]
+  fn match_processes() {
+    let p = PROCT {
+      tgid: 100,
+      ppid: 1,
+      euid: 1000,
+      cmd: String::from("sleep"),
+      cmdline: vec![String::from("/bin/sleep"), String::from("60")],
+      start_time: 50,
+      ..Default::default()
+    };
+    let matches = |v: &[&str]| {
+      let opts = super::parse_args(Mode::PGREP, &args(v)).unwrap();
+      let re = opts.build_regex().unwrap();
+      opts.match_proc(&p, re.as_ref())
+    };
+    assert!(matches(&["pgrep", "sl"]));
+    assert!(!matches(&["pgrep", "-x", "sl"]));
+    assert!(matches(&["pgrep", "-x", "sleep"]));
+    assert!(!matches(&["pgrep", "SLEEP"]));
+    assert!(matches(&["pgrep", "-i", "SLEEP"]));
+    assert!(!matches(&["pgrep", "60"]));
+    assert!(matches(&["pgrep", "-f", "sleep 60$"]));
+    assert!(matches(&["pgrep", "-v", "bash"]));
+    assert!(matches(&["pgrep", "-P", "1", "-u", "1000"]));
+    assert!(!matches(&["pgrep", "-P", "1", "-u", "0"]));
+    assert!(matches(&["pgrep", "-P", "7", "-P", "1", "-u", "0,1000"]));
+    assert!(!matches(&["pgrep", "-v", "-P", "1"]));
+
+    let opts = super::parse_args(Mode::PGREP, &args(&["pgrep", "-a", "x"])).unwrap();
+    assert_eq!(super::format_proc(&p, &opts), "100 /bin/sleep 60");
+    let opts = super::parse_args(Mode::PGREP, &args(&["pgrep", "-l", "x"])).unwrap();
+    assert_eq!(super::format_proc(&p, &opts), "100 sleep");
+  }
+
+  #[test]
+  fn newest_and_oldest() {
+    let procs = || {
+      vec![
+        PROCT {
+          tgid: 1,
+          start_time: 10,
+          ..Default::default()
+        },
+        PROCT {
+          tgid: 2,
+          start_time: 30,
+          ..Default::default()
+        },
+        PROCT {
+          tgid: 3,
+          start_time: 20,
+          ..Default::default()
+        },
+      ]
+    };
+    let mut opts = super::PgrepOptions::new(Mode::PGREP);
+    assert_eq!(super::pick_one(procs(), &opts).len(), 3);
+    opts.newest = true;
+    assert_eq!(super::pick_one(procs(), &opts)[0].tgid, 2);
+    opts.newest = false;
+    opts.oldest = true;
+    assert_eq!(super::pick_one(procs(), &opts)[0].tgid, 1);
+  }
+}
@@ -0,0 +1,299 @@
+// pidof, a port of procps pidof.c.
+// a program is found by the basename of argv[0], the target of
+// /proc/<pid>/exe or the command name.
+
+use crate::readproc;
+use crate::readproc::PROCT;
+
+// exit status
+pub const EXIT_FOUND: i32 = 0;
+pub const EXIT_NOT_FOUND: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+#[derive(Debug)]
+pub struct PidofOptions {
+  pub programs: Vec<String>,
+  pub single_shot: bool, // -s
+  pub scripts_too: bool, // -x
+  pub quiet: bool,       // -q
+  pub omit: Vec<i32>,    // -o
+  pub separator: String, // -d
+}
+
+impl Default for PidofOptions {
+  fn default() -> Self {
+    PidofOptions {
+      programs: vec![],
+      single_shot: false,
+      scripts_too: false,
+      quiet: false,
+      omit: vec![],
+      separator: String::from(" "),
+    }
+  }
+}
+
+fn basename(path: &str) -> &str {
+  match path.rfind('/') {
+    Some(ix) => &path[ix + 1..],
+    None => path,
+  }
+}
+
+// %PPID is the parent of pidof, which is the shell of an init script
+fn parse_omit(arg: &str, omit: &mut Vec<i32>) -> Result<(), String> {
+  for item in arg.split(',') {
+    let pid = if item == "%PPID" {
+      unsafe { libc::getppid() }
+    } else {
+      match item.parse::<i32>() {
+        Ok(pid) if pid > 0 => pid,
+        _ => return Err(format!("illegal omit pid value ({})!", item)),
+      }
+    };
+    omit.push(pid);
+  }
+  Ok(())
+}
+
+pub fn parse_args(args: &[String]) -> Result<PidofOptions, String> {
+  let mut opts = PidofOptions::default();
+  let mut ix = 1;
+  let mut only_args = false;
+  while ix < args.len() {
+    let arg = &args[ix];
+    ix += 1;
+    if only_args || !arg.starts_with('-') || arg == "-" {
+      opts.programs.push(arg.clone());
+      continue;
+    }
+    match arg.as_str() {
+      "--" => {
+        only_args = true;
+        continue;
+      }
+      "--help" => {
+        print!("{}", usage());
+        std::process::exit(EXIT_FOUND);
+      }
+      "--version" => {
+        println!("pidof from rustyps {}", env!("CARGO_PKG_VERSION"));
+        std::process::exit(EXIT_FOUND);
+      }
+      _ if arg.starts_with("--") => return Err(format!("unrecognized option '{}'", arg)),
+      _ => {}
+    }
+    let chars: Vec<char> = arg[1..].chars().collect();
+    for (cix, c) in chars.iter().enumerate() {
+      match c {
+        's' => opts.single_shot = true,
+        'x' => opts.scripts_too = true,
+        'q' => opts.quiet = true,
+        'o' | 'd' => {
+          let rest: String = chars[cix + 1..].iter().collect();
+          let val = if !rest.is_empty() {
+            rest
+          } else if ix < args.len() {
+            ix += 1;
+            args[ix - 1].clone()
+          } else {
+            return Err(format!("option requires an argument -- '{}'", c));
+          };
+          if *c == 'o' {
+            parse_omit(&val, &mut opts.omit)?;
+          } else {
+            opts.separator = val;
+          }
+          break;
+        }
+        'h' => {
+          print!("{}", usage());
+          std::process::exit(EXIT_FOUND);
+        }
+        'V' => {
+          println!("pidof from rustyps {}", env!("CARGO_PKG_VERSION"));
+          std::process::exit(EXIT_FOUND);
+        }
+        _ => return Err(format!("invalid option -- '{}'", c)),
+      }
+    }
+  }
+  Ok(opts)
+}
+
+pub fn usage() -> String {
+  String::from(
+    "
+Usage:
+ pidof [options] [program [...]]
+
+Options:
+ -s                        return only one pid
+ -x                        also find shells running the named scripts
+ -o <pid,...>              omit the pids, %PPID is the parent of pidof
+ -d <sep>                  use the separator instead of a space
+ -q                        quiet mode, only set the exit code
+
+ -h, --help     display this help and exit
+ -V, --version  output version information and exit
+",
+  )
+}
+
+// the first argument of an interpreter, skipping its options:
+// "/bin/sh -e /etc/init.d/foo" gives "/etc/init.d/foo"
+fn script_arg(cmdline: &[String]) -> Option<&str> {
+  cmdline
+    .iter()
+    .skip(1)
+    .find(|arg| !arg.starts_with('-'))
+    .map(|arg| arg.as_str())
+}
+
+// is @p an instance of @program, which is a name or a full path
+pub fn match_program(p: &PROCT, program: &str, scripts_too: bool) -> bool {
+  let program_base = basename(program);
+  if program_base.is_empty() {
+    return false;
+  }
+  if let Some(arg0) = p.cmdline.first() {
+    if arg0 == program || basename(arg0) == program_base {
+      return true;
+    }
+  }
+  if !p.exe.is_empty() && (p.exe == program || basename(&p.exe) == program_base) {
+    return true;
+  }
+  if scripts_too {
+    if let Some(arg) = script_arg(&p.cmdline) {
+      if arg == program || basename(arg) == program_base {
+        return true;
+      }
+    }
+  }
+  // kernel threads have neither argv nor exe
+  p.cmdline.is_empty() && p.cmd == program_base
+}
+
+// pids of the matching processes, newest first like procps
+pub fn select_procs(opts: &PidofOptions) -> Result<Vec<i32>, String> {
+  let flags = readproc::PROC_FILLSTAT | readproc::PROC_FILLARG | readproc::PROC_FILLEXE;
+  let mut pt = readproc::openproc(flags, None, None)?;
+  let myself = std::process::id() as i32;
+  let mut pids = vec![];
+  while let Some(p) = readproc::readproc(&mut pt) {
+    if p.tgid == myself || opts.omit.contains(&p.tgid) {
+      continue;
+    }
+    if opts
+      .programs
+      .iter()
+      .any(|program| match_program(&p, program, opts.scripts_too))
+    {
+      pids.push(p.tgid);
+    }
+  }
+  pids.reverse();
+  if opts.single_shot {
+    pids.truncate(1);
+  }
+  Ok(pids)
+}
+
+// main of pidof. returns the exit status.
+pub fn run(args: Vec<String>) -> i32 {
+  let opts = match parse_args(&args) {
+    Ok(opts) => opts,
+    Err(msg) => {
+      eprintln!("pidof: {}", msg);
+      eprint!("{}", usage());
+      return EXIT_USAGE;
+    }
+  };
+  if opts.programs.is_empty() {
+    return EXIT_NOT_FOUND;
+  }
+  let pids = match select_procs(&opts) {
+    Ok(pids) => pids,
+    Err(msg) => {
+      eprintln!("pidof: {}", msg);
+      return EXIT_NOT_FOUND;
+    }
+  };
+  if pids.is_empty() {
+    return EXIT_NOT_FOUND;
+  }
+  if !opts.quiet {
+    let pids: Vec<String> = pids.iter().map(|pid| pid.to_string()).collect();
+    println!("{}", pids.join(&opts.separator));
+  }
+  EXIT_FOUND
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::readproc::PROCT;
+
+  fn args(v: &[&str]) -> Vec<String> {
+    v.iter().map(|s| String::from(*s)).collect()
+  }
+
+  fn proct(cmd: &str, cmdline: &[&str], exe: &str) -> PROCT {
+    PROCT {
+      cmd: String::from(cmd),
+      cmdline: args(cmdline),
+      exe: String::from(exe),
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn parse_pidof_options() {
+    let opts =
+      super::parse_args(&args(&["pidof", "-sx", "-o", "1,2", "-o3", "sshd", "cron"])).unwrap();
+    assert!(opts.single_shot && opts.scripts_too && !opts.quiet);
+    assert_eq!(opts.omit, vec![1, 2, 3]);
+    assert_eq!(opts.programs, vec!["sshd", "cron"]);
+    let opts = super::parse_args(&args(&["pidof", "-o", "%PPID", "-q", "-d,", "x"])).unwrap();
+    assert_eq!(opts.omit, vec![unsafe { libc::getppid() }]);
+    assert!(opts.quiet);
+    assert_eq!(opts.separator, ",");
+
+    assert!(super::parse_args(&args(&["pidof", "-o", "abc", "x"])).is_err());
+    assert!(super::parse_args(&args(&["pidof", "-o"])).is_err());
+    assert!(super::parse_args(&args(&["pidof", "-z", "x"])).is_err());
+  }
+
+  #[test]
+  fn match_program_names() {
+    let sshd = proct("sshd", &["/usr/sbin/sshd", "-D"], "/usr/sbin/sshd");
+    assert!(super::match_program(&sshd, "sshd", false));
+    assert!(super::match_program(&sshd, "/usr/sbin/sshd", false));
+    assert!(!super::match_program(&sshd, "ssh", false));
+    // argv[0] rewritten by the program itself
+    let renamed = proct(
+      "postgres",
+      &["postgres: writer"],
+      "/usr/lib/postgresql/bin/postgres",
+    );
+    assert!(super::match_program(&renamed, "postgres", false));
+    // kernel threads
+    let kthread = proct("kthreadd", &[], "");
+    assert!(super::match_program(&kthread, "kthreadd", false));
+    assert!(!super::match_program(&sshd, "", false));
+  }
+
+  #[test]
+  fn match_scripts() {
+    let script = proct(
+      "sh",
+      &["/bin/sh", "-e", "/etc/init.d/foo", "start"],
+      "/bin/dash",
+    );
+    assert!(!super::match_program(&script, "foo", false));
+    assert!(super::match_program(&script, "foo", true));
+    assert!(super::match_program(&script, "/etc/init.d/foo", true));
+    assert!(!super::match_program(&script, "start", true));
+    assert!(super::match_program(&script, "sh", false));
+  }
+}
@@ -0,0 +1,568 @@
+// pmap, a port of procps pmap.c.
+// the mappings come from /proc/<pid>/smaps, or maps if smaps is not readable.
+
+use crate::readproc;
+use crate::readproc::{MapEntry, PROCT};
+
+// exit status
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_USAGE: i32 = 1;
+pub const EXIT_NOT_FOUND: i32 = 42; // some pid was not found, like procps
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  DEFAULT,
+  EXTENDED, // -x
+  DEVICE,   // -d
+  X,        // -X
+  XX,       // -XX
+}
+
+#[derive(Debug)]
+pub struct PmapOptions {
+  pub pids: Vec<i32>,
+  pub format: Format,
+  pub quiet: bool,     // -q
+  pub show_path: bool, // -p
+}
+
+impl Default for PmapOptions {
+  fn default() -> Self {
+    PmapOptions {
+      pids: vec![],
+      format: Format::DEFAULT,
+      quiet: false,
+      show_path: false,
+    }
+  }
+}
+
+// smaps fields left out by -X
+const X_HIDDEN: &[&str] = &[
+  "KernelPageSize",
+  "MMUPageSize",
+  "Shared_Clean",
+  "Shared_Dirty",
+  "Private_Clean",
+  "Private_Dirty",
+];
+
+fn basename(path: &str) -> &str {
+  match path.rfind('/') {
+    Some(ix) => &path[ix + 1..],
+    None => path,
+  }
+}
+
+fn set_format(opts: &mut PmapOptions, format: Format) -> Result<(), String> {
+  let format = match (opts.format, format) {
+    (Format::DEFAULT, f) => f,
+    (Format::X, Format::X) => Format::XX,
+    _ => return Err(String::from("options -d, -x, -X are mutually exclusive")),
+  };
+  opts.format = format;
+  Ok(())
+}
+
+pub fn parse_args(args: &[String]) -> Result<PmapOptions, String> {
+  let mut opts = PmapOptions::default();
+  let mut only_args = false;
+  for arg in args.iter().skip(1) {
+    if only_args || !arg.starts_with('-') || arg == "-" {
+      match arg.parse::<i32>() {
+        Ok(pid) if pid > 0 => opts.pids.push(pid),
+        _ => return Err(format!("invalid process id: {}", arg)),
+      }
+      continue;
+    }
+    match arg.as_str() {
+      "--" => {
+        only_args = true;
+        continue;
+      }
+      "--extended" => set_format(&mut opts, Format::EXTENDED)?,
+      "--device" => set_format(&mut opts, Format::DEVICE)?,
+      "--quiet" => opts.quiet = true,
+      "--show-path" => opts.show_path = true,
+      "--help" => {
+        print!("{}", usage());
+        std::process::exit(EXIT_SUCCESS);
+      }
+      "--version" => {
+        println!("pmap from rustyps {}", env!("CARGO_PKG_VERSION"));
+        std::process::exit(EXIT_SUCCESS);
+      }
+      _ if arg.starts_with("--") => return Err(format!("unrecognized option '{}'", arg)),
+      _ => {
+        for c in arg[1..].chars() {
+          match c {
+            'x' => set_format(&mut opts, Format::EXTENDED)?,
+            'd' => set_format(&mut opts, Format::DEVICE)?,
+            'X' => set_format(&mut opts, Format::X)?,
+            'q' => opts.quiet = true,
+            'p' => opts.show_path = true,
+            'h' => {
+              print!("{}", usage());
+              std::process::exit(EXIT_SUCCESS);
+            }
+            'V' => {
+              println!("pmap from rustyps {}", env!("CARGO_PKG_VERSION"));
+              std::process::exit(EXIT_SUCCESS);
+            }
+            _ => return Err(format!("invalid option -- '{}'", c)),
+          }
+        }
+      }
+    }
+  }
+  if opts.pids.is_empty() {
+    return Err(String::from("argument missing"));
+  }
+  Ok(opts)
+}
+
+pub fn usage() -> String {
+  String::from(
+    "
+Usage:
+ pmap [options] PID [PID ...]
+
+Options:
+ -x, --extended              show details
+ -X                          show even more details
+            WARNING: format changes according to /proc/PID/smaps
+ -XX                         show everything the kernel provides
+ -d, --device                show the device format
+ -q, --quiet                 do not display header and footer
+ -p, --show-path             show path in the mapping
+
+ -h, --help     display this help and exit
+ -V, --version  output version information and exit
+",
+  )
+}
+
+// "r-x--": the permissions, 's' if shared and 'R' if no swap space is reserved
+fn mode(m: &MapEntry) -> String {
+  let mut s: String = m.perms.chars().take(3).collect();
+  s.push(if m.is_shared() { 's' } else { '-' });
+  let noreserve = m.vm_flags.split(' ').any(|flag| flag == "nr");
+  s.push(if noreserve { 'R' } else { '-' });
+  s
+}
+
+// the mapping column of the default, -x and -d formats
+fn mapping_name(m: &MapEntry, show_path: bool) -> String {
+  if m.path == "[stack]" {
+    return String::from("  [ stack ]");
+  }
+  if m.path.is_empty() || m.path.starts_with('[') {
+    return String::from("  [ anon ]");
+  }
+  if show_path {
+    m.path.clone()
+  } else {
+    String::from(basename(&m.path))
+  }
+}
+
+// "-" if smaps was not readable
+fn smaps_value(m: &MapEntry, val: u64) -> String {
+  if m.has_smaps {
+    val.to_string()
+  } else {
+    String::from("-")
+  }
+}
+
+fn command_line(p: &PROCT) -> String {
+  if p.cmdline.is_empty() {
+    format!("[{}]", p.cmd)
+  } else {
+    p.cmdline.join(" ")
+  }
+}
+
+fn format_default(p: &PROCT, opts: &PmapOptions) -> Vec<String> {
+  let mut lines = vec![];
+  for m in p.maps.iter() {
+    lines.push(format!(
+      "{:016x} {:>6}K {} {}",
+      m.start,
+      m.size(),
+      mode(m),
+      mapping_name(m, opts.show_path)
+    ));
+  }
+  if !opts.quiet {
+    let total: u64 = p.maps.iter().map(|m| m.size()).sum();
+    lines.push(format!(" total {:>16}K", total));
+  }
+  lines
+}
+
+fn format_extended(p: &PROCT, opts: &PmapOptions) -> Vec<String> {
+  let mut lines = vec![];
+  if !opts.quiet {
+    lines.push(String::from(
+      "Address           Kbytes     RSS   Dirty Mode  Mapping",
+    ));
+  }
+  for m in p.maps.iter() {
+    lines.push(format!(
+      "{:016x} {:>7} {:>7} {:>7} {}  {}",
+      m.start,
+      m.size(),
+      smaps_value(m, m.rss),
+      smaps_value(m, m.shared_dirty + m.private_dirty),
+      mode(m),
+      mapping_name(m, opts.show_path)
+    ));
+  }
+  if !opts.quiet {
+    let sum = |f: fn(&MapEntry) -> u64| -> u64 { p.maps.iter().map(f).sum() };
+    lines.push(String::from("---------------- ------- ------- ------- "));
+    lines.push(format!(
+      "total kB         {:>7} {:>7} {:>7}",
+      sum(|m| m.size()),
+      sum(|m| m.rss),
+      sum(|m| m.shared_dirty + m.private_dirty)
+    ));
+  }
+  lines
+}
+
+fn format_device(p: &PROCT, opts: &PmapOptions) -> Vec<String> {
+  let mut lines = vec![];
+  if !opts.quiet {
+    lines.push(String::from(
+      "Address           Kbytes Mode  Offset           Device    Mapping",
+    ));
+  }
+  for m in p.maps.iter() {
+    lines.push(format!(
+      "{:016x} {:>7} {} {:016x} {:03x}:{:05x} {}",
+      m.start,
+      m.size(),
+      mode(m),
+      m.offset,
+      m.dev_major,
+      m.dev_minor,
+      mapping_name(m, opts.show_path)
+    ));
+  }
+  if !opts.quiet {
+    let mapped: u64 = p.maps.iter().map(|m| m.size()).sum();
+    let shared: u64 = p
+      .maps
+      .iter()
+      .filter(|m| m.is_shared())
+      .map(|m| m.size())
+      .sum();
+    let private_writable: u64 = p
+      .maps
+      .iter()
+      .filter(|m| !m.is_shared() && m.perms.as_bytes().get(1) == Some(&b'w'))
+      .map(|m| m.size())
+      .sum();
+    lines.push(format!(
+      "mapped: {}K    writeable/private: {}K    shared: {}K",
+      mapped, private_writable, shared
+    ));
+  }
+  lines
+}
+
+// -X and -XX: a column for each field of smaps, as wide as its values
+fn format_smaps(p: &PROCT, opts: &PmapOptions) -> Vec<String> {
+  let all = opts.format == Format::XX;
+  let keys: Vec<&str> = match p.maps.iter().find(|m| m.has_smaps) {
+    Some(m) => m
+      .fields
+      .iter()
+      .map(|(key, _)| key.as_str())
+      .filter(|key| all || !X_HIDDEN.contains(key))
+      .collect(),
+    None => vec![],
+  };
+  let value = |m: &MapEntry, key: &str| -> u64 {
+    m.fields
+      .iter()
+      .find(|(k, _)| k == key)
+      .map_or(0, |(_, val)| *val)
+  };
+
+  let mut head = vec!["Address", "Perm", "Offset", "Device", "Inode"];
+  head.extend(keys.iter());
+  if all {
+    head.push("VmFlags");
+  }
+  let mut rows: Vec<Vec<String>> = vec![];
+  for m in p.maps.iter() {
+    let mut row = vec![
+      format!("{:x}", m.start),
+      m.perms.clone(),
+      format!("{:08x}", m.offset),
+      format!("{:02x}:{:02x}", m.dev_major, m.dev_minor),
+      m.inode.to_string(),
+    ];
+    row.extend(keys.iter().map(|key| value(m, key).to_string()));
+    if all {
+      row.push(m.vm_flags.clone());
+    }
+    let path = if opts.show_path {
+      m.path.as_str()
+    } else {
+      basename(&m.path)
+    };
+    row.push(String::from(path));
+    rows.push(row);
+  }
+  let totals: Vec<String> = keys
+    .iter()
+    .map(|key| {
+      p.maps
+        .iter()
+        .map(|m| value(m, key))
+        .sum::<u64>()
+        .to_string()
+    })
+    .collect();
+
+  let mut widths: Vec<usize> = head.iter().map(|h| h.len()).collect();
+  for row in rows.iter() {
+    for (width, cell) in widths.iter_mut().zip(row.iter()) {
+      *width = (*width).max(cell.len());
+    }
+  }
+  for (ix, total) in totals.iter().enumerate() {
+    widths[5 + ix] = widths[5 + ix].max(total.len());
+  }
+
+  let join = |cells: &[String]| -> String {
+    let line: Vec<String> = cells
+      .iter()
+      .enumerate()
+      .map(|(ix, cell)| match widths.get(ix) {
+        Some(width) => format!("{:>1$}", cell, width),
+        None => cell.clone(),
+      })
+      .collect();
+    line.join(" ")
+  };
+  let mut lines = vec![];
+  if !opts.quiet {
+    let mut head: Vec<String> = head.iter().map(|h| String::from(*h)).collect();
+    head.push(String::from("Mapping"));
+    lines.push(join(&head));
+  }
+  for row in rows.iter() {
+    lines.push(join(row));
+  }
+  if !opts.quiet {
+    // the totals under the fields of smaps
+    let blank: Vec<String> = vec![String::new(); 5];
+    let mut sep = blank.clone();
+    sep.extend(widths[5..5 + keys.len()].iter().map(|w| "=".repeat(*w)));
+    lines.push(join(&sep));
+    let mut sum = blank;
+    sum.extend(totals);
+    lines.push(join(&sum) + " KB");
+  }
+  lines
+    .iter()
+    .map(|line| String::from(line.trim_end()))
+    .collect()
+}
+
+// the report of a process
+pub fn format_proc(p: &PROCT, opts: &PmapOptions) -> Vec<String> {
+  let mut lines = vec![format!("{}:   {}", p.tgid, command_line(p))];
+  lines.append(&mut match opts.format {
+    Format::DEFAULT => format_default(p, opts),
+    Format::EXTENDED => format_extended(p, opts),
+    Format::DEVICE => format_device(p, opts),
+    Format::X | Format::XX => format_smaps(p, opts),
+  });
+  lines
+}
+
+// main of pmap. returns the exit status.
+pub fn run(args: Vec<String>) -> i32 {
+  let opts = match parse_args(&args) {
+    Ok(opts) => opts,
+    Err(msg) => {
+      eprintln!("pmap: {}", msg);
+      eprint!("{}", usage());
+      return EXIT_USAGE;
+    }
+  };
+  let flags =
+    readproc::PROC_FILLSTAT | readproc::PROC_FILLARG | readproc::PROC_FILLMAPS | readproc::PROC_PID;
+  let mut pt = match readproc::openproc(flags, Some(opts.pids.clone()), None) {
+    Ok(pt) => pt,
+    Err(msg) => {
+      eprintln!("pmap: {}", msg);
+      return EXIT_NOT_FOUND;
+    }
+  };
+  let mut found = 0;
+  while let Some(p) = readproc::readproc(&mut pt) {
+    found += 1;
+    for line in format_proc(&p, &opts) {
+      println!("{}", line);
+    }
+  }
+  if found < opts.pids.len() {
+    return EXIT_NOT_FOUND;
+  }
+  EXIT_SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Format, PmapOptions};
+  use crate::readproc::PROCT;
+
+  fn args(v: &[&str]) -> Vec<String> {
+    v.iter().map(|s| String::from(*s)).collect()
+  }
+
+  fn sample() -> PROCT {
+    let maps = crate::readproc::maps2proc(
+      "55d0c4a00000-55d0c4a02000 r-xp 00001000 08:01 1311 /usr/bin/cat
+Size:                  8 kB
+KernelPageSize:        4 kB
+Rss:                   8 kB
+Pss:                   4 kB
+Private_Dirty:         0 kB
+Swap:                  0 kB
+VmFlags: rd ex mr mw me
+55d0c4c00000-55d0c4c21000 rw-p 00000000 00:00 0 [heap]
+Size:                132 kB
+KernelPageSize:        4 kB
+Rss:                  12 kB
+Pss:                  12 kB
+Private_Dirty:        12 kB
+Swap:                  4 kB
+VmFlags: rd wr mr mw me ac
+7f0000000000-7f0000001000 rw-s 00000000 00:05 42 /dev/shm/x
+Size:                  4 kB
+KernelPageSize:        4 kB
+Rss:                   4 kB
+Pss:                   2 kB
+Private_Dirty:         0 kB
+Swap:                  0 kB
+VmFlags: rd wr sh mr mw me ms nr
+7ffd1e4fe000-7ffd1e51f000 rw-p 00000000 00:00 0 [stack]
+Size:                132 kB
+KernelPageSize:        4 kB
+Rss:                   8 kB
+Pss:                   8 kB
+Private_Dirty:         8 kB
+Swap:                  0 kB
+VmFlags: rd wr mr mw me gd ac
+",
+    );
+    PROCT {
+      tgid: 321,
+      cmd: String::from("cat"),
+      cmdline: args(&["cat", "-"]),
+      maps,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn parse_pmap_options() {
+    let opts = super::parse_args(&args(&["pmap", "-xq", "1", "22"])).unwrap();
+    assert_eq!(opts.format, Format::EXTENDED);
+    assert!(opts.quiet && !opts.show_path);
+    assert_eq!(opts.pids, vec![1, 22]);
+    assert_eq!(
+      super::parse_args(&args(&["pmap", "-X", "-X", "1"]))
+        .unwrap()
+        .format,
+      Format::XX
+    );
+    assert_eq!(
+      super::parse_args(&args(&["pmap", "-XX", "1"]))
+        .unwrap()
+        .format,
+      Format::XX
+    );
+    assert!(super::parse_args(&args(&["pmap", "-x", "-d", "1"])).is_err());
+    assert!(super::parse_args(&args(&["pmap", "-x"])).is_err());
+    assert!(super::parse_args(&args(&["pmap", "abc"])).is_err());
+  }
+
+  #[test]
+  fn default_extended_and_device() {
+    let p = sample();
+    let mut opts = PmapOptions::default();
+    assert_eq!(
+      super::format_proc(&p, &opts),
+      vec![
+        "321:   cat -",
+        "000055d0c4a00000      8K r-x-- cat",
+        "000055d0c4c00000    132K rw---   [ anon ]",
+        "00007f0000000000      4K rw-sR x",
+        "00007ffd1e4fe000    132K rw---   [ stack ]",
+        " total              276K",
+      ]
+    );
+    opts.format = Format::EXTENDED;
+    opts.show_path = true;
+    let lines = super::format_proc(&p, &opts);
+    assert_eq!(
+      lines[1],
+      "Address           Kbytes     RSS   Dirty Mode  Mapping"
+    );
+    assert_eq!(
+      lines[2],
+      "000055d0c4a00000       8       8       0 r-x--  /usr/bin/cat"
+    );
+    assert_eq!(lines[7], "total kB             276      32      20");
+    opts.format = Format::DEVICE;
+    opts.quiet = true;
+    let lines = super::format_proc(&p, &opts);
+    assert_eq!(lines.len(), 5);
+    assert_eq!(
+      lines[1],
+      "000055d0c4a00000       8 r-x-- 0000000000001000 008:00001 /usr/bin/cat"
+    );
+    opts.quiet = false;
+    assert_eq!(
+      super::format_proc(&p, &opts).last().unwrap(),
+      "mapped: 276K    writeable/private: 264K    shared: 4K"
+    );
+  }
+
+  #[test]
+  fn smaps_columns() {
+    let p = sample();
+    let mut opts = PmapOptions {
+      format: Format::X,
+      ..Default::default()
+    };
+    let lines = super::format_proc(&p, &opts);
+    assert_eq!(
+      lines[1],
+      "     Address Perm   Offset Device Inode Size Rss Pss Swap Mapping"
+    );
+    assert_eq!(
+      lines[2],
+      "55d0c4a00000 r-xp 00001000  08:01  1311    8   8   4    0 cat"
+    );
+    assert_eq!(
+      lines[3],
+      "55d0c4c00000 rw-p 00000000  00:00     0  132  12  12    4 [heap]"
+    );
+    assert_eq!(lines[6], format!("{}==== === === ====", " ".repeat(40)));
+    assert_eq!(lines[7], format!("{} 276  32  26    4 KB", " ".repeat(40)));
+    opts.format = Format::XX;
+    let lines = super::format_proc(&p, &opts);
+    assert!(lines[1].contains(" KernelPageSize ") && lines[1].contains(" Private_Dirty "));
+    assert!(lines[4].contains(" rd wr sh mr mw me ms nr x"));
+  }
+}
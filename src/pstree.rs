@@ -0,0 +1,1033 @@
+// pstree, a port of psmisc pstree.c on top of readproc.
+// the tree is built from PROCT::ppid, and threads hang under their process.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::pwcache;
+use crate::readproc;
+use crate::readproc::PROCT;
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+
+// width used when stdout is not a terminal, like psmisc
+const DEFAULT_WIDTH: usize = 132;
+
+const BOLD: &str = "\x1b[1m";
+const SGR0: &str = "\x1b[0m";
+
+// line-drawing pieces, the names tell their width
+#[derive(Debug, PartialEq)]
+pub struct Symbols {
+  pub empty_2: &'static str,
+  pub branch_2: &'static str,
+  pub vert_2: &'static str,
+  pub last_2: &'static str,
+  pub single_3: &'static str,
+  pub first_3: &'static str,
+}
+
+pub static SYM_ASCII: Symbols = Symbols {
+  empty_2: "  ",
+  branch_2: "|-",
+  vert_2: "| ",
+  last_2: "`-",
+  single_3: "---",
+  first_3: "-+-",
+};
+
+pub static SYM_UTF: Symbols = Symbols {
+  empty_2: "  ",
+  branch_2: "├─",
+  vert_2: "│ ",
+  last_2: "└─",
+  single_3: "───",
+  first_3: "─┬─",
+};
+
+// DEC special graphics, switched on by "ESC ( 0" and off by "ESC ( B"
+pub static SYM_VT100: Symbols = Symbols {
+  empty_2: "  ",
+  branch_2: "\x1b(0tq\x1b(B",
+  vert_2: "\x1b(0x\x1b(B ",
+  last_2: "\x1b(0mq\x1b(B",
+  single_3: "\x1b(0qqq\x1b(B",
+  first_3: "\x1b(0qwq\x1b(B",
+};
+
+#[derive(Debug)]
+pub struct PstreeOptions {
+  pub pid: Option<i32>,          // root of the tree
+  pub user: Option<u32>,         // trees of the processes of the user
+  pub show_args: bool,           // -a
+  pub compact: bool,             // cleared by -c
+  pub show_pgids: bool,          // -g
+  pub highlight: Option<i32>,    // -H, -h
+  pub long: bool,                // -l
+  pub numeric_sort: bool,        // -n
+  pub ns: Option<usize>,         // -N, index of readproc::NS_NAMES
+  pub show_pids: bool,           // -p
+  pub show_parents: bool,        // -s
+  pub thread_names: bool,        // -t
+  pub hide_threads: bool,        // -T
+  pub user_change: bool,         // -u
+  pub symbols: &'static Symbols, // -A, -G, -U
+  pub width: usize,
+  pub bold: bool, // highlighting is shown only on terminals
+}
+
+impl Default for PstreeOptions {
+  fn default() -> Self {
+    PstreeOptions {
+      pid: None,
+      user: None,
+      show_args: false,
+      compact: true,
+      show_pgids: false,
+      highlight: None,
+      long: false,
+      numeric_sort: false,
+      ns: None,
+      show_pids: false,
+      show_parents: false,
+      thread_names: false,
+      hide_threads: false,
+      user_change: false,
+      symbols: &SYM_ASCII,
+      width: DEFAULT_WIDTH,
+      bold: false,
+    }
+  }
+}
+
+// a process or a thread in the tree
+#[derive(Debug, Default, Clone)]
+pub struct TreeNode {
+  pub pid: i32,
+  pub ppid: i32,
+  pub pgid: i32,
+  pub uid: u32,
+  pub name: String, // comm, or {comm} for threads
+  pub args: Vec<String>,
+  pub thread: bool,
+  pub ns: u64,
+}
+
+impl TreeNode {
+  pub fn from_proc(p: &PROCT, ns: Option<usize>) -> TreeNode {
+    TreeNode {
+      pid: p.tgid,
+      ppid: p.ppid,
+      pgid: p.pgrp,
+      uid: p.euid,
+      name: p.cmd.clone(),
+      args: p.cmdline.iter().skip(1).cloned().collect(),
+      thread: false,
+      ns: ns.map_or(0, |ix| p.ns[ix]),
+    }
+  }
+
+  // @t is a thread of the process @p
+  pub fn from_thread(p: &PROCT, t: &PROCT, ns: Option<usize>, thread_names: bool) -> TreeNode {
+    TreeNode {
+      pid: t.tid,
+      ppid: p.tgid,
+      pgid: p.pgrp,
+      uid: p.euid,
+      name: format!("{{{}}}", if thread_names { &t.cmd } else { &p.cmd }),
+      args: vec![],
+      thread: true,
+      ns: ns.map_or(0, |ix| p.ns[ix]),
+    }
+  }
+}
+
+pub struct ProcTree {
+  pub nodes: Vec<TreeNode>,
+  pub children: Vec<Vec<usize>>,
+  pub parent: Vec<Option<usize>>,
+  index: HashMap<i32, usize>,
+}
+
+impl ProcTree {
+  // link the nodes by ppid. in namespace mode, a child in another
+  // namespace than its parent starts a tree of its own.
+  pub fn build(nodes: Vec<TreeNode>, opts: &PstreeOptions) -> ProcTree {
+    let mut index = HashMap::new();
+    for (ix, node) in nodes.iter().enumerate() {
+      if !node.thread {
+        index.insert(node.pid, ix);
+      }
+    }
+    let mut children = vec![vec![]; nodes.len()];
+    let mut parent = vec![None; nodes.len()];
+    for (ix, node) in nodes.iter().enumerate() {
+      let pix = match index.get(&node.ppid) {
+        Some(pix) if *pix != ix => *pix,
+        _ => continue,
+      };
+      if opts.ns.is_some() && nodes[pix].ns != node.ns {
+        continue;
+      }
+      children[pix].push(ix);
+      parent[ix] = Some(pix);
+    }
+    for list in children.iter_mut() {
+      if opts.numeric_sort {
+        list.sort_by_key(|ix| nodes[*ix].pid);
+      } else {
+        list
+          .sort_by(|a, b| (&nodes[*a].name, nodes[*a].pid).cmp(&(&nodes[*b].name, nodes[*b].pid)));
+      }
+    }
+    ProcTree {
+      nodes,
+      children,
+      parent,
+      index,
+    }
+  }
+
+  pub fn find(&self, pid: i32) -> Option<usize> {
+    self.index.get(&pid).copied()
+  }
+
+  // @ix and all of its ancestors
+  fn ancestors(&self, ix: usize) -> Vec<usize> {
+    let mut ret = vec![ix];
+    let mut cur = ix;
+    while let Some(pix) = self.parent[cur] {
+      // the kernel never makes a loop, but a racy read of /proc can
+      if ret.contains(&pix) {
+        break;
+      }
+      ret.push(pix);
+      cur = pix;
+    }
+    ret
+  }
+
+  // nodes without a parent, ordered by pid
+  fn tops(&self) -> Vec<usize> {
+    let mut tops: Vec<usize> = (0..self.nodes.len())
+      .filter(|ix| self.parent[*ix].is_none())
+      .collect();
+    tops.sort_by_key(|ix| self.nodes[*ix].pid);
+    tops
+  }
+
+  // the nodes to start from
+  pub fn roots(&self, opts: &PstreeOptions) -> Vec<usize> {
+    if let Some(uid) = opts.user {
+      let mut roots: Vec<usize> = (0..self.nodes.len())
+        .filter(|ix| {
+          let node = &self.nodes[*ix];
+          !node.thread
+            && node.uid == uid
+            && self.parent[*ix].is_none_or(|pix| self.nodes[pix].uid != uid)
+        })
+        .collect();
+      roots.sort_by_key(|ix| self.nodes[*ix].pid);
+      return roots;
+    }
+    match opts.pid {
+      Some(pid) => match self.find(pid) {
+        Some(ix) if opts.show_parents => vec![*self.ancestors(ix).last().unwrap()],
+        Some(ix) => vec![ix],
+        None => vec![],
+      },
+      // pid 1 is missing in some containers
+      None => match self.find(1) {
+        Some(ix) => vec![ix],
+        None => self.tops(),
+      },
+    }
+  }
+
+  // are the subtrees of @a and @b shown the same
+  fn tree_equal(&self, a: usize, b: usize, opts: &PstreeOptions) -> bool {
+    let (na, nb) = (&self.nodes[a], &self.nodes[b]);
+    if na.name != nb.name || na.thread != nb.thread {
+      return false;
+    }
+    if opts.user_change && na.uid != nb.uid {
+      return false;
+    }
+    let shown = |ix: usize| -> Vec<usize> {
+      self.children[ix]
+        .iter()
+        .copied()
+        .filter(|c| !opts.hide_threads || !self.nodes[*c].thread)
+        .collect()
+    };
+    let (ca, cb) = (shown(a), shown(b));
+    ca.len() == cb.len()
+      && ca
+        .iter()
+        .zip(cb.iter())
+        .all(|(x, y)| self.tree_equal(*x, *y, opts))
+  }
+}
+
+// a character of the output line, or an escape sequence without width
+enum Cell {
+  Char(char),
+  Raw(String),
+}
+
+struct Out<'a> {
+  opts: &'a PstreeOptions,
+  text: String,
+  line: Vec<Cell>,
+  cur_x: usize,
+  width: Vec<usize>, // width of the name of the node on each level
+  more: Vec<bool>,   // the node on each level has siblings below
+}
+
+impl<'a> Out<'a> {
+  fn new(opts: &'a PstreeOptions) -> Out<'a> {
+    Out {
+      opts,
+      text: String::new(),
+      line: vec![],
+      cur_x: 0,
+      width: vec![],
+      more: vec![],
+    }
+  }
+
+  fn out_str(&mut self, s: &str) {
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+      if c != '\x1b' {
+        self.line.push(Cell::Char(c));
+        self.cur_x += 1;
+        continue;
+      }
+      // "ESC ( x" or "ESC [ ... letter"
+      let mut seq = String::from(c);
+      match chars.next() {
+        Some('(') => {
+          seq.push('(');
+          seq.extend(chars.next());
+        }
+        Some(c) => {
+          seq.push(c);
+          for c in chars.by_ref() {
+            seq.push(c);
+            if c.is_ascii_alphabetic() {
+              break;
+            }
+          }
+        }
+        None => {}
+      }
+      self.line.push(Cell::Raw(seq));
+    }
+  }
+
+  fn out_raw(&mut self, s: &str) {
+    self.line.push(Cell::Raw(String::from(s)));
+  }
+
+  // cut the line at the screen width, marking the cut by '+'
+  fn newline(&mut self) {
+    let truncate = !self.opts.long && self.cur_x > self.opts.width;
+    let mut x = 0;
+    for cell in self.line.drain(..) {
+      match cell {
+        Cell::Raw(seq) => self.text.push_str(&seq),
+        Cell::Char(c) => {
+          if !truncate || x + 1 < self.opts.width {
+            self.text.push(c);
+          } else if x + 1 == self.opts.width {
+            self.text.push('+');
+          }
+          x += 1;
+        }
+      }
+    }
+    self.text.push('\n');
+    self.cur_x = 0;
+  }
+}
+
+// "name(pid,pgid,user)"
+fn node_label(node: &TreeNode, prev_uid: u32, opts: &PstreeOptions) -> String {
+  let mut info = vec![];
+  if opts.show_pids {
+    info.push(node.pid.to_string());
+  }
+  if opts.show_pgids {
+    info.push(node.pgid.to_string());
+  }
+  if opts.user_change && node.uid != prev_uid {
+    info.push(pwcache::user_from_uid(node.uid));
+  }
+  if info.is_empty() {
+    node.name.clone()
+  } else {
+    format!("{}({})", node.name, info.join(","))
+  }
+}
+
+struct Dumper<'a> {
+  tree: &'a ProcTree,
+  opts: &'a PstreeOptions,
+  highlighted: HashSet<usize>,
+  // with -s, only these are shown above the target
+  path: HashSet<usize>,
+  target: Option<usize>,
+}
+
+impl<'a> Dumper<'a> {
+  fn visible_children(&self, ix: usize) -> Vec<usize> {
+    self.tree.children[ix]
+      .iter()
+      .copied()
+      .filter(|c| !self.opts.hide_threads || !self.tree.nodes[*c].thread)
+      .filter(|c| !self.path.contains(&ix) || self.path.contains(c) || Some(*c) == self.target)
+      .collect()
+  }
+
+  // fold identical siblings into (first one, count)
+  fn fold(&self, children: Vec<usize>) -> Vec<(usize, usize)> {
+    let mut groups: Vec<(usize, usize)> = vec![];
+    for child in children {
+      let foldable = self.opts.compact
+        && !self.opts.show_pids
+        && !self.opts.show_pgids
+        && (!self.opts.show_args || self.tree.nodes[child].thread);
+      if foldable {
+        if let Some(group) = groups
+          .iter_mut()
+          .find(|group| self.tree.tree_equal(group.0, child, self.opts))
+        {
+          group.1 += 1;
+          continue;
+        }
+      }
+      groups.push((child, 1));
+    }
+    groups
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn dump(
+    &self,
+    out: &mut Out,
+    ix: usize,
+    level: usize,
+    rep: usize,
+    leaf: bool,
+    last: bool,
+    prev_uid: u32,
+    closing: usize,
+  ) {
+    let sym = self.opts.symbols;
+    let node = &self.tree.nodes[ix];
+    if !leaf {
+      for lvl in 0..level {
+        out.out_str(&" ".repeat(out.width[lvl] + 1));
+        let s = if lvl == level - 1 {
+          if last {
+            sym.last_2
+          } else {
+            sym.branch_2
+          }
+        } else if out.more[lvl + 1] {
+          sym.vert_2
+        } else {
+          sym.empty_2
+        };
+        out.out_str(s);
+      }
+    }
+    let start_x = out.cur_x;
+    if rep > 1 {
+      out.out_str(&format!("{}*[", rep));
+    }
+    let bold = self.opts.bold && self.highlighted.contains(&ix);
+    if bold {
+      out.out_raw(BOLD);
+    }
+    out.out_str(&node_label(node, prev_uid, self.opts));
+    if bold {
+      out.out_raw(SGR0);
+    }
+    let name_width = out.cur_x - start_x;
+    if self.opts.show_args {
+      for arg in node.args.iter() {
+        out.out_str(" ");
+        out.out_str(arg);
+      }
+    }
+
+    let children = self.visible_children(ix);
+    if self.opts.show_args || children.is_empty() {
+      out.out_str(&"]".repeat(closing));
+      out.newline();
+    }
+    if out.width.len() <= level {
+      out.width.resize(level + 1, 0);
+      out.more.resize(level + 1, false);
+    }
+    // children start on the next line with -a
+    out.width[level] = if self.opts.show_args { 1 } else { name_width };
+    out.more[level] = !last;
+    if children.is_empty() {
+      return;
+    }
+    if !self.opts.show_args && !self.opts.long && out.cur_x >= self.opts.width {
+      out.out_str(sym.first_3);
+      out.out_str("+");
+      out.newline();
+      return;
+    }
+
+    let groups = self.fold(children);
+    for (gix, (child, count)) in groups.iter().enumerate() {
+      let next = gix + 1 < groups.len();
+      if gix == 0 && !self.opts.show_args {
+        out.out_str(if next { sym.first_3 } else { sym.single_3 });
+      }
+      let inherited = if !next && !self.opts.show_args {
+        closing
+      } else {
+        0
+      };
+      self.dump(
+        out,
+        *child,
+        level + 1,
+        *count,
+        gix == 0 && !self.opts.show_args,
+        !next,
+        node.uid,
+        inherited + (*count > 1) as usize,
+      );
+    }
+  }
+}
+
+// the whole output of pstree
+pub fn format_tree(tree: &ProcTree, opts: &PstreeOptions) -> Result<String, String> {
+  let mut dumper = Dumper {
+    tree,
+    opts,
+    highlighted: HashSet::new(),
+    path: HashSet::new(),
+    target: None,
+  };
+  if let Some(pid) = opts.highlight {
+    if let Some(ix) = tree.find(pid) {
+      dumper.highlighted = tree.ancestors(ix).into_iter().collect();
+    }
+  }
+  if opts.show_parents {
+    if let Some(ix) = opts.pid.and_then(|pid| tree.find(pid)) {
+      dumper.path = tree.ancestors(ix).into_iter().skip(1).collect();
+      dumper.target = Some(ix);
+    }
+  }
+
+  let mut out = Out::new(opts);
+  if opts.ns.is_some() {
+    // a tree for each namespace, ordered by the inode number
+    let mut groups: Vec<(u64, Vec<usize>)> = vec![];
+    for ix in tree.tops() {
+      if tree.nodes[ix].thread {
+        continue;
+      }
+      let ns = tree.nodes[ix].ns;
+      match groups.iter_mut().find(|(n, _)| *n == ns) {
+        Some((_, roots)) => roots.push(ix),
+        None => groups.push((ns, vec![ix])),
+      }
+    }
+    groups.sort_by_key(|(ns, _)| *ns);
+    for (ns, roots) in groups {
+      out.out_str(&format!("[{}]", ns));
+      out.newline();
+      for ix in roots {
+        dumper.dump(&mut out, ix, 0, 1, true, true, 0, 0);
+      }
+    }
+    return Ok(out.text);
+  }
+
+  let roots = tree.roots(opts);
+  if roots.is_empty() {
+    return Err(match (opts.user, opts.pid) {
+      (Some(uid), _) => format!("no processes of user {}", pwcache::user_from_uid(uid)),
+      (None, Some(pid)) => format!("process {} not found", pid),
+      (None, None) => String::from("no processes found"),
+    });
+  }
+  for ix in roots {
+    dumper.dump(&mut out, ix, 0, 1, true, true, 0, 0);
+  }
+  Ok(out.text)
+}
+
+// read every process, and its threads unless -T
+pub fn read_nodes(opts: &PstreeOptions) -> Result<Vec<TreeNode>, String> {
+  let mut flags = readproc::PROC_FILLSTAT;
+  if opts.show_args {
+    flags |= readproc::PROC_FILLARG;
+  }
+  if opts.ns.is_some() {
+    flags |= readproc::PROC_FILLNS;
+  }
+  let mut pt = readproc::openproc(flags, None, None)?;
+  let mut nodes = vec![];
+  while let Some(p) = readproc::readproc(&mut pt) {
+    nodes.push(TreeNode::from_proc(&p, opts.ns));
+    if opts.hide_threads || p.nlwp <= 1 {
+      continue;
+    }
+    while let Some(t) = readproc::readtask(&mut pt, &p) {
+      if t.tid != p.tgid {
+        nodes.push(TreeNode::from_thread(&p, &t, opts.ns, opts.thread_names));
+      }
+    }
+  }
+  Ok(nodes)
+}
+
+fn is_utf8_locale() -> bool {
+  for var in ["LC_ALL", "LC_CTYPE", "LANG"] {
+    if let Ok(val) = std::env::var(var) {
+      if !val.is_empty() {
+        let val = val.to_lowercase();
+        return val.contains("utf-8") || val.contains("utf8");
+      }
+    }
+  }
+  false
+}
+
+fn output_width() -> usize {
+  if let Some(cols) = std::env::var("COLUMNS")
+    .ok()
+    .and_then(|s| s.parse::<usize>().ok())
+  {
+    if cols > 0 {
+      return cols;
+    }
+  }
+  let mut ws = libc::winsize {
+    ws_row: 0,
+    ws_col: 0,
+    ws_xpixel: 0,
+    ws_ypixel: 0,
+  };
+  if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) } != -1 && ws.ws_col > 0 {
+    return ws.ws_col as usize;
+  }
+  DEFAULT_WIDTH
+}
+
+pub fn parse_args(args: &[String]) -> Result<PstreeOptions, String> {
+  let mut opts = PstreeOptions {
+    symbols: if is_utf8_locale() {
+      &SYM_UTF
+    } else {
+      &SYM_ASCII
+    },
+    ..Default::default()
+  };
+  let mut ix = 1;
+  while ix < args.len() {
+    let arg = &args[ix];
+    ix += 1;
+
+    // (option, attached value)
+    let mut flags: Vec<(String, Option<String>)> = vec![];
+    if let Some(long) = arg.strip_prefix("--") {
+      let (name, val) = match long.find('=') {
+        Some(eq) => (&long[..eq], Some(String::from(&long[eq + 1..]))),
+        None => (long, None),
+      };
+      let short = match name {
+        "arguments" => "a",
+        "ascii" => "A",
+        "compact-not" => "c",
+        "show-pgids" => "g",
+        "vt100" => "G",
+        "highlight-all" => "h",
+        "highlight-pid" => "H",
+        "long" => "l",
+        "numeric-sort" => "n",
+        "ns-sort" => "N",
+        "show-pids" => "p",
+        "show-parents" => "s",
+        "thread-names" => "t",
+        "hide-threads" => "T",
+        "uid-changes" => "u",
+        "unicode" => "U",
+        "version" => "V",
+        "help" => "?",
+        _ => return Err(format!("unrecognized option '{}'", arg)),
+      };
+      flags.push((String::from(short), val));
+    } else if arg.len() > 1 && arg.starts_with('-') {
+      let chars: Vec<char> = arg[1..].chars().collect();
+      for (cix, c) in chars.iter().enumerate() {
+        if *c == 'H' || *c == 'N' {
+          let rest: String = chars[cix + 1..].iter().collect();
+          flags.push((
+            c.to_string(),
+            if rest.is_empty() { None } else { Some(rest) },
+          ));
+          break;
+        }
+        flags.push((c.to_string(), None));
+      }
+    } else {
+      // a pid or a user name
+      if opts.pid.is_some() || opts.user.is_some() {
+        return Err(String::from("only one pid or user can be given"));
+      }
+      match arg.parse::<i32>() {
+        Ok(pid) => opts.pid = Some(pid),
+        Err(_) => match pwcache::uid_from_user(arg) {
+          Some(uid) => opts.user = Some(uid),
+          None => return Err(format!("no such user name: {}", arg)),
+        },
+      }
+      continue;
+    }
+
+    for (name, val) in flags {
+      let mut value = || -> Result<String, String> {
+        if let Some(val) = val.clone() {
+          return Ok(val);
+        }
+        if ix < args.len() {
+          ix += 1;
+          return Ok(args[ix - 1].clone());
+        }
+        Err(format!("option requires an argument -- '{}'", name))
+      };
+      match name.as_str() {
+        "a" => opts.show_args = true,
+        "A" => opts.symbols = &SYM_ASCII,
+        "c" => opts.compact = false,
+        "g" => opts.show_pgids = true,
+        "G" => opts.symbols = &SYM_VT100,
+        "h" => opts.highlight = Some(std::process::id() as i32),
+        "H" => {
+          let val = value()?;
+          match val.parse::<i32>() {
+            Ok(pid) if pid > 0 => opts.highlight = Some(pid),
+            _ => return Err(format!("invalid pid: {}", val)),
+          }
+        }
+        "l" => opts.long = true,
+        "n" => opts.numeric_sort = true,
+        "N" => {
+          let val = value()?;
+          match readproc::get_ns_id(&val) {
+            Some(ns) => opts.ns = Some(ns),
+            None => return Err(format!("unknown namespace type: {}", val)),
+          }
+        }
+        "p" => opts.show_pids = true,
+        "s" => opts.show_parents = true,
+        "t" => opts.thread_names = true,
+        "T" => opts.hide_threads = true,
+        "u" => opts.user_change = true,
+        "U" => opts.symbols = &SYM_UTF,
+        "V" => {
+          println!("pstree from rustyps {}", env!("CARGO_PKG_VERSION"));
+          std::process::exit(EXIT_SUCCESS);
+        }
+        "?" => {
+          print!("{}", usage());
+          std::process::exit(EXIT_SUCCESS);
+        }
+        _ => return Err(format!("invalid option -- '{}'", name)),
+      }
+    }
+  }
+  if opts.show_parents && opts.pid.is_none() {
+    return Err(String::from("-s needs a pid"));
+  }
+  Ok(opts)
+}
+
+pub fn usage() -> String {
+  String::from(
+    "
+Usage:
+ pstree [options] [pid|user]
+
+Options:
+ -a, --arguments       show command line arguments
+ -A, --ascii           use ASCII line drawing characters
+ -c, --compact-not     don't compact identical subtrees
+ -g, --show-pgids      show process group ids; implies -c
+ -G, --vt100           use VT100 line drawing characters
+ -h, --highlight-all   highlight current process and its ancestors
+ -H pid, --highlight-pid=pid
+                       highlight this process and its ancestors
+ -l, --long            don't truncate long lines
+ -n, --numeric-sort    sort output by pid
+ -N type, --ns-sort=type
+                       show a tree for each namespace of the type
+                       (cgroup, ipc, mnt, net, pid, time, user, uts)
+ -p, --show-pids       show pids; implies -c
+ -s, --show-parents    show parents of the selected process
+ -t, --thread-names    show full thread names
+ -T, --hide-threads    hide threads, show only processes
+ -u, --uid-changes     show uid transitions
+ -U, --unicode         use UTF-8 (Unicode) line drawing characters
+ -V, --version         display version information
+
+ pid    start at this pid; default is 1 (init)
+ user   show only trees rooted at processes of this user
+",
+  )
+}
+
+// main of pstree. returns the exit status.
+pub fn run(args: Vec<String>) -> i32 {
+  let mut opts = match parse_args(&args) {
+    Ok(opts) => opts,
+    Err(msg) => {
+      eprintln!("pstree: {}", msg);
+      eprint!("{}", usage());
+      return EXIT_FAILURE;
+    }
+  };
+  opts.width = output_width();
+  opts.bold = unsafe { libc::isatty(libc::STDOUT_FILENO) } == 1;
+  let nodes = match read_nodes(&opts) {
+    Ok(nodes) => nodes,
+    Err(msg) => {
+      eprintln!("pstree: {}", msg);
+      return EXIT_FAILURE;
+    }
+  };
+  let tree = ProcTree::build(nodes, &opts);
+  match format_tree(&tree, &opts) {
+    Ok(text) => {
+      print!("{}", text);
+      EXIT_SUCCESS
+    }
+    Err(msg) => {
+      eprintln!("pstree: {}", msg);
+      EXIT_FAILURE
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{PstreeOptions, TreeNode};
+
+  fn node(pid: i32, ppid: i32, name: &str) -> TreeNode {
+    TreeNode {
+      pid,
+      ppid,
+      pgid: pid,
+      name: String::from(name),
+      ..Default::default()
+    }
+  }
+
+  fn thread(tid: i32, pid: i32, name: &str) -> TreeNode {
+    TreeNode {
+      thread: true,
+      name: format!("{{{}}}", name),
+      ..node(tid, pid, name)
+    }
+  }
+
+  // init with gettys, sshd sessions and a threaded daemon
+  fn sample() -> Vec<TreeNode> {
+    let mut bash = node(21, 20, "bash");
+    bash.uid = 4000000000;
+    bash.args = vec![String::from("-l")];
+    vec![
+      node(1, 0, "init"),
+      node(10, 1, "agetty"),
+      node(11, 1, "agetty"),
+      node(12, 1, "agetty"),
+      node(20, 1, "sshd"),
+      bash,
+      node(30, 1, "rsyslogd"),
+      thread(31, 30, "rsyslogd"),
+      thread(32, 30, "rsyslogd"),
+    ]
+  }
+
+  fn format(opts: &PstreeOptions) -> String {
+    let tree = super::ProcTree::build(sample(), opts);
+    super::format_tree(&tree, opts).unwrap()
+  }
+
+  #[test]
+  fn compact_tree() {
+    let opts = PstreeOptions::default();
+    assert_eq!(
+      format(&opts),
+      "init-+-3*[agetty]
+     |-rsyslogd---2*[{rsyslogd}]
+     `-sshd---bash
+"
+    );
+    let opts = PstreeOptions {
+      symbols: &super::SYM_UTF,
+      ..Default::default()
+    };
+    assert_eq!(
+      format(&opts),
+      "init─┬─3*[agetty]
+     ├─rsyslogd───2*[{rsyslogd}]
+     └─sshd───bash
+"
+    );
+  }
+
+  #[test]
+  fn pids_and_users() {
+    let opts = PstreeOptions {
+      show_pids: true,
+      user_change: true,
+      hide_threads: true,
+      ..Default::default()
+    };
+    assert_eq!(
+      format(&opts),
+      "init(1)-+-agetty(10)
+        |-agetty(11)
+        |-agetty(12)
+        |-rsyslogd(30)
+        `-sshd(20)---bash(21,4000000000)
+"
+    );
+    let opts = PstreeOptions {
+      numeric_sort: true,
+      show_pgids: true,
+      pid: Some(20),
+      ..Default::default()
+    };
+    assert_eq!(format(&opts), "sshd(20)---bash(21)\n");
+  }
+
+  #[test]
+  fn arguments_and_parents() {
+    let opts = PstreeOptions {
+      show_args: true,
+      ..Default::default()
+    };
+    assert_eq!(
+      format(&opts),
+      "init
+  |-agetty
+  |-agetty
+  |-agetty
+  |-rsyslogd
+  |   `-2*[{rsyslogd}]
+  `-sshd
+      `-bash -l
+"
+    );
+    let opts = PstreeOptions {
+      show_parents: true,
+      pid: Some(21),
+      ..Default::default()
+    };
+    assert_eq!(format(&opts), "init---sshd---bash\n");
+  }
+
+  #[test]
+  fn folded_subtrees_are_closed() {
+    let mut nodes = sample();
+    nodes.push(node(40, 1, "cron"));
+    nodes.push(node(41, 40, "sh"));
+    nodes.push(node(42, 1, "cron"));
+    nodes.push(node(43, 42, "sh"));
+    let opts = PstreeOptions {
+      hide_threads: true,
+      pid: Some(1),
+      ..Default::default()
+    };
+    let tree = super::ProcTree::build(nodes, &opts);
+    assert_eq!(
+      super::format_tree(&tree, &opts).unwrap(),
+      "init-+-3*[agetty]
+     |-2*[cron---sh]
+     |-rsyslogd
+     `-sshd---bash
+"
+    );
+  }
+
+  #[test]
+  fn truncate_and_highlight() {
+    let opts = PstreeOptions {
+      width: 12,
+      highlight: Some(21),
+      bold: true,
+      hide_threads: true,
+      pid: Some(20),
+      ..Default::default()
+    };
+    assert_eq!(format(&opts), "\x1b[1msshd\x1b[0m---\x1b[1mbash\x1b[0m\n");
+    let opts = PstreeOptions {
+      width: 10,
+      ..Default::default()
+    };
+    assert_eq!(
+      format(&opts),
+      "init-+-3*+
+     |-rs+
+     `-ss+
+"
+    );
+  }
+
+  #[test]
+  fn namespaces() {
+    let mut nodes = sample();
+    for n in nodes.iter_mut() {
+      n.ns = if n.pid >= 20 && n.pid < 30 { 200 } else { 100 };
+    }
+    let opts = PstreeOptions {
+      ns: Some(4),
+      hide_threads: true,
+      ..Default::default()
+    };
+    let tree = super::ProcTree::build(nodes, &opts);
+    assert_eq!(
+      super::format_tree(&tree, &opts).unwrap(),
+      "[100]
+init-+-3*[agetty]
+     `-rsyslogd
+[200]
+sshd---bash
+"
+    );
+  }
+
+  #[test]
+  fn parse_pstree_options() {
+    let args = |v: &[&str]| -> Vec<String> { v.iter().map(|s| String::from(*s)).collect() };
+    let opts = super::parse_args(&args(&["pstree", "-apnU", "-H", "5", "42"])).unwrap();
+    assert!(opts.show_args && opts.show_pids && opts.numeric_sort);
+    assert_eq!(opts.symbols, &super::SYM_UTF);
+    assert_eq!((opts.highlight, opts.pid), (Some(5), Some(42)));
+    let opts = super::parse_args(&args(&["pstree", "-Nnet", "--vt100", "root"])).unwrap();
+    assert_eq!(opts.ns, Some(3));
+    assert_eq!(opts.symbols, &super::SYM_VT100);
+    assert_eq!(opts.user, Some(0));
+
+    assert!(super::parse_args(&args(&["pstree", "-N", "pod"])).is_err());
+    assert!(super::parse_args(&args(&["pstree", "-s"])).is_err());
+    assert!(super::parse_args(&args(&["pstree", "1", "2"])).is_err());
+    assert!(super::parse_args(&args(&["pstree", "--nosuch"])).is_err());
+  }
+}
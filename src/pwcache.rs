@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::sync::Mutex;
+
+// user names already looked up, like procps pwcache.c
+static USER_CACHE: Mutex<Option<HashMap<u32, String>>> = Mutex::new(None);
+
+fn getpwuid_name(uid: u32) -> Option<String> {
+  let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+  let mut buf = vec![0 as libc::c_char; 1024];
+  let mut result: *mut libc::passwd = std::ptr::null_mut();
+  loop {
+    let rc = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc == libc::ERANGE {
+      let len = buf.len() * 2;
+      buf.resize(len, 0);
+      continue;
+    }
+    if rc != 0 || result.is_null() {
+      return None;
+    }
+    let name = unsafe { CStr::from_ptr(pwd.pw_name) };
+    return Some(name.to_string_lossy().into_owned());
+  }
+}
+
+// the name of @uid, or the number if it has no name
+pub fn user_from_uid(uid: u32) -> String {
+  let mut cache = USER_CACHE.lock().unwrap();
+  let cache = cache.get_or_insert_with(HashMap::new);
+  cache
+    .entry(uid)
+    .or_insert_with(|| getpwuid_name(uid).unwrap_or_else(|| uid.to_string()))
+    .clone()
+}
+
+// uid of a user name or number, like "root" or "0"
+pub fn uid_from_user(name: &str) -> Option<u32> {
+  if let Ok(n) = name.parse::<u32>() {
+    return Some(n);
+  }
+  let cname = CString::new(name).ok()?;
+  let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+  let mut buf = vec![0 as libc::c_char; 4096];
+  let mut result: *mut libc::passwd = std::ptr::null_mut();
+  let rc = unsafe {
+    libc::getpwnam_r(
+      cname.as_ptr(),
+      &mut pwd,
+      buf.as_mut_ptr(),
+      buf.len(),
+      &mut result,
+    )
+  };
+  if rc != 0 || result.is_null() {
+    return None;
+  }
+  Some(pwd.pw_uid)
+}
+
+// gid of a group name or number, like "wheel" or "10"
+pub fn gid_from_group(name: &str) -> Option<u32> {
+  if let Ok(n) = name.parse::<u32>() {
+    return Some(n);
+  }
+  let cname = CString::new(name).ok()?;
+  let mut grp: libc::group = unsafe { std::mem::zeroed() };
+  let mut buf = vec![0 as libc::c_char; 4096];
+  let mut result: *mut libc::group = std::ptr::null_mut();
+  let rc = unsafe {
+    libc::getgrnam_r(
+      cname.as_ptr(),
+      &mut grp,
+      buf.as_mut_ptr(),
+      buf.len(),
+      &mut result,
+    )
+  };
+  if rc != 0 || result.is_null() {
+    return None;
+  }
+  Some(grp.gr_gid)
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn lookup_user() {
+    // our own user, whatever its name is on this host
+    let uid = unsafe { libc::getuid() };
+    let name = super::user_from_uid(uid);
+    assert_eq!(super::uid_from_user(&name), Some(uid));
+    // no one has this uid
+    assert_eq!(super::user_from_uid(4000000000), "4000000000");
+    assert_eq!(super::uid_from_user("42"), Some(42));
+    assert_eq!(super::uid_from_user("no-such-user"), None);
+  }
+
+  #[test]
+  fn lookup_group() {
+    let gid = unsafe { libc::getgid() };
+    let grp = unsafe { libc::getgrgid(gid) };
+    if !grp.is_null() {
+      let name = unsafe { std::ffi::CStr::from_ptr((*grp).gr_name) };
+      assert_eq!(super::gid_from_group(name.to_str().unwrap()), Some(gid));
+    }
+    assert_eq!(super::gid_from_group("42"), Some(42));
+    assert_eq!(super::gid_from_group("no-such-group"), None);
+  }
+}
@@ -1,13 +1,19 @@
+// explicit returns are the style of this module
+#![allow(clippy::needless_return)]
+
 use std::convert::TryInto;
 use std::io::Read;
-use std::os;
 use std::os::unix::fs::MetadataExt;
 use std::path;
+use std::sync::OnceLock;
 
 use crate::argparser;
-use crate::helper;
+use crate::sockets;
+use crate::wchan;
 
+#[allow(non_camel_case_types)]
 type pid_t = i32;
+#[allow(non_camel_case_types)]
 type uid_t = i32;
 
 pub const PROC_FILLMEM: u64 = 0x1;
@@ -25,10 +31,29 @@ pub const PROC_PID: u64 = 0x1000;
 pub const PROC_UID: u64 = 0x4000;
 
 pub const PROC_FILLNS: u64 = 0x8000;
+pub const PROC_FILLWCHAN: u64 = 0x20000;
 pub const PROC_FILLSYSTEMD: u64 = 0x80000;
 pub const PROC_FILL_LXC: u64 = 0x80000;
+pub const PROC_FILLEXE: u64 = 0x200000;
+pub const PROC_FILLIO: u64 = 0x400000;
+pub const PROC_FILLMAPS: u64 = 0x800000;
+pub const PROC_FILLSMAPS: u64 = 0x1000000;
+pub const PROC_FILLFDS: u64 = 0x2000000;
+pub const PROC_FILLLIMITS: u64 = 0x4000000;
+pub const PROC_FILLATTR: u64 = 0x8000000;
+
+// namespaces of /proc/<pid>/ns, in the order of PROCT::ns
+pub const NUM_NS: usize = 8;
+pub const NS_NAMES: [&str; NUM_NS] = ["cgroup", "ipc", "mnt", "net", "pid", "time", "user", "uts"];
+pub const NS_PID: usize = 4;
+
+pub fn get_ns_id(name: &str) -> Option<usize> {
+  NS_NAMES.iter().position(|ns| *ns == name)
+}
 
-#[derive(Debug, PartialEq)]
+// state letter of /proc/<pid>/stat.
+// 'W' meant paging before Linux 2.6, but it is waking since then.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub enum ProcState {
   RUNNING,
   SLEEPING,
@@ -36,21 +61,61 @@ pub enum ProcState {
   ZOMBIE,
   STOPPED,
   TSTOP,
-  PAGING,
   DEAD,
   DEAD2,
   WAKEKILL,
   WAKING,
   PARKED,
+  IDLE,
+  #[default]
   UNKNOWN,
 }
-impl Default for ProcState {
-  fn default() -> Self {
-    ProcState::UNKNOWN
+
+impl ProcState {
+  pub fn from_char(c: char) -> ProcState {
+    match c {
+      'R' => ProcState::RUNNING,
+      'S' => ProcState::SLEEPING,
+      'D' => ProcState::WAITING,
+      'Z' => ProcState::ZOMBIE,
+      'T' => ProcState::STOPPED,
+      't' => ProcState::TSTOP,
+      'X' => ProcState::DEAD,
+      'x' => ProcState::DEAD2,
+      'K' => ProcState::WAKEKILL,
+      'W' => ProcState::WAKING,
+      'P' => ProcState::PARKED,
+      'I' => ProcState::IDLE,
+      _ => ProcState::UNKNOWN,
+    }
+  }
+
+  pub fn to_char(self) -> char {
+    match self {
+      ProcState::RUNNING => 'R',
+      ProcState::SLEEPING => 'S',
+      ProcState::WAITING => 'D',
+      ProcState::ZOMBIE => 'Z',
+      ProcState::STOPPED => 'T',
+      ProcState::TSTOP => 't',
+      ProcState::DEAD => 'X',
+      ProcState::DEAD2 => 'x',
+      ProcState::WAKEKILL => 'K',
+      ProcState::WAKING => 'W',
+      ProcState::PARKED => 'P',
+      ProcState::IDLE => 'I',
+      ProcState::UNKNOWN => '?',
+    }
+  }
+}
+
+impl std::fmt::Display for ProcState {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "{}", self.to_char())
   }
 }
 
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct PROCT {
   // all the information about proc
   pub tgid: i32, // thread group ID
@@ -92,6 +157,210 @@ pub struct PROCT {
   pub rtprio: u64,
   pub sched: u64,
   pub cmd: String,
+  pub cmdline: Vec<String>,
+  pub wchan_name: String, // symbol name of wchan, empty if not waiting
+  pub vm_lock: u64,       // locked memory in kB
+  pub signal: u64,        // pending signals shared by threads
+  pub blocked: u64,       // blocked signals
+  pub sigignore: u64,     // ignored signals
+  pub sigcatch: u64,      // caught signals
+  pub sigpnd: u64,        // pending signals of this thread
+  pub ruid: u32,          // real uid
+  pub rgid: u32,          // real gid
+  pub exe: String,        // target of /proc/<pid>/exe, empty if not readable
+  pub ns: [u64; NUM_NS],  // inode numbers of the namespaces, 0 if not readable
+  pub nvcsw: u64,         // voluntary context switches
+  pub nivcsw: u64,        // involuntary context switches
+  pub rss_file: u64,      // resident file mappings in kB
+  pub cap_inh: u64,       // inheritable capabilities
+  pub cap_prm: u64,       // permitted capabilities
+  pub cap_eff: u64,       // effective capabilities
+  pub cap_bnd: u64,       // capability bounding set
+  pub cap_amb: u64,       // ambient capabilities
+  pub label: String,      // LSM label of attr/current, empty if not readable
+  pub nspid: Vec<i32>,    // NSpid, from the pid namespace of /proc to the innermost
+  pub nstgid: Vec<i32>,   // NStgid
+  pub nspgid: Vec<i32>,   // NSpgid
+  pub nssid: Vec<i32>,    // NSsid
+  // 0 disabled, 1 strict, 2 filter. None if the kernel has no seccomp
+  pub seccomp: Option<u32>,
+  // None if the kernel does not show it
+  pub no_new_privs: Option<u32>,
+  // None if /proc/<pid>/io is not readable
+  pub io: Option<ProcIo>,
+  // rates of the last interval, set by --interval
+  pub rates: Option<Rates>,
+  // mappings of smaps, or of maps if smaps is not readable
+  pub maps: Vec<MapEntry>,
+  // None if neither smaps_rollup nor smaps is readable
+  pub smaps: Option<SmapsRollup>,
+  // number of open files, None if /proc/<pid>/fd is not readable
+  pub nfd: Option<u64>,
+  // None if /proc/<pid>/limits is not readable
+  pub limits: Option<Limits>,
+}
+
+// resources of setrlimit(2), in the order of Limits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resource {
+  AS,
+  CORE,
+  CPU,
+  DATA,
+  FSIZE,
+  LOCKS,
+  MEMLOCK,
+  MSGQUEUE,
+  NICE,
+  NOFILE,
+  NPROC,
+  RSS,
+  RTPRIO,
+  RTTIME,
+  SIGPENDING,
+  STACK,
+}
+
+#[derive(Debug)]
+pub struct ResourceInfo {
+  pub res: Resource,
+  pub label: &'static str, // in /proc/<pid>/limits
+  pub name: &'static str,
+  pub desc: &'static str,
+  pub units: &'static str,
+}
+
+// in the order of Resource, like prlimit
+#[rustfmt::skip]
+pub static RESOURCES: &[ResourceInfo] = &[
+  ResourceInfo { res: Resource::AS,         label: "Max address space",     name: "AS",         desc: "address space limit",                units: "bytes" },
+  ResourceInfo { res: Resource::CORE,       label: "Max core file size",    name: "CORE",       desc: "max core file size",                 units: "bytes" },
+  ResourceInfo { res: Resource::CPU,        label: "Max cpu time",          name: "CPU",        desc: "CPU time",                           units: "seconds" },
+  ResourceInfo { res: Resource::DATA,       label: "Max data size",         name: "DATA",       desc: "max data size",                      units: "bytes" },
+  ResourceInfo { res: Resource::FSIZE,      label: "Max file size",         name: "FSIZE",      desc: "max file size",                      units: "bytes" },
+  ResourceInfo { res: Resource::LOCKS,      label: "Max file locks",        name: "LOCKS",      desc: "max number of file locks held",      units: "locks" },
+  ResourceInfo { res: Resource::MEMLOCK,    label: "Max locked memory",     name: "MEMLOCK",    desc: "max locked-in-memory address space", units: "bytes" },
+  ResourceInfo { res: Resource::MSGQUEUE,   label: "Max msgqueue size",     name: "MSGQUEUE",   desc: "max bytes in POSIX mqueues",         units: "bytes" },
+  ResourceInfo { res: Resource::NICE,       label: "Max nice priority",     name: "NICE",       desc: "max nice prio allowed to raise",     units: "" },
+  ResourceInfo { res: Resource::NOFILE,     label: "Max open files",        name: "NOFILE",     desc: "max number of open files",           units: "files" },
+  ResourceInfo { res: Resource::NPROC,      label: "Max processes",         name: "NPROC",      desc: "max number of processes",            units: "processes" },
+  ResourceInfo { res: Resource::RSS,        label: "Max resident set",      name: "RSS",        desc: "max resident set size",              units: "bytes" },
+  ResourceInfo { res: Resource::RTPRIO,     label: "Max realtime priority", name: "RTPRIO",     desc: "max real-time priority",             units: "" },
+  ResourceInfo { res: Resource::RTTIME,     label: "Max realtime timeout",  name: "RTTIME",     desc: "timeout for real-time tasks",        units: "microsecs" },
+  ResourceInfo { res: Resource::SIGPENDING, label: "Max pending signals",   name: "SIGPENDING", desc: "max number of pending signals",      units: "signals" },
+  ResourceInfo { res: Resource::STACK,      label: "Max stack size",        name: "STACK",      desc: "max stack size",                     units: "bytes" },
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum RlimValue {
+  LIMIT(u64),
+  UNLIMITED,
+  #[default]
+  UNKNOWN, // missing in /proc/<pid>/limits or not a number
+}
+
+impl std::fmt::Display for RlimValue {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      RlimValue::LIMIT(n) => write!(f, "{}", n),
+      RlimValue::UNLIMITED => write!(f, "unlimited"),
+      RlimValue::UNKNOWN => write!(f, "-"),
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Rlimit {
+  pub soft: RlimValue,
+  pub hard: RlimValue,
+}
+
+// /proc/<pid>/limits. a resource missing in the file is UNKNOWN.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Limits {
+  limits: [Rlimit; 16],
+}
+
+impl Limits {
+  pub fn get(&self, res: Resource) -> Rlimit {
+    self.limits[res as usize]
+  }
+}
+
+// memory of all the mappings in kB, from smaps_rollup
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SmapsRollup {
+  pub rss: u64,
+  pub pss: u64,
+  pub uss: u64, // private pages, which are freed when the process exits
+  pub anonymous: u64,
+  pub swap: u64,
+  pub swap_pss: u64,
+  pub locked: u64,
+}
+
+// /proc/<pid>/io, in bytes and calls
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ProcIo {
+  pub rchar: u64,
+  pub wchar: u64,
+  pub syscr: u64,
+  pub syscw: u64,
+  pub read_bytes: u64,
+  pub write_bytes: u64,
+  pub cancelled_write_bytes: u64,
+}
+
+// per-second rates between two scans of the same process
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Rates {
+  pub pcpu: f64,
+  pub min_flt: f64,
+  pub maj_flt: f64,
+  pub nvcsw: f64,
+  pub nivcsw: f64,
+  pub read_bytes: Option<f64>, // None without PROC_FILLIO or permission
+  pub write_bytes: Option<f64>,
+}
+
+// a mapping of /proc/<pid>/maps with the counters of smaps in kB
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MapEntry {
+  pub start: u64,
+  pub end: u64,
+  pub perms: String, // like "r-xp", 'p' for private and 's' for shared
+  pub offset: u64,
+  pub dev_major: u32,
+  pub dev_minor: u32,
+  pub inode: u64,
+  pub path: String, // empty for anonymous mappings
+  pub has_smaps: bool,
+  pub rss: u64,
+  pub pss: u64,
+  pub shared_clean: u64,
+  pub shared_dirty: u64,
+  pub private_clean: u64,
+  pub private_dirty: u64,
+  pub anonymous: u64,
+  pub anon_huge_pages: u64, // THP
+  pub swap: u64,
+  pub swap_pss: u64,
+  pub locked: u64,
+  // every "Key: value" of smaps in order, for pmap -X. values are in kB
+  // except THPeligible and ProtectionKey.
+  pub fields: Vec<(String, u64)>,
+  pub vm_flags: String,
+}
+
+impl MapEntry {
+  // size in kB
+  pub fn size(&self) -> u64 {
+    (self.end - self.start) / 1024
+  }
+
+  pub fn is_shared(&self) -> bool {
+    self.perms.as_bytes().get(3) == Some(&b's')
+  }
 }
 
 #[derive(Default)]
@@ -101,8 +370,8 @@ pub struct PROCTAB {
   pub taskdir_user: i64,
   pub finder: Option<fn(&mut PROCTAB) -> Option<PROCT>>,
   pub reader: Option<fn(&PROCTAB, &mut PROCT) -> Option<()>>,
-  pub taskfinder: Option<fn(&PROCTAB, &PROCT, &PROCT, &String) -> i32>,
-  pub taskreader: Option<fn(&PROCTAB, &PROCT, &PROCT, &String) -> Option<PROCT>>,
+  pub taskfinder: Option<fn(&mut PROCTAB, &PROCT) -> Option<PROCT>>,
+  pub taskreader: Option<fn(&PROCTAB, &mut PROCT) -> Option<()>>,
   pub pids: Vec<i32>,
   pub uids: Vec<i32>,
   pub nuid: i32,
@@ -111,12 +380,13 @@ pub struct PROCTAB {
   pub u: u32,
   pub path: std::path::PathBuf,
   pub pathlen: u32,
+  pub taskpath: std::path::PathBuf,
 }
 
 pub fn openproc(
   flags: u64,
   pidlist: Option<Vec<pid_t>>,
-  uidlist: Option<Vec<uid_t>>,
+  _uidlist: Option<Vec<uid_t>>,
 ) -> Result<PROCTAB, String> {
   let mut pt = PROCTAB {
     flags: PROC_FILLSTAT,
@@ -130,7 +400,8 @@ pub fn openproc(
   pt.reader = Some(simple_readproc);
 
   if flags & PROC_PID != 0 {
-    unimplemented!();
+    pt.pids = pidlist.unwrap_or_default();
+    pt.finder = Some(listed_nextpid);
   } else {
     pt.procfs = match std::fs::read_dir(path::Path::new("/proc")) {
       Ok(d) => Some(d),
@@ -155,7 +426,7 @@ pub fn readproc(pt: &mut PROCTAB) -> Option<PROCT> {
         return None;
       }
     };
-    match pt.reader.unwrap()(&pt, &mut p) {
+    match pt.reader.unwrap()(pt, &mut p) {
       Some(()) => {
         log::trace!("success read proc: {:?}", p);
         return Some(p);
@@ -172,27 +443,79 @@ pub fn want_this_proc(p: &PROCT, parser: &argparser::PsParser) -> bool {
   log::trace!("want_this_proc(): {:?}", p);
 
   let mut proc_is_wanted = false;
-  if !parser.all_process {
+  if parser.all_process {
+    proc_is_wanted = true;
+  } else if parser.simple_select != 0 || parser.selection_list.is_empty() {
     // use table for -a a d g x
-    if parser.simple_select || parser.selection_list.len() == 0 {
-      if table_accept() {
-        unimplemented!();
-      }
-    } else {
-      // search lists
-      if proc_was_listed(&p, &parser) {
-        proc_is_wanted = true;
-      }
+    if table_accept(p, parser) {
+      proc_is_wanted = true;
+    }
+  } else {
+    // search lists
+    if proc_was_listed(p, parser) {
+      proc_is_wanted = true;
     }
   }
   // finish
   return proc_is_wanted;
 }
 
+// information of ps itself, used by the default selection and BSD `t`
+pub fn our_self() -> &'static PROCT {
+  static OUR_SELF: OnceLock<PROCT> = OnceLock::new();
+  OUR_SELF.get_or_init(|| {
+    let mut p = PROCT {
+      euid: unsafe { libc::geteuid() },
+      ..Default::default()
+    };
+    match std::fs::read_to_string("/proc/self/stat") {
+      Ok(stat) => {
+        if stat2proc(&stat, &mut p).is_err() {
+          log::warn!("failed to parse /proc/self/stat");
+        }
+      }
+      Err(_) => log::warn!("failed to read /proc/self/stat"),
+    }
+    p
+  })
+}
+
+// read the next thread of @p. readproc() must be called before.
+pub fn readtask(pt: &mut PROCTAB, p: &PROCT) -> Option<PROCT> {
+  log::trace!("readtask()");
+
+  loop {
+    let mut t = match pt.taskfinder.unwrap()(pt, p) {
+      Some(_t) => _t,
+      None => {
+        log::trace!("failed to find next tid");
+        return None;
+      }
+    };
+    match pt.taskreader.unwrap()(pt, &mut t) {
+      Some(()) => return Some(t),
+      None => {
+        log::trace!("failed pt.taskreader()");
+        continue;
+      }
+    }
+  }
+}
+
 // return None if the proc file does no more exist.
 fn simple_readproc(pt: &PROCTAB, p: &mut PROCT) -> Option<()> {
   log::trace!("pt.path: {:?}", pt.path);
-  let sb = match std::fs::metadata(&pt.path) {
+  read_pid_dir(pt.flags, &pt.path, p)
+}
+
+// same as simple_readproc(), but for /proc/<pid>/task/<tid>
+fn simple_readtask(pt: &PROCTAB, t: &mut PROCT) -> Option<()> {
+  log::trace!("pt.taskpath: {:?}", pt.taskpath);
+  read_pid_dir(pt.flags, &pt.taskpath, t)
+}
+
+fn read_pid_dir(flags: u64, path: &path::Path, p: &mut PROCT) -> Option<()> {
+  let sb = match std::fs::metadata(path) {
     Ok(meta) => meta,
     Err(_) => return None,
   };
@@ -200,73 +523,338 @@ fn simple_readproc(pt: &PROCTAB, p: &mut PROCT) -> Option<()> {
   p.euid = sb.uid();
   p.egid = sb.gid();
 
-  if pt.flags & PROC_FILLSTAT != 0 {
-    let statpath = path::PathBuf::from(format!("{}/stat", pt.path.to_str().unwrap()));
-    let mut statfile = std::fs::File::open(statpath.to_str().unwrap()).unwrap();
-    let mut stat = String::new();
-    match statfile.read_to_string(&mut stat) {
+  if flags & PROC_FILLSTAT != 0 {
+    let statpath = path::PathBuf::from(format!("{}/stat", path.to_str().unwrap()));
+    let mut statfile = match std::fs::File::open(statpath.to_str().unwrap()) {
+      Ok(f) => f,
+      Err(_) => return None,
+    };
+    // comm can be any bytes, so it is not always UTF-8
+    let mut buf = vec![];
+    match statfile.read_to_end(&mut buf) {
       Ok(n) => {
         log::trace!("read stat: {} bytes", n);
       }
-      Err(_) => log::error!("failed to read stat"),
+      Err(_) => {
+        log::debug!("failed to read stat");
+        return None;
+      }
     };
+    let stat = String::from_utf8_lossy(&buf);
     match stat2proc(&stat, p) {
       Ok(()) => log::trace!("success stat2proc()"),
       Err(_msg) => return None,
     }
   };
 
-  if pt.flags & PROC_FILLMEM != 0 {
-    unimplemented!();
+  if flags & PROC_FILLSTATUS != 0 {
+    let status = match std::fs::read_to_string(format!("{}/status", path.to_str().unwrap())) {
+      Ok(s) => s,
+      Err(_) => return None,
+    };
+    // the fields of a bad line keep what stat says
+    match status2proc(&status, p) {
+      Ok(()) => log::trace!("success status2proc()"),
+      Err(msg) => log::debug!("{}: {}", path.display(), msg),
+    }
   };
 
-  if pt.flags & PROC_FILLMEM != 0 {
+  if flags & PROC_FILLMEM != 0 {
     unimplemented!();
   };
 
-  if pt.flags & PROC_FILLUSER != 0 {
+  if flags & PROC_FILLUSER != 0 {
     unimplemented!();
   };
 
-  if pt.flags & PROC_FILLGRP != 0 {
+  if flags & PROC_FILLGRP != 0 {
     unimplemented!();
   };
 
-  if pt.flags & PROC_FILLENV != 0 {
+  if flags & PROC_FILLENV != 0 {
     unimplemented!();
   };
 
-  if (pt.flags & PROC_FILLARG != 0) && (pt.flags & PROC_FILLCOM != 0) {
-    unimplemented!();
+  if flags & PROC_FILLARG != 0 {
+    let cmdlinepath = format!("{}/cmdline", path.to_str().unwrap());
+    p.cmdline = match std::fs::read(cmdlinepath) {
+      Ok(buf) => file2strvec(&buf),
+      Err(_) => vec![],
+    };
   };
 
-  if pt.flags & PROC_FILLCGROUP != 0 {
-    unimplemented!();
+  if flags & PROC_FILLEXE != 0 {
+    // kernel threads and processes of other users have no readable link
+    p.exe = match std::fs::read_link(path.join("exe")) {
+      Ok(target) => target.to_string_lossy().into_owned(),
+      Err(_) => String::new(),
+    };
+  };
+
+  if flags & PROC_FILLIO != 0 {
+    // only readable by the owner and root
+    p.io = match std::fs::read_to_string(path.join("io")) {
+      Ok(s) => io2proc(&s),
+      Err(_) => None,
+    };
+  };
+
+  if flags & PROC_FILLMAPS != 0 {
+    // smaps needs the same permission as ptrace, maps is more open
+    p.maps = match std::fs::read_to_string(path.join("smaps")) {
+      Ok(s) => maps2proc(&s),
+      Err(_) => match std::fs::read_to_string(path.join("maps")) {
+        Ok(s) => maps2proc(&s),
+        Err(_) => vec![],
+      },
+    };
+  };
+
+  if flags & PROC_FILLSMAPS != 0 {
+    // smaps_rollup is since Linux 4.14, and much faster than smaps
+    p.smaps = match std::fs::read_to_string(path.join("smaps_rollup")) {
+      Ok(s) => Some(smaps_rollup(&maps2proc(&s))),
+      Err(_) => match std::fs::read_to_string(path.join("smaps")) {
+        Ok(s) => Some(smaps_rollup(&maps2proc(&s))),
+        Err(_) => None,
+      },
+    };
+  };
+
+  if flags & PROC_FILLFDS != 0 {
+    p.nfd = match std::fs::read_dir(path.join("fd")) {
+      Ok(dir) => Some(dir.count() as u64),
+      Err(_) => None,
+    };
+  };
+
+  if flags & PROC_FILLLIMITS != 0 {
+    p.limits = match std::fs::read_to_string(path.join("limits")) {
+      Ok(s) => Some(limits2proc(&s)),
+      Err(_) => None,
+    };
+  };
+
+  if flags & PROC_FILLATTR != 0 {
+    // "unconfined" or "system_u:system_r:init_t:s0", ending with a newline or NUL
+    p.label = match std::fs::read_to_string(path.join("attr/current")) {
+      Ok(s) => String::from(s.trim_end_matches(['\n', '\0'])),
+      Err(_) => String::new(),
+    };
+  };
+
+  if flags & PROC_FILLWCHAN != 0 {
+    p.wchan_name = wchan::read_wchan(path.to_str().unwrap(), p.wchan);
   };
 
-  if pt.flags & PROC_FILLCOM != 0 {
+  if flags & PROC_FILLCGROUP != 0 {
     unimplemented!();
   };
 
-  if pt.flags & PROC_FILLNS != 0 {
+  if flags & PROC_FILLCOM != 0 {
     unimplemented!();
   };
 
-  if pt.flags & PROC_FILLSYSTEMD != 0 {
+  if flags & PROC_FILLNS != 0 {
+    ns2proc(path, p);
+  };
+
+  if flags & PROC_FILLSYSTEMD != 0 {
     unimplemented!();
   };
 
-  if pt.flags & PROC_FILL_LXC != 0 {
+  if flags & PROC_FILL_LXC != 0 {
     unimplemented!();
   };
 
   return Some(());
 }
 
-// XXX
-fn simple_nexttid(pt: &PROCTAB, p: &PROCT, t: &PROCT, path: &String) -> i32 {
-  unimplemented!();
-  return 0;
+// parse /proc/<pid>/maps or smaps. a line of maps is like
+// "7f2c4a000000-7f2c4a021000 r-xp 00000000 08:01 1234 /usr/lib/libc.so.6",
+// and smaps adds lines of "Key: value" after each of them.
+pub fn maps2proc(s: &str) -> Vec<MapEntry> {
+  let mut maps: Vec<MapEntry> = vec![];
+  for line in s.lines() {
+    let mut words = line.split_whitespace();
+    let first = match words.next() {
+      Some(w) => w,
+      None => continue,
+    };
+    if let Some(key) = first.strip_suffix(':') {
+      let entry = match maps.last_mut() {
+        Some(entry) => entry,
+        None => continue,
+      };
+      if key == "VmFlags" {
+        entry.vm_flags = words.collect::<Vec<&str>>().join(" ");
+        continue;
+      }
+      let val = match words.next().and_then(|w| w.parse::<u64>().ok()) {
+        Some(val) => val,
+        None => continue,
+      };
+      match key {
+        "Rss" => entry.rss = val,
+        "Pss" => entry.pss = val,
+        "Shared_Clean" => entry.shared_clean = val,
+        "Shared_Dirty" => entry.shared_dirty = val,
+        "Private_Clean" => entry.private_clean = val,
+        "Private_Dirty" => entry.private_dirty = val,
+        "Anonymous" => entry.anonymous = val,
+        "AnonHugePages" => entry.anon_huge_pages = val,
+        "Swap" => entry.swap = val,
+        "SwapPss" => entry.swap_pss = val,
+        "Locked" => entry.locked = val,
+        _ => {}
+      }
+      entry.has_smaps = true;
+      entry.fields.push((String::from(key), val));
+      continue;
+    }
+    if let Some(entry) = parse_map_line(first, line) {
+      maps.push(entry);
+    }
+  }
+  maps
+}
+
+// /proc/<pid>/limits, like
+// "Max open files            1024                 1048576              files"
+pub fn limits2proc(s: &str) -> Limits {
+  let parse = |val: Option<&str>| match val {
+    Some("unlimited") => RlimValue::UNLIMITED,
+    Some(val) => val
+      .parse::<u64>()
+      .map_or(RlimValue::UNKNOWN, RlimValue::LIMIT),
+    None => RlimValue::UNKNOWN,
+  };
+  let mut limits = Limits::default();
+  for line in s.lines() {
+    let info = RESOURCES
+      .iter()
+      .find(|info| line.starts_with(info.label) && line[info.label.len()..].starts_with(' '));
+    if let Some(info) = info {
+      let mut vals = line[info.label.len()..].split_whitespace();
+      limits.limits[info.res as usize] = Rlimit {
+        soft: parse(vals.next()),
+        hard: parse(vals.next()),
+      };
+    }
+  }
+  limits
+}
+
+// the sum of the mappings. smaps_rollup is a single mapping like
+// "00400000-7ffd1e51f000 ---p 00000000 00:00 0 [rollup]".
+pub fn smaps_rollup(maps: &[MapEntry]) -> SmapsRollup {
+  let mut sum = SmapsRollup::default();
+  for m in maps.iter() {
+    sum.rss += m.rss;
+    sum.pss += m.pss;
+    sum.uss += m.private_clean + m.private_dirty;
+    sum.anonymous += m.anonymous;
+    sum.swap += m.swap;
+    sum.swap_pss += m.swap_pss;
+    sum.locked += m.locked;
+  }
+  sum
+}
+
+fn parse_map_line(range: &str, line: &str) -> Option<MapEntry> {
+  let (start, end) = range.split_once('-')?;
+  // the path can contain spaces, so it is the rest after 5 fields
+  let mut rest = line;
+  let mut fields = vec![];
+  for _ in 0..5 {
+    rest = rest.trim_start();
+    let ix = rest.find(char::is_whitespace).unwrap_or(rest.len());
+    fields.push(&rest[..ix]);
+    rest = &rest[ix..];
+  }
+  let (major, minor) = fields[3].split_once(':')?;
+  Some(MapEntry {
+    start: u64::from_str_radix(start, 16).ok()?,
+    end: u64::from_str_radix(end, 16).ok()?,
+    perms: String::from(fields[1]),
+    offset: u64::from_str_radix(fields[2], 16).ok()?,
+    dev_major: u32::from_str_radix(major, 16).ok()?,
+    dev_minor: u32::from_str_radix(minor, 16).ok()?,
+    inode: fields[4].parse::<u64>().ok()?,
+    path: String::from(rest.trim_start()),
+    ..Default::default()
+  })
+}
+
+// read the links like "ipc:[4026531839]" in /proc/<pid>/ns
+fn ns2proc(path: &path::Path, p: &mut PROCT) {
+  for (ix, name) in NS_NAMES.iter().enumerate() {
+    p.ns[ix] = match std::fs::read_link(path.join("ns").join(name)) {
+      Ok(target) => parse_ns_link(&target.to_string_lossy()).unwrap_or(0),
+      Err(_) => 0,
+    };
+  }
+}
+
+// parse /proc/<pid>/io, which consists of lines of "key: value"
+pub fn io2proc(s: &str) -> Option<ProcIo> {
+  let mut io = ProcIo::default();
+  for line in s.lines() {
+    let (key, val) = match line.find(':') {
+      Some(ix) => (&line[..ix], line[ix + 1..].trim().parse::<u64>().ok()?),
+      None => continue,
+    };
+    match key {
+      "rchar" => io.rchar = val,
+      "wchar" => io.wchar = val,
+      "syscr" => io.syscr = val,
+      "syscw" => io.syscw = val,
+      "read_bytes" => io.read_bytes = val,
+      "write_bytes" => io.write_bytes = val,
+      "cancelled_write_bytes" => io.cancelled_write_bytes = val,
+      _ => {}
+    }
+  }
+  Some(io)
+}
+
+fn parse_ns_link(target: &str) -> Option<u64> {
+  let start = target.find('[')?;
+  target[start + 1..].strip_suffix(']')?.parse::<u64>().ok()
+}
+
+// split NUL separated contents like /proc/<pid>/cmdline
+pub fn file2strvec(buf: &[u8]) -> Vec<String> {
+  let buf = buf.strip_suffix(&[0]).unwrap_or(buf);
+  if buf.is_empty() {
+    return vec![];
+  }
+  buf
+    .split(|c| *c == 0)
+    .map(|s| String::from_utf8_lossy(s).into_owned())
+    .collect()
+}
+
+fn simple_nexttid(pt: &mut PROCTAB, p: &PROCT) -> Option<PROCT> {
+  if pt.taskdir_user != p.tgid as i64 {
+    pt.taskdir = std::fs::read_dir(format!("/proc/{}/task", p.tgid)).ok();
+    pt.taskdir_user = p.tgid as i64;
+  }
+  loop {
+    let d = match pt.taskdir.as_mut()?.next() {
+      Some(Ok(_d)) => _d,
+      Some(Err(_)) => continue,
+      None => return None,
+    };
+    if let Ok(n) = d.file_name().to_str().unwrap_or("").parse::<i32>() {
+      pt.taskpath = d.path();
+      return Some(PROCT {
+        tgid: p.tgid,
+        tid: n,
+        ..Default::default()
+      });
+    }
+  }
 }
 
 // XXX
@@ -279,10 +867,7 @@ fn simple_nextpid(pt: &mut PROCTAB) -> Option<PROCT> {
     match d.file_name().to_str().unwrap().parse::<i32>() {
       Ok(n) => {
         log::trace!("success parse proc name: {:?}", n);
-        pt.path = path::PathBuf::from(String::from(format!(
-          "/proc/{}",
-          d.file_name().to_str().unwrap()
-        )));
+        pt.path = path::PathBuf::from(format!("/proc/{}", d.file_name().to_str().unwrap()));
         return Some(PROCT {
           tgid: n,
           tid: n,
@@ -298,12 +883,18 @@ fn simple_nextpid(pt: &mut PROCTAB) -> Option<PROCT> {
       }
     };
   }
-  unreachable!();
 }
 
-// XXX
-fn simple_readtask(pt: &PROCTAB, p: &PROCT, t: &PROCT, path: &String) -> Option<PROCT> {
-  unimplemented!();
+// the next pid of the list given to openproc() with PROC_PID
+fn listed_nextpid(pt: &mut PROCTAB) -> Option<PROCT> {
+  let pid = *pt.pids.get(pt.i as usize)?;
+  pt.i += 1;
+  pt.path = path::PathBuf::from(format!("/proc/{}", pid));
+  Some(PROCT {
+    tgid: pid,
+    tid: pid,
+    ..Default::default()
+  })
 }
 
 pub fn i2u64(n: i64) -> u64 {
@@ -321,11 +912,17 @@ pub fn i2u32(n: i32) -> u32 {
   }
 }
 
-pub fn stat2proc(s: &String, p: &mut PROCT) -> Result<(), String> {
+pub fn stat2proc(s: &str, p: &mut PROCT) -> Result<(), String> {
   log::trace!("{:?}", s);
-  let com_start = s.find("(").unwrap();
-  let com_end = *helper::strpbrk_all(&s, ")").last().unwrap();
-  let s_after_com_ix = com_end + 2;
+  // byte offsets, comm can have multi-byte chars and parentheses
+  let (com_start, com_end) = match (s.find('('), s.rfind(')')) {
+    (Some(start), Some(end)) if start < end => (start, end),
+    _ => return Err(String::from("no command name in stat")),
+  };
+  let s_after_com = match s.get(com_end + 2..) {
+    Some(rest) => rest,
+    None => return Err(String::from("Parse error while reading stat")),
+  };
 
   // read `com` first, cuz scan_fmt! does bad for example when parsing `((tmux: client))`.
   let com = String::from(&s[com_start + 1..com_end]);
@@ -334,11 +931,11 @@ pub fn stat2proc(s: &String, p: &mut PROCT) -> Result<(), String> {
   let (
     state, ppid, pgrp, sess, ttynr, tpgid, flags, minflt, 
     cminflt, majflt, cmajflt, utime, stime, cutime, cstime, prio, nice, num_threads, 
-    itrealvalue, _starttime, vsize, rss, rsslim, startcode, endcode, startstack, kstkesp, kstkeip, 
-    _signal, _blocked, _sigignore, _sigcatch, wchan, _nswap, _cnswap, exit_signal, processor, rt_prio, 
+    itrealvalue, starttime, vsize, rss, rsslim, startcode, endcode, startstack, kstkesp, kstkeip, 
+    signal, blocked, sigignore, sigcatch, wchan, _nswap, _cnswap, exit_signal, processor, rt_prio, 
     policy, _delayacct_blkio_ticks, _guest_time, _cguest_time, _start_data, _end_data, _start_brk, _arg_start, _arg_end, _env_start, 
     _env_end, _exit_code,
-  ) = if let Ok(r) = scan_fmt!(&s[s_after_com_ix..], 
+  ) = if let Ok(r) = scan_fmt!(s_after_com, 
     "{} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}\n",
     String, i32, i32, i32, i32, i32, u64, u64, 
     u64, u64, u64, u64, u64, u64, u64, i64, i64, i32, 
@@ -352,19 +949,9 @@ pub fn stat2proc(s: &String, p: &mut PROCT) -> Result<(), String> {
     return Err(String::from("Parse error while reading stat"));
   };
 
-  p.state = match state.as_str() {
-    "R" => ProcState::RUNNING,
-    "S" => ProcState::SLEEPING,
-    "D" => ProcState::WAITING,
-    "Z" => ProcState::ZOMBIE,
-    "T" => ProcState::STOPPED,
-    "t" => ProcState::TSTOP,
-    "W" => ProcState::PAGING,
-    "X" => ProcState::DEAD,
-    "x" => ProcState::DEAD2,
-    "K" => ProcState::WAKEKILL,
-    "W" => ProcState::WAKING,
-    "P" => ProcState::PARKED,
+  let mut state_chars = state.chars();
+  p.state = match (state_chars.next(), state_chars.next()) {
+    (Some(c), None) => ProcState::from_char(c),
     _ => ProcState::UNKNOWN,
   };
   p.ppid = ppid;
@@ -385,7 +972,7 @@ pub fn stat2proc(s: &String, p: &mut PROCT) -> Result<(), String> {
   p.nice = nice;
   p.nlwp = num_threads;
   p.alarm = itrealvalue;
-  p.start_time = stime;
+  p.start_time = starttime;
   p.vsize = vsize;
   p.rss = rss;
   p.rss_rlim = rsslim;
@@ -394,6 +981,11 @@ pub fn stat2proc(s: &String, p: &mut PROCT) -> Result<(), String> {
   p.start_stack = startstack;
   p.kstk_esp = kstkesp;
   p.kstk_eip = kstkeip;
+  // these are obsolete 32bit masks. status2proc() overwrites them if PROC_FILLSTATUS.
+  p.signal = signal as u64;
+  p.blocked = blocked as u64;
+  p.sigignore = sigignore as u64;
+  p.sigcatch = sigcatch as u64;
   p.wchan = wchan;
   p.exit_signal = exit_signal;
   p.processor = processor;
@@ -403,30 +995,100 @@ pub fn stat2proc(s: &String, p: &mut PROCT) -> Result<(), String> {
   Ok(())
 }
 
-pub fn table_accept() -> bool {
-  unimplemented!();
+// parse /proc/<pid>/status, which consists of lines of "Key:\tvalue".
+// a line that does not parse is skipped, and the first of them is the error.
+pub fn status2proc(s: &str, p: &mut PROCT) -> Result<(), String> {
+  let mut result = Ok(());
+  for line in s.lines() {
+    let (key, val) = match line.find(':') {
+      Some(ix) => (&line[..ix], line[ix + 1..].trim()),
+      None => continue,
+    };
+    let ok = match key {
+      "VmLck" => scan_fmt!(val, "{} kB", u64).map(|n| p.vm_lock = n).is_ok(),
+      "SigPnd" => u64::from_str_radix(val, 16).map(|n| p.sigpnd = n).is_ok(),
+      "ShdPnd" => u64::from_str_radix(val, 16).map(|n| p.signal = n).is_ok(),
+      "SigBlk" => u64::from_str_radix(val, 16).map(|n| p.blocked = n).is_ok(),
+      "SigIgn" => u64::from_str_radix(val, 16)
+        .map(|n| p.sigignore = n)
+        .is_ok(),
+      "SigCgt" => u64::from_str_radix(val, 16).map(|n| p.sigcatch = n).is_ok(),
+      // real, effective, saved and filesystem ids
+      "Uid" => scan_fmt!(val, "{} {}", u32, u32)
+        .map(|(r, _)| p.ruid = r)
+        .is_ok(),
+      "Gid" => scan_fmt!(val, "{} {}", u32, u32)
+        .map(|(r, _)| p.rgid = r)
+        .is_ok(),
+      "RssFile" => scan_fmt!(val, "{} kB", u64).map(|n| p.rss_file = n).is_ok(),
+      "voluntary_ctxt_switches" => val.parse::<u64>().map(|n| p.nvcsw = n).is_ok(),
+      "nonvoluntary_ctxt_switches" => val.parse::<u64>().map(|n| p.nivcsw = n).is_ok(),
+      "CapInh" => u64::from_str_radix(val, 16).map(|n| p.cap_inh = n).is_ok(),
+      "CapPrm" => u64::from_str_radix(val, 16).map(|n| p.cap_prm = n).is_ok(),
+      "CapEff" => u64::from_str_radix(val, 16).map(|n| p.cap_eff = n).is_ok(),
+      "CapBnd" => u64::from_str_radix(val, 16).map(|n| p.cap_bnd = n).is_ok(),
+      "CapAmb" => u64::from_str_radix(val, 16).map(|n| p.cap_amb = n).is_ok(),
+      "Seccomp" => val.parse::<u32>().map(|n| p.seccomp = Some(n)).is_ok(),
+      "NoNewPrivs" => val.parse::<u32>().map(|n| p.no_new_privs = Some(n)).is_ok(),
+      "NSpid" => parse_ns_ids(val).map(|ids| p.nspid = ids).is_some(),
+      "NStgid" => parse_ns_ids(val).map(|ids| p.nstgid = ids).is_some(),
+      "NSpgid" => parse_ns_ids(val).map(|ids| p.nspgid = ids).is_some(),
+      "NSsid" => parse_ns_ids(val).map(|ids| p.nssid = ids).is_some(),
+      _ => continue,
+    };
+    if !ok && result.is_ok() {
+      result = Err(format!("Parse error while reading status: {}", line));
+    }
+  }
+  result
+}
+
+// "1234\t56\t1" of NSpid and the like
+fn parse_ns_ids(val: &str) -> Option<Vec<i32>> {
+  val
+    .split_whitespace()
+    .map(|id| id.parse::<i32>().ok())
+    .collect()
+}
+
+// accept @p according to select_bits, which is indexed by the attributes of @p.
+pub fn table_accept(p: &PROCT, parser: &argparser::PsParser) -> bool {
+  let me = our_self();
+  let proc_index = ((p.euid == me.euid) as u32)
+    | (((p.session == p.tgid) as u32) << 1)
+    | (((p.tty == 0) as u32) << 2)
+    | (((p.tty == me.tty) as u32) << 3);
+  return parser.select_bits & (1 << proc_index) != 0;
 }
 
 pub fn proc_was_listed(p: &PROCT, parser: &argparser::PsParser) -> bool {
   let sn = &parser.selection_list;
-  if sn.len() == 0 {
+  if sn.is_empty() {
     false
+  } else if parser.match_every_list {
+    sn.iter().all(|snode| node_lists(p, snode))
   } else {
-    for snode in sn {
-      match snode {
-        argparser::SelectionNode::PID(pid_selection) => {
-          for pid in pid_selection.pid.iter() {
-            if p.tgid == *pid {
-              return true;
-            }
-          }
-        }
-        _ => {
-          unimplemented!();
-        }
-      }
+    sn.iter().any(|snode| node_lists(p, snode))
+  }
+}
+
+// whether @p is in the list of @snode
+fn node_lists(p: &PROCT, snode: &argparser::SelectionNode) -> bool {
+  match snode {
+    argparser::SelectionNode::PID(pid_selection) => pid_selection.pid.contains(&p.tgid),
+    argparser::SelectionNode::TTY(tty_selection) => tty_selection.tty.contains(&p.tty),
+    argparser::SelectionNode::PORT(port_selection) => {
+      let pids = port_selection
+        .pids
+        .get_or_init(|| sockets::port_owners(&port_selection.port));
+      pids.contains(&p.tgid)
     }
-    false
+    argparser::SelectionNode::EUID(uid_selection) => uid_selection.uid.contains(&p.euid),
+    argparser::SelectionNode::RUID(uid_selection) => uid_selection.uid.contains(&p.ruid),
+    argparser::SelectionNode::RGID(gid_selection) => gid_selection.gid.contains(&p.rgid),
+    argparser::SelectionNode::PGRP(pid_selection) => pid_selection.pid.contains(&p.pgrp),
+    argparser::SelectionNode::SESS(pid_selection) => pid_selection.pid.contains(&p.session),
+    argparser::SelectionNode::PPID(pid_selection) => pid_selection.pid.contains(&p.ppid),
   }
 }
 
@@ -434,7 +1096,7 @@ pub fn proc_was_listed(p: &PROCT, parser: &argparser::PsParser) -> bool {
 mod tests {
   #[test]
   fn simple_openproc_flag0() {
-    let res = super::openproc(0, None, None).unwrap().procfs.unwrap();
+    let _res = super::openproc(0, None, None).unwrap().procfs.unwrap();
   }
 
   #[test]
@@ -445,6 +1107,11 @@ mod tests {
   }
 
   #[test]
+  #[allow(
+    unused_variables,
+    clippy::while_let_loop,
+    clippy::bool_assert_comparison
+  )]
   fn simple_nextpid_iterate_all() {
     let mut pt = super::openproc(0, None, None).unwrap();
     let mut count = 0;
@@ -507,6 +1174,270 @@ mod tests {
   }
 
   #[test]
+  fn stat2proc_multibyte_comm() {
+    let rest = " S 1 100 100 0 -1 4194304 0 0 0 0 0 0 0 0 20 0 1 0 15 0 0 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n";
+    for comm in ["日本", "héllo", "a) (b"] {
+      let mut p = super::PROCT::default();
+      super::stat2proc(&format!("42 ({}){}", comm, rest), &mut p).unwrap();
+      assert_eq!(p.cmd, comm);
+      assert_eq!(p.session, 100);
+    }
+    // a comm that is not UTF-8, as read by read_pid_dir
+    let stat = String::from_utf8_lossy(b"42 (bad\xff\xfe) S 1 100 100 0 -1 4194304 0 0 0 0 0 0 0 0 20 0 1 0 15 0 0 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n");
+    let mut p = super::PROCT::default();
+    super::stat2proc(&stat, &mut p).unwrap();
+    assert_eq!(p.cmd, "bad\u{fffd}\u{fffd}");
+    // broken input is an error, not a panic
+    assert!(super::stat2proc("", &mut p).is_err());
+    assert!(super::stat2proc("42 (sleep", &mut p).is_err());
+    assert!(super::stat2proc("42 (sleep)", &mut p).is_err());
+    assert!(super::stat2proc("42 (sleep)日 S", &mut p).is_err());
+  }
+
+  #[test]
+  fn proc_state_round_trip() {
+    for c in "RSDZTtXxKWPI".chars() {
+      let state = super::ProcState::from_char(c);
+      assert_ne!(state, super::ProcState::UNKNOWN);
+      assert_eq!(state.to_char(), c);
+      assert_eq!(state.to_string(), c.to_string());
+    }
+    assert_eq!(super::ProcState::from_char('?'), super::ProcState::UNKNOWN);
+
+    let stat = String::from("77 (kworker) W 2 0 0 0 -1 69238880 0 0 0 0 0 0 0 0 20 0 1 0 15 0 0 18446744073709551615 0 0 0 0 0 0 0 2147483647 0 0 0 0 17 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n");
+    let mut p = super::PROCT {
+      ..Default::default()
+    };
+    super::stat2proc(&stat, &mut p).unwrap();
+    assert_eq!(p.state, super::ProcState::WAKING);
+  }
+
+  #[test]
+  fn status2proc_vmlck() {
+    let status = "Name:\tcat\nState:\tR (running)\nVmLck:\t     128 kB\nVmPin:\t       0 kB\n";
+    let mut p = super::PROCT {
+      ..Default::default()
+    };
+    super::status2proc(status, &mut p).unwrap();
+    assert_eq!(p.vm_lock, 128);
+  }
+
+  #[test]
+  fn status2proc_signals() {
+    let status = "SigQ:\t0/63459\nSigPnd:\t0000000000000100\nShdPnd:\t0000000000004000\nSigBlk:\t0000000000010000\nSigIgn:\t0000000000384004\nSigCgt:\t000000004b813efb\n";
+    let mut p = super::PROCT {
+      signal: 1,
+      ..Default::default()
+    };
+    super::status2proc(status, &mut p).unwrap();
+    assert_eq!(p.sigpnd, 0x100);
+    assert_eq!(p.signal, 0x4000);
+    assert_eq!(p.blocked, 0x10000);
+    assert_eq!(p.sigignore, 0x384004);
+    assert_eq!(p.sigcatch, 0x4b813efb);
+    assert!(super::status2proc("SigBlk:\tzzz\nSigPnd:\t0000000000000200\n", &mut p).is_err());
+    // the bad line keeps its old value, the next one is still taken
+    assert_eq!((p.blocked, p.sigpnd), (0x10000, 0x200));
+  }
+
+  #[test]
+  fn status2proc_ids() {
+    let status = "Uid:\t1000\t0\t0\t0\nGid:\t100\t100\t100\t100\n";
+    let mut p = super::PROCT::default();
+    super::status2proc(status, &mut p).unwrap();
+    assert_eq!((p.ruid, p.rgid), (1000, 100));
+    let status = "voluntary_ctxt_switches:\t120\nnonvoluntary_ctxt_switches:\t7\n";
+    super::status2proc(status, &mut p).unwrap();
+    assert_eq!((p.nvcsw, p.nivcsw), (120, 7));
+  }
+
+  #[test]
+  fn status2proc_nsids() {
+    let status =
+      "NStgid:\t41234\t7\t1\nNSpid:\t41240\t13\t7\nNSpgid:\t41234\t7\t1\nNSsid:\t41200\t0\n";
+    let mut p = super::PROCT::default();
+    super::status2proc(status, &mut p).unwrap();
+    assert_eq!(p.nstgid, vec![41234, 7, 1]);
+    assert_eq!(p.nspid, vec![41240, 13, 7]);
+    assert_eq!(p.nspgid, vec![41234, 7, 1]);
+    assert_eq!(p.nssid, vec![41200, 0]);
+    assert!(super::status2proc("NSpid:\t12\tx\n", &mut p).is_err());
+    assert_eq!(super::NS_NAMES[super::NS_PID], "pid");
+  }
+
+  #[test]
+  fn status2proc_security() {
+    let status = "CapInh:	0000000000000000
+CapPrm:	0000000000003000
+CapEff:	0000000000001000
+CapBnd:	000001ffffffffff
+CapAmb:	0000000000000400
+NoNewPrivs:	1
+Seccomp:	2
+Seccomp_filters:	1
+";
+    let mut p = super::PROCT::default();
+    assert_eq!((p.seccomp, p.no_new_privs), (None, None));
+    super::status2proc(status, &mut p).unwrap();
+    assert_eq!((p.cap_inh, p.cap_prm, p.cap_eff), (0, 0x3000, 0x1000));
+    assert_eq!((p.cap_bnd, p.cap_amb), (0x1ffffffffff, 0x400));
+    assert_eq!((p.seccomp, p.no_new_privs), (Some(2), Some(1)));
+    assert!(super::status2proc("CapEff:	zzz\n", &mut p).is_err());
+  }
+
+  #[test]
+  fn listed_pids() {
+    let me = std::process::id() as i32;
+    let mut pt = super::openproc(
+      super::PROC_FILLSTAT | super::PROC_PID,
+      Some(vec![me, 1, -5]),
+      None,
+    )
+    .unwrap();
+    assert_eq!(super::readproc(&mut pt).unwrap().tgid, me);
+    assert_eq!(super::readproc(&mut pt).unwrap().tgid, 1);
+    assert!(super::readproc(&mut pt).is_none());
+  }
+
+  #[test]
+  fn maps_and_smaps() {
+    let maps = super::maps2proc(
+      "55d0c4a00000-55d0c4a02000 r--p 00000000 08:01 1311 /usr/bin/cat
+7ffd1e4fe000-7ffd1e51f000 rw-p 00000000 00:00 0                          [stack]
+7f0000000000-7f0000001000 rw-s 00001000 00:05 42   /dev/shm/a b (deleted)
+",
+    );
+    assert_eq!(maps.len(), 3);
+    assert_eq!(
+      (maps[0].start, maps[0].end),
+      (0x55d0c4a00000, 0x55d0c4a02000)
+    );
+    assert_eq!(
+      (maps[0].dev_major, maps[0].dev_minor, maps[0].inode),
+      (8, 1, 1311)
+    );
+    assert_eq!(maps[0].size(), 8);
+    assert_eq!(maps[1].path, "[stack]");
+    assert_eq!(maps[2].path, "/dev/shm/a b (deleted)");
+    assert_eq!(maps[2].offset, 0x1000);
+    assert!(maps[2].is_shared() && !maps[0].is_shared() && !maps[0].has_smaps);
+
+    let smaps = super::maps2proc(
+      "55d0c4a00000-55d0c4a02000 r--p 00000000 08:01 1311 /usr/bin/cat
+Size:                  8 kB
+Rss:                   8 kB
+Pss:                   4 kB
+Private_Dirty:         2 kB
+Swap:                  0 kB
+THPeligible:    0
+VmFlags: rd mr mw me dw sd
+",
+    );
+    assert_eq!(smaps.len(), 1);
+    assert!(smaps[0].has_smaps);
+    assert_eq!(
+      (smaps[0].rss, smaps[0].pss, smaps[0].private_dirty),
+      (8, 4, 2)
+    );
+    assert_eq!(smaps[0].fields.len(), 6);
+    assert_eq!(smaps[0].fields[5], (String::from("THPeligible"), 0));
+    assert_eq!(smaps[0].vm_flags, "rd mr mw me dw sd");
+  }
+
+  #[test]
+  fn limits() {
+    use super::{Resource, RlimValue};
+    let limits = "Limit                     Soft Limit           Hard Limit           Units
+Max cpu time              unlimited            unlimited            seconds
+Max file size             unlimited            unlimited            bytes
+Max stack size            8388608              unlimited            bytes
+Max core file size        0                    unlimited            bytes
+Max processes             63448                63448                processes
+Max open files            1024                 1048576              files
+Max locked memory         8388608              8388608              bytes
+Max file locks            unlimited            unlimited            locks
+Max nice priority         0                    0
+Max realtime timeout      unlimited            unlimited            us
+Max msgqueue size         819200               garbage              bytes
+";
+    let limits = super::limits2proc(limits);
+    let nofile = limits.get(Resource::NOFILE);
+    assert_eq!(nofile.soft, RlimValue::LIMIT(1024));
+    assert_eq!(nofile.hard, RlimValue::LIMIT(1048576));
+    let stack = limits.get(Resource::STACK);
+    assert_eq!(stack.soft, RlimValue::LIMIT(8388608));
+    assert_eq!(stack.hard, RlimValue::UNLIMITED);
+    assert_eq!(limits.get(Resource::CORE).soft, RlimValue::LIMIT(0));
+    assert_eq!(limits.get(Resource::NPROC).hard, RlimValue::LIMIT(63448));
+    assert_eq!(limits.get(Resource::NICE).hard, RlimValue::LIMIT(0));
+    assert_eq!(limits.get(Resource::LOCKS).soft, RlimValue::UNLIMITED);
+    let msgqueue = limits.get(Resource::MSGQUEUE);
+    assert_eq!(msgqueue.soft, RlimValue::LIMIT(819200));
+    assert_eq!(msgqueue.hard, RlimValue::UNKNOWN);
+    // not in the file
+    assert_eq!(limits.get(Resource::RTPRIO).soft, RlimValue::UNKNOWN);
+    assert_eq!(RlimValue::UNLIMITED.to_string(), "unlimited");
+    assert_eq!(RlimValue::UNKNOWN.to_string(), "-");
+    for (ix, info) in super::RESOURCES.iter().enumerate() {
+      assert_eq!(info.res as usize, ix);
+    }
+  }
+
+  #[test]
+  fn rollup() {
+    let maps = super::maps2proc(
+      "00400000-7ffd1e51f000 ---p 00000000 00:00 0                          [rollup]
+Rss:                3000 kB
+Pss:                1200 kB
+Pss_Anon:            800 kB
+Shared_Clean:       1500 kB
+Shared_Dirty:        100 kB
+Private_Clean:       300 kB
+Private_Dirty:      1100 kB
+Anonymous:           900 kB
+Swap:                 40 kB
+SwapPss:              20 kB
+Locked:                0 kB
+",
+    );
+    let sum = super::smaps_rollup(&maps);
+    assert_eq!((sum.rss, sum.pss, sum.uss), (3000, 1200, 1400));
+    assert_eq!((sum.anonymous, sum.swap, sum.swap_pss), (900, 40, 20));
+    // summed over the mappings of smaps
+    let sum = super::smaps_rollup(&[maps[0].clone(), maps[0].clone()]);
+    assert_eq!((sum.pss, sum.uss), (2400, 2800));
+
+    let mut p = super::PROCT::default();
+    super::status2proc(
+      "RssAnon:	     900 kB
+RssFile:	    2100 kB
+",
+      &mut p,
+    )
+    .unwrap();
+    assert_eq!(p.rss_file, 2100);
+  }
+
+  #[test]
+  fn io_counters() {
+    let io = super::io2proc(
+      "rchar: 4096\nwchar: 100\nsyscr: 3\nsyscw: 2\nread_bytes: 8192\nwrite_bytes: 0\ncancelled_write_bytes: 0\n",
+    )
+    .unwrap();
+    assert_eq!((io.rchar, io.syscw, io.read_bytes), (4096, 2, 8192));
+    assert!(super::io2proc("rchar: x\n").is_none());
+  }
+
+  #[test]
+  fn ns_links() {
+    assert_eq!(super::parse_ns_link("ipc:[4026531839]"), Some(4026531839));
+    assert_eq!(super::parse_ns_link("ipc:4026531839"), None);
+    assert_eq!(super::get_ns_id("pid"), Some(4));
+    assert_eq!(super::get_ns_id("pod"), None);
+  }
+
+  #[test]
+  #[allow(clippy::redundant_field_names, clippy::bool_assert_comparison)]
   fn want_this_proc_single_pid() {
     use crate::argparser;
     let p1 = super::PROCT {
@@ -0,0 +1,76 @@
+// signal names, indexed by the signal number - 1
+const SIGLIST: [&str; 31] = [
+  "HUP", "INT", "QUIT", "ILL", "TRAP", "ABRT", "BUS", "FPE", "KILL", "USR1", "SEGV", "USR2",
+  "PIPE", "ALRM", "TERM", "STKFLT", "CHLD", "CONT", "STOP", "TSTP", "TTIN", "TTOU", "URG", "XCPU",
+  "XFSZ", "VTALRM", "PROF", "WINCH", "IO", "PWR", "SYS",
+];
+
+const SIGRTMIN: u32 = 34;
+const SIGRTMAX: u32 = 64;
+
+// name of signal @signo without "SIG", like "TERM" or "RTMIN+2"
+pub fn signal_number_to_name(signo: u32) -> String {
+  match signo {
+    1..=31 => String::from(SIGLIST[signo as usize - 1]),
+    SIGRTMIN => String::from("RTMIN"),
+    SIGRTMAX => String::from("RTMAX"),
+    n if n > SIGRTMIN && n <= (SIGRTMIN + SIGRTMAX) / 2 => format!("RTMIN+{}", n - SIGRTMIN),
+    n if n > SIGRTMIN && n < SIGRTMAX => format!("RTMAX-{}", SIGRTMAX - n),
+    n => n.to_string(),
+  }
+}
+
+// number of a signal like "TERM", "SIGTERM", "term", "15" or "RTMIN+2"
+pub fn signal_name_to_number(name: &str) -> Option<u32> {
+  if let Ok(n) = name.parse::<u32>() {
+    return if n <= SIGRTMAX { Some(n) } else { None };
+  }
+  let name = name.to_uppercase();
+  let name = name.strip_prefix("SIG").unwrap_or(&name);
+  (1..=SIGRTMAX).find(|signo| signal_number_to_name(*signo) == name)
+}
+
+// decode a signal mask of /proc/<pid>/status into names like "SIGHUP,SIGTERM".
+// "-" for an empty mask.
+pub fn mask_to_names(mask: u64) -> String {
+  let names: Vec<String> = (1..=SIGRTMAX)
+    .filter(|signo| mask & (1 << (signo - 1)) != 0)
+    .map(|signo| format!("SIG{}", signal_number_to_name(signo)))
+    .collect();
+  if names.is_empty() {
+    String::from("-")
+  } else {
+    names.join(",")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn signal_names() {
+    assert_eq!(super::signal_number_to_name(1), "HUP");
+    assert_eq!(super::signal_number_to_name(15), "TERM");
+    assert_eq!(super::signal_number_to_name(34), "RTMIN");
+    assert_eq!(super::signal_number_to_name(36), "RTMIN+2");
+    assert_eq!(super::signal_number_to_name(62), "RTMAX-2");
+    assert_eq!(super::signal_number_to_name(64), "RTMAX");
+  }
+
+  #[test]
+  fn signal_numbers() {
+    assert_eq!(super::signal_name_to_number("TERM"), Some(15));
+    assert_eq!(super::signal_name_to_number("sigkill"), Some(9));
+    assert_eq!(super::signal_name_to_number("0"), Some(0));
+    assert_eq!(super::signal_name_to_number("RTMIN+2"), Some(36));
+    assert_eq!(super::signal_name_to_number("65"), None);
+    assert_eq!(super::signal_name_to_number("NOSUCH"), None);
+  }
+
+  #[test]
+  fn decode_mask() {
+    assert_eq!(super::mask_to_names(0), "-");
+    // SIGHUP and SIGTERM
+    assert_eq!(super::mask_to_names(0x4001), "SIGHUP,SIGTERM");
+    assert_eq!(super::mask_to_names(1 << 63), "SIGRTMAX");
+  }
+}
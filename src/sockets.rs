@@ -0,0 +1,376 @@
+// sockets of processes, from /proc/<pid>/net/{tcp,tcp6,udp,udp6,unix}.
+// a socket belongs to the processes with a "socket:[inode]" link in /proc/<pid>/fd.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::files;
+use crate::readproc::PROCT;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Socket {
+  pub netid: &'static str, // tcp, udp, u_str, ... like ss
+  pub state: &'static str,
+  pub local: String,
+  pub peer: String,
+  pub local_port: Option<u16>, // None for unix sockets
+  pub inode: u64,
+}
+
+// tcp states by their number in include/net/tcp_states.h, with the names of ss
+const TCP_STATES: &[&str] = &[
+  "UNKNOWN",
+  "ESTAB",
+  "SYN-SENT",
+  "SYN-RECV",
+  "FIN-WAIT-1",
+  "FIN-WAIT-2",
+  "TIME-WAIT",
+  "UNCONN",
+  "CLOSE-WAIT",
+  "LAST-ACK",
+  "LISTEN",
+  "CLOSING",
+];
+
+// __SO_ACCEPTCON in the flags of /proc/net/unix
+const UNIX_ACCEPTCON: u32 = 0x10000;
+// SS_CONNECTED
+const UNIX_CONNECTED: u32 = 3;
+
+// "0100007F:0CEA" or the 32 digits of ipv6. the address is printed
+// as native 32-bit words of the network order bytes.
+fn parse_inet_addr(s: &str) -> Option<(IpAddr, u16)> {
+  let (addr, port) = s.split_once(':')?;
+  let port = u16::from_str_radix(port, 16).ok()?;
+  let mut bytes = vec![];
+  for ix in (0..addr.len()).step_by(8) {
+    let word = u32::from_str_radix(addr.get(ix..ix + 8)?, 16).ok()?;
+    bytes.extend_from_slice(&word.to_ne_bytes());
+  }
+  let ip = match bytes.len() {
+    4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+    16 => {
+      let mut octets = [0; 16];
+      octets.copy_from_slice(&bytes);
+      IpAddr::V6(Ipv6Addr::from(octets))
+    }
+    _ => return None,
+  };
+  Some((ip, port))
+}
+
+// "127.0.0.1:8080", "[::1]:8080", and "*" for port 0
+fn format_addr(ip: IpAddr, port: u16) -> String {
+  let port = match port {
+    0 => String::from("*"),
+    _ => port.to_string(),
+  };
+  match ip {
+    IpAddr::V4(ip) => format!("{}:{}", ip, port),
+    IpAddr::V6(ip) => format!("[{}]:{}", ip, port),
+  }
+}
+
+// a table of /proc/net/{tcp,tcp6,udp,udp6}:
+//   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+//    0: 0100007F:0CEA 00000000:0000 0A 00000000:00000000 00:00000000 00000000   0        0 23915 ...
+pub fn parse_inet(s: &str, netid: &'static str) -> Vec<Socket> {
+  let mut sockets = vec![];
+  for line in s.lines().skip(1) {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 10 {
+      continue;
+    }
+    let (local, peer) = match (parse_inet_addr(fields[1]), parse_inet_addr(fields[2])) {
+      (Some(local), Some(peer)) => (local, peer),
+      _ => continue,
+    };
+    let state = match u8::from_str_radix(fields[3], 16) {
+      Ok(st) => *TCP_STATES.get(st as usize).unwrap_or(&"UNKNOWN"),
+      Err(_) => continue,
+    };
+    let inode = match fields[9].parse::<u64>() {
+      Ok(inode) => inode,
+      Err(_) => continue,
+    };
+    sockets.push(Socket {
+      netid,
+      state,
+      local: format_addr(local.0, local.1),
+      peer: format_addr(peer.0, peer.1),
+      local_port: Some(local.1),
+      inode,
+    });
+  }
+  sockets
+}
+
+// /proc/net/unix:
+//   Num       RefCount Protocol Flags    Type St Inode Path
+//   0000000000000000: 00000002 00000000 00010000 0001 01 21377 /run/dbus/system_bus_socket
+pub fn parse_unix(s: &str) -> Vec<Socket> {
+  let mut sockets = vec![];
+  for line in s.lines().skip(1) {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 7 {
+      continue;
+    }
+    let flags = u32::from_str_radix(fields[3], 16).unwrap_or(0);
+    let netid = match fields[4] {
+      "0001" => "u_str",
+      "0002" => "u_dgr",
+      "0005" => "u_seq",
+      _ => "unix",
+    };
+    let state = if flags & UNIX_ACCEPTCON != 0 {
+      "LISTEN"
+    } else if u32::from_str_radix(fields[5], 16) == Ok(UNIX_CONNECTED) {
+      "ESTAB"
+    } else {
+      "UNCONN"
+    };
+    let inode = match fields[6].parse::<u64>() {
+      Ok(inode) => inode,
+      Err(_) => continue,
+    };
+    sockets.push(Socket {
+      netid,
+      state,
+      local: String::from(*fields.get(7).unwrap_or(&"*")),
+      peer: String::from("*"),
+      local_port: None,
+      inode,
+    });
+  }
+  sockets
+}
+
+// the sockets of a net directory like "/proc/1/net", by inode
+pub fn read_sockets(net_dir: &str) -> HashMap<u64, Socket> {
+  let mut sockets = HashMap::new();
+  for (file, netid) in [
+    ("tcp", "tcp"),
+    ("tcp6", "tcp6"),
+    ("udp", "udp"),
+    ("udp6", "udp6"),
+    ("unix", ""),
+  ] {
+    let s = match std::fs::read_to_string(format!("{}/{}", net_dir, file)) {
+      Ok(s) => s,
+      Err(_) => continue,
+    };
+    let table = match file {
+      "unix" => parse_unix(&s),
+      _ => parse_inet(&s, netid),
+    };
+    for socket in table {
+      sockets.insert(socket.inode, socket);
+    }
+  }
+  sockets
+}
+
+// the socket tables of each network namespace, read at most once
+#[derive(Default)]
+pub struct SocketTables {
+  tables: HashMap<String, HashMap<u64, Socket>>,
+}
+
+impl SocketTables {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  // the sockets seen by @pid
+  pub fn of_pid(&mut self, pid: i32) -> &HashMap<u64, Socket> {
+    let net_dir = format!("/proc/{}/net", pid);
+    // the namespace is not readable for processes of other users
+    let key = match std::fs::read_link(format!("/proc/{}/ns/net", pid)) {
+      Ok(ns) => ns.to_string_lossy().into_owned(),
+      Err(_) => net_dir.clone(),
+    };
+    self
+      .tables
+      .entry(key)
+      .or_insert_with(|| read_sockets(&net_dir))
+  }
+}
+
+// the processes with a tcp or udp socket on one of @ports, like fuser
+pub fn port_owners(ports: &[u16]) -> Vec<i32> {
+  let mut tables = SocketTables::new();
+  let mut pids = vec![];
+  let entries = match std::fs::read_dir("/proc") {
+    Ok(entries) => entries,
+    Err(_) => return pids,
+  };
+  for entry in entries.flatten() {
+    let pid = match entry
+      .file_name()
+      .to_str()
+      .and_then(|s| s.parse::<i32>().ok())
+    {
+      Some(pid) => pid,
+      None => continue,
+    };
+    let inodes = match files::socket_inodes(pid) {
+      Some(inodes) if !inodes.is_empty() => inodes,
+      _ => continue,
+    };
+    let table = tables.of_pid(pid);
+    let on_port = inodes.iter().any(|inode| match table.get(inode) {
+      Some(Socket {
+        local_port: Some(port),
+        ..
+      }) => ports.contains(port),
+      _ => false,
+    });
+    if on_port {
+      pids.push(pid);
+    }
+  }
+  pids
+}
+
+// the lines of the --sockets report, like `ss -p`
+pub fn format_sockets(rows: &[(&PROCT, &Socket)]) -> Vec<String> {
+  let local_width = rows
+    .iter()
+    .map(|(_, s)| s.local.len())
+    .max()
+    .unwrap_or(0)
+    .max(5);
+  let peer_width = rows
+    .iter()
+    .map(|(_, s)| s.peer.len())
+    .max()
+    .unwrap_or(0)
+    .max(4);
+  let mut lines = vec![format!(
+    "{:<5} {:<10} {:<lw$} {:<pw$} {:>7} COMMAND",
+    "NETID",
+    "STATE",
+    "LOCAL",
+    "PEER",
+    "PID",
+    lw = local_width,
+    pw = peer_width
+  )];
+  for (p, s) in rows {
+    lines.push(format!(
+      "{:<5} {:<10} {:<lw$} {:<pw$} {:>7} {}",
+      s.netid,
+      s.state,
+      s.local,
+      s.peer,
+      p.tgid,
+      p.cmd,
+      lw = local_width,
+      pw = peer_width
+    ));
+  }
+  lines
+}
+
+#[cfg(test)]
+mod tests {
+  // the kernel prints the address as a native word
+  fn hex_v4(a: [u8; 4]) -> String {
+    format!("{:08X}", u32::from_ne_bytes(a))
+  }
+
+  #[test]
+  fn inet_tables() {
+    let tcp = format!(
+      "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: {}:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 23915 1 0000000000000000 100 0 0 10 0
+   1: {}:D2F0 {}:0016 01 00000000:00000000 02:0009A3C1 00000000  1000        0 50021 2 0000000000000000 20 4 30 10 -1
+   2: garbage
+",
+      hex_v4([127, 0, 0, 1]),
+      hex_v4([192, 168, 1, 5]),
+      hex_v4([10, 0, 0, 1]),
+    );
+    let sockets = super::parse_inet(&tcp, "tcp");
+    assert_eq!(sockets.len(), 2);
+    assert_eq!(sockets[0].state, "LISTEN");
+    assert_eq!(sockets[0].local, "127.0.0.1:8080");
+    assert_eq!(sockets[0].peer, "0.0.0.0:*");
+    assert_eq!(sockets[0].local_port, Some(8080));
+    assert_eq!(sockets[0].inode, 23915);
+    assert_eq!(sockets[1].state, "ESTAB");
+    assert_eq!(sockets[1].local, "192.168.1.5:54000");
+    assert_eq!(sockets[1].peer, "10.0.0.1:22");
+
+    let loopback6 = format!("{}{}", "0".repeat(24), hex_v4([0, 0, 0, 1]));
+    let udp6 = format!(
+      "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  12: {}:0035 {}:0000 07 00000000:00000000 00:00000000 00000000     0        0 1234 2 0000000000000000 0
+",
+      loopback6,
+      "0".repeat(32),
+    );
+    let sockets = super::parse_inet(&udp6, "udp6");
+    assert_eq!(sockets[0].state, "UNCONN");
+    assert_eq!(sockets[0].local, "[::1]:53");
+    assert_eq!(sockets[0].peer, "[::]:*");
+  }
+
+  #[test]
+  fn unix_table() {
+    let unix = "Num       RefCount Protocol Flags    Type St Inode Path
+0000000000000000: 00000002 00000000 00010000 0001 01 21377 /run/dbus/system_bus_socket
+0000000000000000: 00000003 00000000 00000000 0001 03 21400
+0000000000000000: 00000002 00000000 00000000 0002 01 19001 /run/systemd/notify
+";
+    let sockets = super::parse_unix(unix);
+    assert_eq!(sockets.len(), 3);
+    assert_eq!(sockets[0].netid, "u_str");
+    assert_eq!(sockets[0].state, "LISTEN");
+    assert_eq!(sockets[0].local, "/run/dbus/system_bus_socket");
+    assert_eq!(sockets[1].state, "ESTAB");
+    assert_eq!(sockets[1].local, "*");
+    assert_eq!(sockets[2].netid, "u_dgr");
+    assert_eq!(sockets[2].state, "UNCONN");
+    assert_eq!(sockets[2].local_port, None);
+  }
+
+  #[test]
+  fn own_listening_port() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let pid = std::process::id() as i32;
+    assert!(super::port_owners(&[port]).contains(&pid));
+
+    let mut tables = super::SocketTables::new();
+    let table = tables.of_pid(pid);
+    assert!(table
+      .values()
+      .any(|s| s.local_port == Some(port) && s.state == "LISTEN"));
+  }
+
+  #[test]
+  fn format_report() {
+    let p = crate::readproc::PROCT {
+      tgid: 812,
+      cmd: String::from("nginx"),
+      ..Default::default()
+    };
+    let s = super::Socket {
+      netid: "tcp",
+      state: "LISTEN",
+      local: String::from("0.0.0.0:80"),
+      peer: String::from("0.0.0.0:*"),
+      local_port: Some(80),
+      inode: 1,
+    };
+    assert_eq!(
+      super::format_sockets(&[(&p, &s)]),
+      vec![
+        "NETID STATE      LOCAL      PEER          PID COMMAND",
+        "tcp   LISTEN     0.0.0.0:80 0.0.0.0:*     812 nginx",
+      ]
+    );
+  }
+}
@@ -0,0 +1,178 @@
+use std::cmp::Ordering;
+
+use crate::output;
+use crate::readproc::PROCT;
+
+// default format of SysV style
+pub const DFMT: &str = "pid,tname,time,cmd";
+// default format when BSD options are used
+pub const BSD_DFMT: &str = "pid,tname,stat,time,command";
+// SysV scheduler class format, -c
+pub const CFMT: &str = "pid,class,pri,tname,time,cmd";
+// BSD signal format, s
+pub const SFMT: &str = "uid,pid,pending,blocked,ignored,caught,stat,tname,time,command";
+// SysV full, jobs and long formats, -f, -j, -l and their combinations
+pub const FFMT: &str = "uid_hack,pid,ppid,c,stime,tname,time,cmd";
+pub const JFMT: &str = "pid,pgid,sid,tname,time,ucmd";
+pub const LFMT: &str = "f,s,uid,pid,ppid,c,opri,ni,addr,sz,wchan,tname,time,ucmd";
+pub const FJFMT: &str = "uid_hack,pid,ppid,pgid,sid,c,stime,tname,time,cmd";
+pub const FLFMT: &str = "f,s,uid_hack,pid,ppid,c,opri,ni,addr,sz,wchan,stime,tname,time,cmd";
+pub const JLFMT: &str = "f,s,uid,pid,pgid,sid,c,opri,ni,addr,sz,wchan,tname,time,ucmd";
+pub const FJLFMT: &str =
+  "f,s,uid_hack,pid,ppid,pgid,sid,c,opri,ni,addr,sz,wchan,stime,tname,time,cmd";
+
+// a column to be displayed
+#[derive(Debug, Clone)]
+pub struct FormatNode {
+  pub name: String, // header
+  pub spec: &'static str,
+  pub pr: fn(&PROCT) -> output::Value,
+  pub need: u64,
+  pub width: usize,
+  pub flags: u32,
+}
+
+impl FormatNode {
+  pub fn from(fs: &output::FormatStruct) -> FormatNode {
+    FormatNode {
+      name: String::from(fs.head),
+      spec: fs.spec,
+      pr: fs.pr,
+      need: fs.need,
+      width: fs.width,
+      flags: fs.flags,
+    }
+  }
+}
+
+// parse format list like "pid,tty,cmd" or "pid tty cmd".
+// "spec=NAME" renames the column. like procps, NAME is the rest of the list,
+// so "pid=X,comm=Y" is a single column named "X,comm=Y".
+pub fn parse_formatted(s: &str) -> Result<Vec<FormatNode>, String> {
+  let mut format_list = vec![];
+  let (s, header) = match s.find('=') {
+    Some(ix) => (&s[..ix], Some(&s[ix + 1..])),
+    None => (s, None),
+  };
+  for spec in s.split(&[',', ' ', '\t'][..]) {
+    if spec.is_empty() {
+      return Err(String::from("improper format list"));
+    }
+    match output::search_format_array(spec) {
+      Some(fs) => format_list.push(FormatNode::from(fs)),
+      None => {
+        return Err(format!(
+          "unknown user-defined format specifier \"{}\"",
+          spec
+        ))
+      }
+    }
+  }
+  if let Some(header) = header {
+    let node = format_list.last_mut().unwrap();
+    node.name = String::from(header);
+    // the column is widened for the header
+    node.width = std::cmp::max(node.width, header.chars().count());
+  }
+  Ok(format_list)
+}
+
+// a sort key
+#[derive(Debug, Clone)]
+pub struct SortNode {
+  pub pr: fn(&PROCT) -> output::Value,
+  pub need: u64,
+  pub reverse: bool,
+}
+
+// parse sort keys like "-pcpu,+pid" or "pid"
+pub fn parse_sort_opt(s: &str) -> Result<Vec<SortNode>, String> {
+  let mut sort_list = vec![];
+  for key in s.split(&[',', ' ', '\t'][..]) {
+    let (reverse, spec) = if let Some(spec) = key.strip_prefix('-') {
+      (true, spec)
+    } else if let Some(spec) = key.strip_prefix('+') {
+      (false, spec)
+    } else {
+      (false, key)
+    };
+    if spec.is_empty() {
+      return Err(String::from("improper sort list"));
+    }
+    match output::search_format_array(spec) {
+      Some(fs) => sort_list.push(SortNode {
+        pr: fs.pr,
+        need: fs.need,
+        reverse,
+      }),
+      None => return Err(format!("unknown sort specifier \"{}\"", spec)),
+    }
+  }
+  Ok(sort_list)
+}
+
+// compare two processes by the sort keys in order
+pub fn compare_procs(sort_list: &[SortNode], a: &PROCT, b: &PROCT) -> Ordering {
+  for node in sort_list {
+    let ord = (node.pr)(a).compare(&(node.pr)(b));
+    let ord = if node.reverse { ord.reverse() } else { ord };
+    if ord != Ordering::Equal {
+      return ord;
+    }
+  }
+  Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn parse_default_format() {
+    let list = super::parse_formatted(super::DFMT).unwrap();
+    let names: Vec<&str> = list.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["PID", "TTY", "TIME", "CMD"]);
+    assert!(super::parse_formatted("pid,,tty").is_err());
+    assert!(super::parse_formatted("pid,nosuchcolumn").is_err());
+  }
+
+  #[test]
+  fn parse_renamed_format() {
+    let list = super::parse_formatted("pid,comm=").unwrap();
+    let names: Vec<&str> = list.iter().map(|n| n.name.as_str()).collect();
+    assert_eq!(names, vec!["PID", ""]);
+    let list = super::parse_formatted("tty,pid=PROCESS-ID,comm=X").unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[1].name, "PROCESS-ID,comm=X");
+    assert_eq!(list[1].width, 17);
+    assert!(super::parse_formatted("=X").is_err());
+  }
+
+  #[test]
+  fn sort_by_keys() {
+    use crate::readproc::PROCT;
+    let sort_list = super::parse_sort_opt("-ppid,+pid").unwrap();
+    assert!(sort_list[0].reverse);
+    assert!(!sort_list[1].reverse);
+    let mut procs = [
+      PROCT {
+        tgid: 3,
+        ppid: 1,
+        ..Default::default()
+      },
+      PROCT {
+        tgid: 2,
+        ppid: 1,
+        ..Default::default()
+      },
+      PROCT {
+        tgid: 1,
+        ppid: 7,
+        ..Default::default()
+      },
+    ];
+    procs.sort_by(|a, b| super::compare_procs(&sort_list, a, b));
+    let pids: Vec<i32> = procs.iter().map(|p| p.tgid).collect();
+    assert_eq!(pids, vec![1, 2, 3]);
+    assert!(super::parse_sort_opt("pid,-").is_err());
+    assert!(super::parse_sort_opt("nosuchkey").is_err());
+  }
+}
@@ -0,0 +1,233 @@
+use std::sync::OnceLock;
+
+static HERTZ: OnceLock<u64> = OnceLock::new();
+static PAGE_SIZE: OnceLock<u64> = OnceLock::new();
+static BOOT_UPTIME: OnceLock<f64> = OnceLock::new();
+static BOOT_TIME: OnceLock<u64> = OnceLock::new();
+
+// clock ticks per second, used to convert jiffies in /proc/<pid>/stat
+pub fn hertz() -> u64 {
+  *HERTZ.get_or_init(|| {
+    let hz = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if hz > 0 {
+      hz as u64
+    } else {
+      log::warn!("failed to get clock tick rate, assume 100");
+      100
+    }
+  })
+}
+
+// bytes per page, used to convert `rss` in /proc/<pid>/stat
+pub fn page_size() -> u64 {
+  *PAGE_SIZE.get_or_init(|| {
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    if size > 0 {
+      size as u64
+    } else {
+      log::warn!("failed to get page size, assume 4096");
+      4096
+    }
+  })
+}
+
+// /proc/loadavg
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoadAvg {
+  pub av1: f64,
+  pub av5: f64,
+  pub av15: f64,
+}
+
+pub fn parse_loadavg(s: &str) -> Option<LoadAvg> {
+  let mut it = s.split_whitespace().map(|v| v.parse::<f64>());
+  Some(LoadAvg {
+    av1: it.next()?.ok()?,
+    av5: it.next()?.ok()?,
+    av15: it.next()?.ok()?,
+  })
+}
+
+pub fn loadavg() -> Option<LoadAvg> {
+  parse_loadavg(&std::fs::read_to_string("/proc/loadavg").ok()?)
+}
+
+// /proc/meminfo in kB, like kb_main_* of procps
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MemInfo {
+  pub main_total: u64,
+  pub main_free: u64,
+  pub main_available: u64,
+  pub main_buffers: u64,
+  pub main_cached: u64, // Cached + SReclaimable
+  pub swap_total: u64,
+  pub swap_free: u64,
+}
+
+impl MemInfo {
+  pub fn main_used(&self) -> u64 {
+    let used = self
+      .main_total
+      .saturating_sub(self.main_free + self.main_buffers + self.main_cached);
+    // buffers and cache can be larger than the total in some containers
+    if used == 0 {
+      self.main_total.saturating_sub(self.main_free)
+    } else {
+      used
+    }
+  }
+
+  pub fn swap_used(&self) -> u64 {
+    self.swap_total.saturating_sub(self.swap_free)
+  }
+}
+
+pub fn parse_meminfo(s: &str) -> MemInfo {
+  let mut mem = MemInfo::default();
+  let mut reclaimable = 0;
+  for line in s.lines() {
+    let mut it = line.split_whitespace();
+    let (key, val) = match (it.next(), it.next().and_then(|v| v.parse::<u64>().ok())) {
+      (Some(key), Some(val)) => (key, val),
+      _ => continue,
+    };
+    match key {
+      "MemTotal:" => mem.main_total = val,
+      "MemFree:" => mem.main_free = val,
+      "MemAvailable:" => mem.main_available = val,
+      "Buffers:" => mem.main_buffers = val,
+      "Cached:" => mem.main_cached = val,
+      "SReclaimable:" => reclaimable = val,
+      "SwapTotal:" => mem.swap_total = val,
+      "SwapFree:" => mem.swap_free = val,
+      _ => {}
+    }
+  }
+  mem.main_cached += reclaimable;
+  // kernels before 3.14 have no MemAvailable
+  if mem.main_available == 0 {
+    mem.main_available = mem.main_free;
+  }
+  mem
+}
+
+pub fn meminfo() -> Option<MemInfo> {
+  Some(parse_meminfo(
+    &std::fs::read_to_string("/proc/meminfo").ok()?,
+  ))
+}
+
+// the "cpu" line of /proc/stat, in jiffies
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CpuJiffies {
+  pub user: u64,
+  pub nice: u64,
+  pub system: u64,
+  pub idle: u64,
+  pub iowait: u64,
+  pub irq: u64,
+  pub softirq: u64,
+  pub steal: u64,
+}
+
+impl CpuJiffies {
+  pub fn total(&self) -> u64 {
+    self.user
+      + self.nice
+      + self.system
+      + self.idle
+      + self.iowait
+      + self.irq
+      + self.softirq
+      + self.steal
+  }
+}
+
+pub fn parse_stat_cpu(s: &str) -> Option<CpuJiffies> {
+  let line = s.lines().find(|line| line.starts_with("cpu "))?;
+  let vals: Vec<u64> = line
+    .split_whitespace()
+    .skip(1)
+    .filter_map(|v| v.parse::<u64>().ok())
+    .collect();
+  let val = |ix: usize| vals.get(ix).copied().unwrap_or(0);
+  if vals.len() < 4 {
+    return None;
+  }
+  Some(CpuJiffies {
+    user: val(0),
+    nice: val(1),
+    system: val(2),
+    idle: val(3),
+    iowait: val(4),
+    irq: val(5),
+    softirq: val(6),
+    steal: val(7),
+  })
+}
+
+// btime of /proc/stat, the boot time in seconds since the epoch
+pub fn parse_stat_btime(s: &str) -> Option<u64> {
+  let line = s.lines().find(|line| line.starts_with("btime "))?;
+  line["btime ".len()..].trim().parse::<u64>().ok()
+}
+
+// the boot time, read once
+pub fn boot_time() -> u64 {
+  *BOOT_TIME.get_or_init(|| {
+    std::fs::read_to_string("/proc/stat")
+      .ok()
+      .and_then(|s| parse_stat_btime(&s))
+      .unwrap_or(0)
+  })
+}
+
+pub fn cpu_jiffies() -> Option<CpuJiffies> {
+  parse_stat_cpu(&std::fs::read_to_string("/proc/stat").ok()?)
+}
+
+// seconds since boot
+pub fn uptime() -> Option<f64> {
+  let s = std::fs::read_to_string("/proc/uptime").ok()?;
+  s.split_whitespace().next()?.parse::<f64>().ok()
+}
+
+// uptime read once, like seconds_since_boot of procps,
+// so that the lifetime %CPU of every process is taken at the same moment
+pub fn seconds_since_boot() -> f64 {
+  *BOOT_UPTIME.get_or_init(|| uptime().unwrap_or(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn parse_proc_files() {
+    let load = super::parse_loadavg("0.52 0.58 0.59 1/917 12345\n").unwrap();
+    assert_eq!((load.av1, load.av5, load.av15), (0.52, 0.58, 0.59));
+    assert!(super::parse_loadavg("0.52\n").is_none());
+
+    let mem = super::parse_meminfo(
+      "MemTotal:       16000000 kB
+MemFree:         4000000 kB
+MemAvailable:   10000000 kB
+Buffers:          500000 kB
+Cached:          5000000 kB
+SwapCached:            0 kB
+SReclaimable:     500000 kB
+SwapTotal:       2000000 kB
+SwapFree:        1500000 kB
+",
+    );
+    assert_eq!(mem.main_cached, 5500000);
+    assert_eq!(mem.main_used(), 6000000);
+    assert_eq!(mem.swap_used(), 500000);
+
+    let cpu = super::parse_stat_cpu("cpu  10 20 30 40 50 60 70 80 0 0\ncpu0 1 2 3 4\n").unwrap();
+    assert_eq!((cpu.user, cpu.steal), (10, 80));
+    assert_eq!(cpu.total(), 360);
+    assert!(super::parse_stat_cpu("intr 1 2 3\n").is_none());
+    let stat = "cpu  10 20 30 40\nctxt 123\nbtime 1760832000\nprocesses 99\n";
+    assert_eq!(super::parse_stat_btime(stat), Some(1760832000));
+    assert!(super::parse_stat_btime("btime x\n").is_none());
+  }
+}
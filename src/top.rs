@@ -0,0 +1,1117 @@
+// rtop, an interactive top-like view built on repeated readproc scans.
+// only ANSI/VT100 escape sequences are used, so it works over ssh.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+
+use crate::argparser::ForestType;
+use crate::display;
+use crate::pwcache;
+use crate::readproc;
+use crate::readproc::{ProcState, PROCT};
+use crate::sig;
+use crate::sysinfo;
+use crate::sysinfo::{CpuJiffies, LoadAvg, MemInfo};
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+
+const HEADER: &str = "    PID USER      PR  NI    VIRT    RES S  %CPU  %MEM     TIME+ COMMAND";
+// lines above the task list: 5 summary lines, the message line and the header
+const SUMMARY_ROWS: usize = 7;
+
+// set by SIGINT, SIGTERM and SIGHUP to leave the loop and restore the terminal
+static STOP: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+  PCPU,
+  PMEM,
+  PID,
+  TIME,
+  USER,
+  COMMAND,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum PromptKind {
+  USER,
+  FILTER,
+  KILL_PID,
+  KILL_SIG(i32),
+  DELAY,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Prompt {
+  pub kind: PromptKind,
+  pub text: String,
+}
+
+impl Prompt {
+  fn question(&self, top: &Top) -> String {
+    match self.kind {
+      PromptKind::USER => String::from("Which user (blank for all): "),
+      PromptKind::FILTER => String::from("Filter COMMAND by regex (blank for none): "),
+      PromptKind::KILL_PID => String::from("PID to signal/kill: "),
+      PromptKind::KILL_SIG(pid) => format!("Send pid {} signal [15/sigterm]: ", pid),
+      PromptKind::DELAY => format!("Change delay from {:.1} to: ", top.delay.as_secs_f64()),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Action {
+  NONE,
+  REDRAW,  // show the same snapshot again, like after a new sort key
+  REFRESH, // scan the processes now
+  QUIT,
+}
+
+// a scan of /proc and the summary of the interval before it
+#[derive(Debug, Default)]
+pub struct Snapshot {
+  pub procs: Vec<PROCT>,
+  pub pcpu: Vec<f64>,  // %CPU of procs[ix] in the interval
+  pub cpu: CpuJiffies, // jiffies of the interval
+  pub mem: MemInfo,
+  pub load: LoadAvg,
+  pub uptime: f64,
+  pub clock: String,
+}
+
+pub struct Top {
+  pub delay: Duration,
+  pub iterations: Option<u64>, // -n
+  pub batch: bool,             // -b
+  pub sort: SortKey,
+  pub descending: bool,
+  pub user: Option<u32>,
+  pub pattern: Option<Regex>,
+  pub threads: bool,
+  pub tree: bool,
+  pub show_help: bool,
+  pub prompt: Option<Prompt>,
+  pub message: String,
+  prev: Option<HashMap<(i32, u64), u64>>, // utime + stime by tid and start time at the last scan
+  prev_cpu: Option<CpuJiffies>,
+  prev_time: Option<Instant>,
+}
+
+impl Default for Top {
+  fn default() -> Self {
+    Top {
+      delay: Duration::from_secs(3),
+      iterations: None,
+      batch: false,
+      sort: SortKey::PCPU,
+      descending: true,
+      user: None,
+      pattern: None,
+      threads: false,
+      tree: false,
+      show_help: false,
+      prompt: None,
+      message: String::new(),
+      prev: None,
+      prev_cpu: None,
+      prev_time: None,
+    }
+  }
+}
+
+// %CPU over the interval: the jiffies used since the last scan divided by
+// the jiffies elapsed. a task born in the interval used all of its jiffies in it.
+// a tid is reused by a new task, so a task is known by its tid and start time.
+pub fn interval_pcpu(prev: &HashMap<(i32, u64), u64>, p: &PROCT, elapsed_jiffies: f64) -> f64 {
+  let now = p.utime + p.stime;
+  let before = prev.get(&(p.tid, p.start_time)).copied().unwrap_or(0);
+  if elapsed_jiffies <= 0.0 {
+    return 0.0;
+  }
+  now.saturating_sub(before) as f64 * 100.0 / elapsed_jiffies
+}
+
+// %CPU over the whole life, used for the first scan like ps
+pub fn lifetime_pcpu(p: &PROCT, uptime: f64) -> f64 {
+  let hz = sysinfo::hertz() as f64;
+  let alive = uptime * hz - p.start_time as f64;
+  if alive <= 0.0 {
+    return 0.0;
+  }
+  (p.utime + p.stime) as f64 * 100.0 / alive
+}
+
+fn delta_cpu(now: &CpuJiffies, prev: &CpuJiffies) -> CpuJiffies {
+  CpuJiffies {
+    user: now.user.saturating_sub(prev.user),
+    nice: now.nice.saturating_sub(prev.nice),
+    system: now.system.saturating_sub(prev.system),
+    idle: now.idle.saturating_sub(prev.idle),
+    iowait: now.iowait.saturating_sub(prev.iowait),
+    irq: now.irq.saturating_sub(prev.irq),
+    softirq: now.softirq.saturating_sub(prev.softirq),
+    steal: now.steal.saturating_sub(prev.steal),
+  }
+}
+
+// "up 3 days,  2:01" or "up 15 min"
+pub fn format_uptime(uptime: f64) -> String {
+  let secs = uptime as u64;
+  let days = secs / 86400;
+  let hours = secs % 86400 / 3600;
+  let mins = secs % 3600 / 60;
+  let mut s = String::from("up ");
+  if days > 0 {
+    s.push_str(&format!(
+      "{} day{}, ",
+      days,
+      if days > 1 { "s" } else { "" }
+    ));
+  }
+  if hours > 0 {
+    s.push_str(&format!("{:2}:{:02}", hours, mins));
+  } else {
+    s.push_str(&format!("{} min", mins));
+  }
+  s
+}
+
+// kB in at most @width columns, scaled to m, g or t
+pub fn scale_kb(kb: u64, width: usize) -> String {
+  let s = kb.to_string();
+  if s.len() <= width {
+    return s;
+  }
+  let mut val = kb as f64;
+  for unit in ["m", "g", "t", "p"] {
+    val /= 1024.0;
+    let s = format!("{:.1}{}", val, unit);
+    if s.len() <= width {
+      return s;
+    }
+    let s = format!("{}{}", val as u64, unit);
+    if s.len() <= width {
+      return s;
+    }
+  }
+  String::from("?")
+}
+
+// cumulative cpu time as "m:ss.hh", hundredths of a second
+pub fn format_time_plus(jiffies: u64) -> String {
+  let cs = jiffies * 100 / sysinfo::hertz();
+  let s = format!("{}:{:02}.{:02}", cs / 6000, cs / 100 % 60, cs % 100);
+  if s.len() <= 9 {
+    return s;
+  }
+  // too long, drop the hundredths
+  format!("{}:{:02}", cs / 6000, cs / 100 % 60)
+}
+
+fn local_clock() -> String {
+  let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+  let now = unsafe { libc::time(std::ptr::null_mut()) };
+  if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+    return String::from("??:??:??");
+  }
+  format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+fn truncate_to(s: &str, cols: usize) -> String {
+  s.chars().take(cols).collect()
+}
+
+impl Top {
+  // scan /proc and compute the values of the interval since the last scan
+  pub fn sample(&mut self) -> Result<Snapshot, String> {
+    let mut pt = readproc::openproc(readproc::PROC_FILLSTAT, None, None)?;
+    let mut procs = vec![];
+    while let Some(p) = readproc::readproc(&mut pt) {
+      if !self.threads {
+        procs.push(p);
+        continue;
+      }
+      while let Some(t) = readproc::readtask(&mut pt, &p) {
+        procs.push(t);
+      }
+    }
+    let now = Instant::now();
+    let elapsed = self.prev_time.map(|t| now.duration_since(t).as_secs_f64());
+    self.prev_time = Some(now);
+    let uptime = sysinfo::uptime().unwrap_or(0.0);
+    let cpu = sysinfo::cpu_jiffies().unwrap_or_default();
+    let mut snap = self.make_snapshot(procs, cpu, elapsed, uptime);
+    snap.mem = sysinfo::meminfo().unwrap_or_default();
+    snap.load = sysinfo::loadavg().unwrap_or_default();
+    snap.clock = local_clock();
+    Ok(snap)
+  }
+
+  // @elapsed is None for the first scan
+  pub fn make_snapshot(
+    &mut self,
+    procs: Vec<PROCT>,
+    cpu: CpuJiffies,
+    elapsed: Option<f64>,
+    uptime: f64,
+  ) -> Snapshot {
+    let hz = sysinfo::hertz() as f64;
+    let pcpu = match (&self.prev, elapsed) {
+      (Some(prev), Some(elapsed)) => procs
+        .iter()
+        .map(|p| interval_pcpu(prev, p, elapsed * hz))
+        .collect(),
+      _ => procs.iter().map(|p| lifetime_pcpu(p, uptime)).collect(),
+    };
+    self.prev = Some(
+      procs
+        .iter()
+        .map(|p| ((p.tid, p.start_time), p.utime + p.stime))
+        .collect(),
+    );
+    let delta = match &self.prev_cpu {
+      Some(prev) => delta_cpu(&cpu, prev),
+      None => cpu,
+    };
+    self.prev_cpu = Some(cpu);
+    Snapshot {
+      procs,
+      pcpu,
+      cpu: delta,
+      uptime,
+      ..Default::default()
+    }
+  }
+
+  fn wanted(&self, p: &PROCT) -> bool {
+    if let Some(uid) = self.user {
+      if p.euid != uid {
+        return false;
+      }
+    }
+    match &self.pattern {
+      Some(re) => re.is_match(&p.cmd),
+      None => true,
+    }
+  }
+
+  // indexes of the shown tasks in order, with the forest art of each
+  pub fn visible(&self, snap: &Snapshot) -> Vec<(usize, String)> {
+    let mut order: Vec<usize> = (0..snap.procs.len())
+      .filter(|ix| self.wanted(&snap.procs[*ix]))
+      .collect();
+    let key = |ix: &usize, iy: &usize| {
+      let (p, q) = (&snap.procs[*ix], &snap.procs[*iy]);
+      match self.sort {
+        SortKey::PCPU => snap.pcpu[*ix].total_cmp(&snap.pcpu[*iy]),
+        SortKey::PMEM => p.rss.cmp(&q.rss),
+        SortKey::PID => p.tid.cmp(&q.tid),
+        SortKey::TIME => (p.utime + p.stime).cmp(&(q.utime + q.stime)),
+        SortKey::USER => pwcache::user_from_uid(p.euid).cmp(&pwcache::user_from_uid(q.euid)),
+        SortKey::COMMAND => p.cmd.cmp(&q.cmd),
+      }
+    };
+    // ties are broken by pid, always ascending
+    order.sort_by(|ix, iy| {
+      let ord = key(ix, iy);
+      let ord = if self.descending { ord.reverse() } else { ord };
+      ord.then(snap.procs[*ix].tid.cmp(&snap.procs[*iy].tid))
+    });
+    if !self.tree {
+      return order.into_iter().map(|ix| (ix, String::new())).collect();
+    }
+
+    // siblings keep the sort order
+    let sorted: Vec<PROCT> = order.iter().map(|ix| snap.procs[*ix].clone()).collect();
+    let forest = display::Forest::new(&sorted);
+    let mut ret = vec![];
+    let mut stack: Vec<(usize, Vec<bool>)> = forest
+      .roots
+      .iter()
+      .rev()
+      .map(|root| (*root, vec![]))
+      .collect();
+    while let Some((ix, last)) = stack.pop() {
+      ret.push((order[ix], display::forest_prefix(&ForestType::BSD, &last)));
+      let children = &forest.children[ix];
+      for (cix, child) in children.iter().enumerate().rev() {
+        let mut last = last.clone();
+        last.push(cix + 1 == children.len());
+        stack.push((*child, last));
+      }
+    }
+    ret
+  }
+
+  fn summary(&self, snap: &Snapshot) -> Vec<String> {
+    let mut lines = vec![];
+    lines.push(format!(
+      "rtop - {} {},  load average: {:.2}, {:.2}, {:.2}",
+      snap.clock,
+      format_uptime(snap.uptime),
+      snap.load.av1,
+      snap.load.av5,
+      snap.load.av15
+    ));
+
+    let count = |states: &[ProcState]| {
+      snap
+        .procs
+        .iter()
+        .filter(|p| states.contains(&p.state))
+        .count()
+    };
+    lines.push(format!(
+      "{}: {:3} total, {:3} running, {:3} sleeping, {:3} stopped, {:3} zombie",
+      if self.threads { "Threads" } else { "Tasks" },
+      snap.procs.len(),
+      count(&[ProcState::RUNNING]),
+      count(&[ProcState::SLEEPING, ProcState::WAITING, ProcState::IDLE]),
+      count(&[ProcState::STOPPED, ProcState::TSTOP]),
+      count(&[ProcState::ZOMBIE])
+    ));
+
+    let cpu = &snap.cpu;
+    let total = cpu.total().max(1) as f64;
+    let pct = |v: u64| v as f64 * 100.0 / total;
+    lines.push(format!(
+      "%Cpu(s): {:4.1} us, {:4.1} sy, {:4.1} ni, {:4.1} id, {:4.1} wa, {:4.1} hi, {:4.1} si, {:4.1} st",
+      pct(cpu.user),
+      pct(cpu.system),
+      pct(cpu.nice),
+      pct(cpu.idle),
+      pct(cpu.iowait),
+      pct(cpu.irq),
+      pct(cpu.softirq),
+      pct(cpu.steal)
+    ));
+
+    let mem = &snap.mem;
+    let mib = |kb: u64| kb as f64 / 1024.0;
+    lines.push(format!(
+      "MiB Mem : {:8.1} total, {:8.1} free, {:8.1} used, {:8.1} buff/cache",
+      mib(mem.main_total),
+      mib(mem.main_free),
+      mib(mem.main_used()),
+      mib(mem.main_buffers + mem.main_cached)
+    ));
+    lines.push(format!(
+      "MiB Swap: {:8.1} total, {:8.1} free, {:8.1} used. {:8.1} avail Mem",
+      mib(mem.swap_total),
+      mib(mem.swap_free),
+      mib(mem.swap_used()),
+      mib(mem.main_available)
+    ));
+    lines
+  }
+
+  fn task_line(&self, snap: &Snapshot, ix: usize, prefix: &str) -> String {
+    let p = &snap.procs[ix];
+    let res = p.rss.max(0) as u64 * sysinfo::page_size() / 1024;
+    let pmem = if snap.mem.main_total > 0 {
+      res as f64 * 100.0 / snap.mem.main_total as f64
+    } else {
+      0.0
+    };
+    let mut user = pwcache::user_from_uid(p.euid);
+    if user.chars().count() > 8 {
+      user = user.chars().take(7).collect::<String>() + "+";
+    }
+    // real-time tasks have a negative priority
+    let pr = if p.priority <= -100 {
+      String::from("rt")
+    } else {
+      p.priority.to_string()
+    };
+    format!(
+      "{:>7} {:<8} {:>3} {:>3} {:>7} {:>6} {} {:>5.1} {:>5.1} {:>9} {}{}",
+      p.tid,
+      user,
+      pr,
+      p.nice,
+      scale_kb(p.vsize / 1024, 7),
+      scale_kb(res, 6),
+      p.state,
+      snap.pcpu[ix],
+      pmem,
+      format_time_plus(p.utime + p.stime),
+      prefix,
+      p.cmd
+    )
+  }
+
+  fn help_lines(&self) -> Vec<String> {
+    let lines = [
+      String::from("Help for interactive commands - rtop"),
+      format!(
+        "Delay {:.1} secs; sort by {:?}, {}",
+        self.delay.as_secs_f64(),
+        self.sort,
+        if self.descending {
+          "high to low"
+        } else {
+          "low to high"
+        }
+      ),
+      String::new(),
+      String::from("  P,M,N,T,U,C  sort by %CPU, %MEM, PID, TIME+, USER, COMMAND"),
+      String::from("  R            reverse the sort order"),
+      String::from("  u            show only the tasks of a user"),
+      String::from("  o, /         show only the tasks whose COMMAND matches a regex"),
+      String::from("  H            toggle threads"),
+      String::from("  V            toggle the forest view"),
+      String::from("  k            send a signal to a task"),
+      String::from("  d, s         change the delay between updates"),
+      String::from("  space        update now"),
+      String::from("  h, ?         toggle this help"),
+      String::from("  q            quit"),
+    ];
+    lines.to_vec()
+  }
+
+  // the lines of a frame. @rows limits the number of lines if given.
+  pub fn render(&self, snap: &Snapshot, rows: Option<usize>, cols: usize) -> Vec<String> {
+    let mut lines = self.summary(snap);
+    lines.push(match &self.prompt {
+      Some(prompt) => format!("{}{}", prompt.question(self), prompt.text),
+      None => self.message.clone(),
+    });
+    if self.show_help {
+      lines.extend(self.help_lines());
+    } else {
+      // reverse video, filling the line
+      let head = format!("{:<width$}", HEADER, width = cols.min(512));
+      lines.push(format!("\x1b[7m{}\x1b[0m", truncate_to(&head, cols)));
+      let max = rows.map_or(usize::MAX, |rows| rows.saturating_sub(SUMMARY_ROWS));
+      for (ix, prefix) in self.visible(snap).into_iter().take(max) {
+        lines.push(self.task_line(snap, ix, &prefix));
+      }
+    }
+    let mut lines: Vec<String> = lines
+      .into_iter()
+      .map(|line| {
+        if line.starts_with('\x1b') {
+          line
+        } else {
+          truncate_to(&line, cols)
+        }
+      })
+      .collect();
+    if let Some(rows) = rows {
+      lines.truncate(rows);
+    }
+    lines
+  }
+
+  pub fn handle_key(&mut self, key: u8) -> Action {
+    if self.prompt.is_some() {
+      return self.prompt_key(key);
+    }
+    self.message.clear();
+    let sort = |top: &mut Top, key: SortKey| {
+      top.sort = key;
+      Action::REDRAW
+    };
+    match key {
+      b'q' => Action::QUIT,
+      b'P' => sort(self, SortKey::PCPU),
+      b'M' => sort(self, SortKey::PMEM),
+      b'N' => sort(self, SortKey::PID),
+      b'T' => sort(self, SortKey::TIME),
+      b'U' => sort(self, SortKey::USER),
+      b'C' => sort(self, SortKey::COMMAND),
+      b'R' => {
+        self.descending = !self.descending;
+        Action::REDRAW
+      }
+      b'H' => {
+        self.threads = !self.threads;
+        self.message = format!("Show threads {}", if self.threads { "On" } else { "Off" });
+        // %CPU of the other kind of tasks is not known yet
+        self.prev = None;
+        Action::REFRESH
+      }
+      b'V' => {
+        self.tree = !self.tree;
+        self.message = format!("Forest view {}", if self.tree { "On" } else { "Off" });
+        Action::REDRAW
+      }
+      b'h' | b'?' => {
+        self.show_help = !self.show_help;
+        Action::REDRAW
+      }
+      b'u' => self.ask(PromptKind::USER),
+      b'o' | b'/' => self.ask(PromptKind::FILTER),
+      b'k' => self.ask(PromptKind::KILL_PID),
+      b'd' | b's' => self.ask(PromptKind::DELAY),
+      b' ' | 0x0c => Action::REFRESH,
+      _ => {
+        self.show_help = false;
+        Action::REDRAW
+      }
+    }
+  }
+
+  fn ask(&mut self, kind: PromptKind) -> Action {
+    self.prompt = Some(Prompt {
+      kind,
+      text: String::new(),
+    });
+    Action::REDRAW
+  }
+
+  // editing of the prompt line
+  fn prompt_key(&mut self, key: u8) -> Action {
+    let prompt = self.prompt.as_mut().unwrap();
+    match key {
+      b'\r' | b'\n' => {
+        let prompt = self.prompt.take().unwrap();
+        self.submit(prompt.kind, prompt.text.trim());
+      }
+      // escape or ctrl-c
+      0x1b | 0x03 => self.prompt = None,
+      0x7f | 0x08 => {
+        prompt.text.pop();
+      }
+      // ctrl-u
+      0x15 => prompt.text.clear(),
+      0x20..=0x7e => prompt.text.push(key as char),
+      _ => {}
+    }
+    Action::REDRAW
+  }
+
+  fn submit(&mut self, kind: PromptKind, text: &str) {
+    match kind {
+      PromptKind::USER => {
+        if text.is_empty() {
+          self.user = None;
+        } else {
+          match pwcache::uid_from_user(text) {
+            Some(uid) => self.user = Some(uid),
+            None => self.message = format!("Invalid user: {}", text),
+          }
+        }
+      }
+      PromptKind::FILTER => {
+        if text.is_empty() {
+          self.pattern = None;
+        } else {
+          match Regex::new(text) {
+            Ok(re) => self.pattern = Some(re),
+            Err(_) => self.message = format!("Invalid regex: {}", text),
+          }
+        }
+      }
+      PromptKind::KILL_PID => {
+        if text.is_empty() {
+          return;
+        }
+        match text.parse::<i32>() {
+          Ok(pid) if pid > 0 => {
+            self.prompt = Some(Prompt {
+              kind: PromptKind::KILL_SIG(pid),
+              text: String::new(),
+            })
+          }
+          _ => self.message = format!("Invalid pid: {}", text),
+        }
+      }
+      PromptKind::KILL_SIG(pid) => {
+        let text = if text.is_empty() { "15" } else { text };
+        let signo = match sig::signal_name_to_number(text) {
+          Some(signo) if signo > 0 => signo,
+          _ => {
+            self.message = format!("Invalid signal: {}", text);
+            return;
+          }
+        };
+        if unsafe { libc::kill(pid, signo as i32) } != 0 {
+          let err = std::io::Error::last_os_error();
+          self.message = format!("Failed signal pid '{}' with '{}': {}", pid, signo, err);
+        }
+      }
+      PromptKind::DELAY => match text.parse::<f64>() {
+        Ok(secs) if secs > 0.0 && secs.is_finite() => self.delay = Duration::from_secs_f64(secs),
+        _ => {
+          if !text.is_empty() {
+            self.message = format!("Invalid delay: {}", text);
+          }
+        }
+      },
+    }
+  }
+}
+
+// the terminal in raw mode on the alternate screen, restored on drop
+struct Terminal {
+  orig: libc::termios,
+}
+
+impl Terminal {
+  fn enter() -> Result<Terminal, String> {
+    let mut orig: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut orig) } != 0 {
+      return Err(String::from("stdin is not a terminal, try -b"));
+    }
+    let mut raw = orig;
+    raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+    raw.c_cc[libc::VMIN] = 1;
+    raw.c_cc[libc::VTIME] = 0;
+    unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw) };
+    // alternate screen, hidden cursor
+    print!("\x1b[?1049h\x1b[?25l");
+    let _ = std::io::stdout().flush();
+    Ok(Terminal { orig })
+  }
+
+  fn size() -> (usize, usize) {
+    let mut ws = libc::winsize {
+      ws_row: 0,
+      ws_col: 0,
+      ws_xpixel: 0,
+      ws_ypixel: 0,
+    };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) } != -1
+      && ws.ws_row > 0
+      && ws.ws_col > 0
+    {
+      return (ws.ws_row as usize, ws.ws_col as usize);
+    }
+    (24, 80)
+  }
+
+  // wait a key for @timeout. None on timeout or a signal.
+  fn read_keys(timeout: Duration) -> Option<Vec<u8>> {
+    let mut fds = libc::pollfd {
+      fd: libc::STDIN_FILENO,
+      events: libc::POLLIN,
+      revents: 0,
+    };
+    let ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+    if unsafe { libc::poll(&mut fds, 1, ms) } <= 0 {
+      return None;
+    }
+    let mut buf = [0u8; 64];
+    let n = unsafe {
+      libc::read(
+        libc::STDIN_FILENO,
+        buf.as_mut_ptr() as *mut libc::c_void,
+        buf.len(),
+      )
+    };
+    if n <= 0 {
+      // the terminal is gone
+      STOP.store(true, Ordering::Relaxed);
+      return None;
+    }
+    Some(buf[..n as usize].to_vec())
+  }
+}
+
+impl Drop for Terminal {
+  fn drop(&mut self) {
+    print!("\x1b[?25h\x1b[?1049l");
+    let _ = std::io::stdout().flush();
+    unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.orig) };
+  }
+}
+
+extern "C" fn on_stop_signal(_signo: libc::c_int) {
+  STOP.store(true, Ordering::Relaxed);
+}
+
+// only interrupts poll(), the size is read at each frame
+extern "C" fn on_winch(_signo: libc::c_int) {}
+
+fn draw(top: &Top, snap: &Snapshot) {
+  let (rows, cols) = Terminal::size();
+  let lines = top.render(snap, Some(rows), cols);
+  let mut s = String::from("\x1b[H");
+  s.push_str(&lines.join("\x1b[K\r\n"));
+  s.push_str("\x1b[K\x1b[J");
+  if top.prompt.is_some() {
+    // the cursor at the end of the prompt line
+    let col = lines[SUMMARY_ROWS - 2].chars().count() + 1;
+    s.push_str(&format!("\x1b[{};{}H\x1b[?25h", SUMMARY_ROWS - 1, col));
+  } else {
+    s.push_str("\x1b[?25l");
+  }
+  let mut out = std::io::stdout();
+  let _ = out.write_all(s.as_bytes());
+  let _ = out.flush();
+}
+
+fn run_interactive(top: &mut Top) -> Result<(), String> {
+  let _term = Terminal::enter()?;
+  unsafe {
+    for signo in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+      libc::signal(
+        signo,
+        on_stop_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+      );
+    }
+    libc::signal(
+      libc::SIGWINCH,
+      on_winch as extern "C" fn(libc::c_int) as libc::sighandler_t,
+    );
+  }
+  let mut count = 0;
+  'scan: loop {
+    let snap = top.sample()?;
+    count += 1;
+    draw(top, &snap);
+    let deadline = Instant::now() + top.delay;
+    loop {
+      if STOP.load(Ordering::Relaxed) {
+        break 'scan;
+      }
+      let now = Instant::now();
+      if now >= deadline {
+        if top.iterations.is_some_and(|n| count >= n) {
+          break 'scan;
+        }
+        continue 'scan;
+      }
+      let keys = match Terminal::read_keys(deadline - now) {
+        Some(keys) => keys,
+        // a timeout, or a signal like SIGWINCH
+        None => {
+          draw(top, &snap);
+          continue;
+        }
+      };
+      let mut refresh = false;
+      for key in keys {
+        match top.handle_key(key) {
+          Action::QUIT => break 'scan,
+          Action::REFRESH => refresh = true,
+          Action::REDRAW | Action::NONE => {}
+        }
+      }
+      if refresh {
+        continue 'scan;
+      }
+      draw(top, &snap);
+    }
+  }
+  Ok(())
+}
+
+// -b: plain frames without the terminal control, for logs and pipes
+fn run_batch(top: &mut Top) -> Result<(), String> {
+  let mut count = 0;
+  loop {
+    let snap = top.sample()?;
+    count += 1;
+    for line in top.render(&snap, None, usize::MAX) {
+      let line = line.replace("\x1b[7m", "").replace("\x1b[0m", "");
+      println!("{}", line.trim_end());
+    }
+    if top.iterations.is_some_and(|n| count >= n) {
+      return Ok(());
+    }
+    println!();
+    std::thread::sleep(top.delay);
+  }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Top, String> {
+  let mut top = Top::default();
+  let mut ix = 1;
+  while ix < args.len() {
+    let arg = &args[ix];
+    ix += 1;
+    match arg.as_str() {
+      "--help" | "-h" => {
+        print!("{}", usage());
+        std::process::exit(EXIT_SUCCESS);
+      }
+      "--version" | "-v" | "-V" => {
+        println!("rtop from rustyps {}", env!("CARGO_PKG_VERSION"));
+        std::process::exit(EXIT_SUCCESS);
+      }
+      _ => {}
+    }
+    if !arg.starts_with('-') || arg.len() < 2 {
+      return Err(format!("unknown argument '{}'", arg));
+    }
+    let chars: Vec<char> = arg[1..].chars().collect();
+    for (cix, c) in chars.iter().enumerate() {
+      match c {
+        'b' => top.batch = true,
+        'H' => top.threads = true,
+        'V' => top.tree = true,
+        'd' | 'n' | 'u' => {
+          let rest: String = chars[cix + 1..].iter().collect();
+          let val = if !rest.is_empty() {
+            rest
+          } else if ix < args.len() {
+            ix += 1;
+            args[ix - 1].clone()
+          } else {
+            return Err(format!("option requires an argument -- '{}'", c));
+          };
+          match c {
+            'd' => match val.parse::<f64>() {
+              Ok(secs) if secs > 0.0 && secs.is_finite() => {
+                top.delay = Duration::from_secs_f64(secs)
+              }
+              _ => return Err(format!("bad delay interval '{}'", val)),
+            },
+            'n' => match val.parse::<u64>() {
+              Ok(n) if n > 0 => top.iterations = Some(n),
+              _ => return Err(format!("bad iterations argument '{}'", val)),
+            },
+            _ => match pwcache::uid_from_user(&val) {
+              Some(uid) => top.user = Some(uid),
+              None => return Err(format!("invalid user '{}'", val)),
+            },
+          }
+          break;
+        }
+        _ => return Err(format!("unknown option '{}'", c)),
+      }
+    }
+  }
+  Ok(top)
+}
+
+pub fn usage() -> String {
+  String::from(
+    "
+Usage:
+ rtop [options]
+
+Options:
+ -b          batch mode, print frames without terminal control
+ -d <secs>   delay between updates
+ -H          show threads
+ -n <count>  exit after the number of updates
+ -u <user>   show only the tasks of the user
+ -V          forest view
+
+ -h, --help     display this help and exit
+ -v, --version  output version information and exit
+
+Press h in rtop for the interactive commands.
+",
+  )
+}
+
+// main of rtop. returns the exit status.
+pub fn run(args: Vec<String>) -> i32 {
+  let mut top = match parse_args(&args) {
+    Ok(top) => top,
+    Err(msg) => {
+      eprintln!("rtop: {}", msg);
+      eprint!("{}", usage());
+      return EXIT_FAILURE;
+    }
+  };
+  let res = if top.batch {
+    run_batch(&mut top)
+  } else {
+    run_interactive(&mut top)
+  };
+  match res {
+    Ok(()) => EXIT_SUCCESS,
+    Err(msg) => {
+      eprintln!("rtop: {}", msg);
+      EXIT_FAILURE
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Action, PromptKind, SortKey, Top};
+  use crate::readproc::{ProcState, PROCT};
+  use crate::sysinfo::CpuJiffies;
+
+  fn proct(tid: i32, ppid: i32, cmd: &str, ticks: u64) -> PROCT {
+    PROCT {
+      tid,
+      tgid: tid,
+      ppid,
+      cmd: String::from(cmd),
+      utime: ticks,
+      state: ProcState::SLEEPING,
+      ..Default::default()
+    }
+  }
+
+  fn cpu(user: u64, idle: u64) -> CpuJiffies {
+    CpuJiffies {
+      user,
+      idle,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn cpu_of_the_interval() {
+    let hz = crate::sysinfo::hertz();
+    let mut top = Top::default();
+    let first = vec![proct(1, 0, "init", 10), proct(2, 1, "busy", 100)];
+    top.make_snapshot(first, cpu(100, 100), None, 10.0);
+    // busy used a whole cpu for 2 seconds, the newcomer half of a second
+    let second = vec![
+      proct(1, 0, "init", 10),
+      proct(2, 1, "busy", 100 + 2 * hz),
+      proct(3, 1, "new", hz / 2),
+    ];
+    let snap = top.make_snapshot(second, cpu(150, 250), Some(2.0), 12.0);
+    assert_eq!(snap.pcpu, vec![0.0, 100.0, 25.0]);
+    assert_eq!(snap.cpu, cpu(50, 150));
+    // tid 2 exited and a new task got it, which is counted from zero
+    let reused = PROCT {
+      start_time: 1000,
+      ..proct(2, 1, "reused", hz / 4)
+    };
+    let snap = top.make_snapshot(vec![reused], cpu(200, 350), Some(1.0), 13.0);
+    assert_eq!(snap.pcpu, vec![25.0]);
+  }
+
+  #[test]
+  fn sort_filter_and_tree() {
+    let mut top = Top::default();
+    let procs = vec![
+      proct(1, 0, "init", 0),
+      proct(5, 1, "sshd", 0),
+      proct(7, 5, "bash", 0),
+      proct(9, 1, "cron", 0),
+    ];
+    let mut snap = top.make_snapshot(procs, cpu(0, 0), None, 0.0);
+    snap.pcpu = vec![0.0, 1.0, 5.0, 2.0];
+    let pids = |top: &Top| -> Vec<i32> {
+      top
+        .visible(&snap)
+        .iter()
+        .map(|(ix, _)| snap.procs[*ix].tid)
+        .collect()
+    };
+    assert_eq!(pids(&top), vec![7, 9, 5, 1]);
+    top.handle_key(b'R');
+    assert_eq!(pids(&top), vec![1, 5, 9, 7]);
+    top.handle_key(b'N');
+    assert_eq!(pids(&top), vec![1, 5, 7, 9]);
+    top.handle_key(b'V');
+    let prefixes: Vec<String> = top.visible(&snap).into_iter().map(|(_, s)| s).collect();
+    assert_eq!(prefixes, vec!["", " \\_ ", " |   \\_ ", " \\_ "]);
+    for key in b"o^(sshd|cron)$\r" {
+      top.handle_key(*key);
+    }
+    assert_eq!(pids(&top), vec![5, 9]);
+    for key in b"/\r" {
+      top.handle_key(*key);
+    }
+    assert_eq!(pids(&top).len(), 4);
+  }
+
+  #[test]
+  fn prompts() {
+    let mut top = Top::default();
+    assert_eq!(top.handle_key(b'd'), Action::REDRAW);
+    for key in b"0.5x" {
+      top.handle_key(*key);
+    }
+    top.handle_key(0x7f);
+    assert_eq!(top.prompt.as_ref().unwrap().text, "0.5");
+    top.handle_key(b'\r');
+    assert_eq!(top.delay.as_secs_f64(), 0.5);
+    assert!(top.prompt.is_none());
+
+    // escape cancels
+    top.handle_key(b'u');
+    top.handle_key(b'x');
+    top.handle_key(0x1b);
+    assert!(top.prompt.is_none() && top.user.is_none());
+    for key in b"uno-such-user\r" {
+      top.handle_key(*key);
+    }
+    assert!(top.message.starts_with("Invalid user"));
+    for key in b"uroot\r" {
+      top.handle_key(*key);
+    }
+    assert_eq!(top.user, Some(0));
+
+    // kill asks the pid, then the signal
+    for key in b"k42\r" {
+      top.handle_key(*key);
+    }
+    assert_eq!(top.prompt.as_ref().unwrap().kind, PromptKind::KILL_SIG(42));
+    for key in b"NOSUCH\r" {
+      top.handle_key(*key);
+    }
+    assert!(top.message.starts_with("Invalid signal"));
+    assert_eq!(top.handle_key(b'q'), Action::QUIT);
+    assert_eq!(top.sort, SortKey::PCPU);
+  }
+
+  #[test]
+  fn render_frame() {
+    let mut top = Top::default();
+    let mut p = proct(42, 1, "busy", 0);
+    p.state = ProcState::RUNNING;
+    p.priority = 20;
+    p.vsize = 2048 * 1024;
+    let mut snap = top.make_snapshot(vec![p, proct(43, 1, "idle", 0)], cpu(25, 75), None, 90061.0);
+    snap.pcpu = vec![12.5, 0.0];
+    snap.clock = String::from("12:34:56");
+    let lines = top.render(&snap, Some(9), 80);
+    assert_eq!(lines.len(), 9);
+    assert_eq!(
+      lines[0],
+      "rtop - 12:34:56 up 1 day,  1:01,  load average: 0.00, 0.00, 0.00"
+    );
+    assert_eq!(
+      lines[1],
+      "Tasks:   2 total,   1 running,   1 sleeping,   0 stopped,   0 zombie"
+    );
+    assert!(lines[2].starts_with("%Cpu(s): 25.0 us,  0.0 sy,  0.0 ni, 75.0 id"));
+    assert!(lines[6].starts_with("\x1b[7m    PID USER"));
+    assert!(
+      lines[7].starts_with("     42 root      20   0    2048      0 R  12.5   0.0   0:00.00 busy")
+    );
+    // only two lines are left for the tasks
+    assert_eq!(top.render(&snap, Some(8), 80).len(), 8);
+
+    top.handle_key(b'k');
+    let lines = top.render(&snap, Some(8), 80);
+    assert_eq!(lines[5], "PID to signal/kill: ");
+  }
+
+  #[test]
+  fn formatting() {
+    assert_eq!(super::format_uptime(59.0 * 60.0), "up 59 min");
+    assert_eq!(
+      super::format_uptime(2.0 * 86400.0 + 3600.0),
+      "up 2 days,  1:00"
+    );
+    assert_eq!(super::scale_kb(123456, 7), "123456");
+    assert_eq!(super::scale_kb(12345678, 7), "12056m");
+    assert_eq!(super::scale_kb(123456789, 6), "117.7g");
+    assert_eq!(super::scale_kb(1234567, 4), "1.2g");
+    let hz = crate::sysinfo::hertz();
+    assert_eq!(super::format_time_plus(hz * 61 + hz / 2), "1:01.50");
+  }
+
+  #[test]
+  fn parse_rtop_options() {
+    let args = |v: &[&str]| -> Vec<String> { v.iter().map(|s| String::from(*s)).collect() };
+    let top = super::parse_args(&args(&["rtop", "-bH", "-d", "0.5", "-n2", "-u", "root"])).unwrap();
+    assert!(top.batch && top.threads);
+    assert_eq!(top.delay.as_secs_f64(), 0.5);
+    assert_eq!((top.iterations, top.user), (Some(2), Some(0)));
+    assert!(super::parse_args(&args(&["rtop", "-d", "0"])).is_err());
+    assert!(super::parse_args(&args(&["rtop", "-n"])).is_err());
+    assert!(super::parse_args(&args(&["rtop", "-x"])).is_err());
+  }
+}
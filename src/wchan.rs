@@ -0,0 +1,115 @@
+use std::sync::OnceLock;
+
+// text symbols of /proc/kallsyms, sorted by address
+static KSYMS: OnceLock<Vec<(u64, String)>> = OnceLock::new();
+
+pub fn parse_kallsyms(s: &str) -> Vec<(u64, String)> {
+  let mut syms = vec![];
+  for line in s.lines() {
+    // address type name [module]
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() < 3 {
+      continue;
+    }
+    if !matches!(fields[1], "t" | "T" | "w" | "W") {
+      continue;
+    }
+    match u64::from_str_radix(fields[0], 16) {
+      // addresses are all zero when hidden by kptr_restrict
+      Ok(0) | Err(_) => continue,
+      Ok(addr) => syms.push((addr, String::from(fields[2]))),
+    }
+  }
+  syms.sort_by_key(|(addr, _)| *addr);
+  syms
+}
+
+fn ksyms() -> &'static Vec<(u64, String)> {
+  KSYMS.get_or_init(|| match std::fs::read_to_string("/proc/kallsyms") {
+    Ok(s) => parse_kallsyms(&s),
+    Err(_) => {
+      log::trace!("failed to read /proc/kallsyms");
+      vec![]
+    }
+  })
+}
+
+// find the symbol containing @addr
+pub fn search_ksyms(syms: &[(u64, String)], addr: u64) -> Option<&str> {
+  match syms.binary_search_by_key(&addr, |(a, _)| *a) {
+    Ok(ix) => Some(syms[ix].1.as_str()),
+    Err(0) => None,
+    Err(ix) => Some(syms[ix - 1].1.as_str()),
+  }
+}
+
+// read the wait channel of the process at @path (like "/proc/1").
+// @addr is the `wchan` field of stat, used when /proc/<pid>/wchan is unavailable.
+// returns an empty string when the process is not waiting.
+pub fn read_wchan(path: &str, addr: u64) -> String {
+  match std::fs::read_to_string(format!("{}/wchan", path)) {
+    Ok(s) => {
+      let s = s.trim();
+      if s == "0" {
+        String::new()
+      } else {
+        String::from(s)
+      }
+    }
+    Err(_) => {
+      if addr == 0 {
+        return String::new();
+      }
+      match search_ksyms(ksyms(), addr) {
+        Some(name) => String::from(name),
+        None => String::new(),
+      }
+    }
+  }
+}
+
+// remove the common prefixes, like "do_sys_poll" -> "sys_poll" or "sys_read" -> "read".
+pub fn strip_wchan(name: &str) -> &str {
+  // ppc64 has a '.' in front of every name
+  let name = name.strip_prefix('.').unwrap_or(name);
+  if let Some(s) = name.strip_prefix("sys_") {
+    s
+  } else if let Some(s) = name.strip_prefix("do_") {
+    s
+  } else {
+    name.trim_start_matches('_')
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #[test]
+  fn strip_prefixes() {
+    assert_eq!(super::strip_wchan("sys_nanosleep"), "nanosleep");
+    assert_eq!(super::strip_wchan("do_wait"), "wait");
+    assert_eq!(super::strip_wchan("__x64_sys_poll"), "x64_sys_poll");
+    assert_eq!(super::strip_wchan(".pipe_read"), "pipe_read");
+    assert_eq!(super::strip_wchan("ep_poll"), "ep_poll");
+  }
+
+  #[test]
+  fn kallsyms_lookup() {
+    let kallsyms = "0000000000000000 A fixed_percpu_data
+ffffffff81000100 T do_wait
+ffffffff81000000 T _stext
+ffffffff81000200 d some_data
+ffffffff81000300 t pipe_read\t[pipe]
+";
+    let syms = super::parse_kallsyms(kallsyms);
+    assert_eq!(syms.len(), 3);
+    assert_eq!(
+      super::search_ksyms(&syms, 0xffffffff81000150),
+      Some("do_wait")
+    );
+    assert_eq!(
+      super::search_ksyms(&syms, 0xffffffff81000300),
+      Some("pipe_read")
+    );
+    assert_eq!(super::search_ksyms(&syms, 0x1000), None);
+  }
+}